@@ -0,0 +1,795 @@
+// End-to-end tests of the router client against a stand-in router on
+// localhost: a bare HTTP/1.1 server that answers each API command with a
+// canned JSON body and remembers what it was sent.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::band_scan::{self, ScanOptions};
+use crate::cli::OutputFormat;
+use crate::client::{build_http_client, RetryPolicy, RouterClient};
+use crate::credentials::{AuthMode, Credentials};
+use crate::dashboard;
+use crate::discovery::{probe_all, Probe};
+use crate::doctor::{self, Check, Outcome};
+use crate::error::ZitelError;
+use crate::neighbors::{ranked_cells, serving_rsrp};
+use crate::port_forward::Protocol;
+use crate::prometheus;
+use crate::prompt;
+use crate::rpc;
+use crate::sha256;
+
+const INDEX_DATA: &str = include_str!("fixtures/index_data.json");
+const NEIGHBOUR_CELL: &str = include_str!("fixtures/neighbour_cell.json");
+
+const TOKEN: &str = "mock-session-token";
+const PASSWORD: &str = "secret";
+
+// ---------- mock router ----------
+struct State {
+    answers: HashMap<String, Value>,
+    // commands in the order they arrived, with their arguments
+    commands: Vec<String>,
+    logins: usize,
+    // newer firmware: only SHA-256(user:password) is accepted
+    hashed_only: bool,
+    // carried out, but the answer never comes
+    silent: Vec<String>,
+}
+
+struct MockRouter {
+    base_url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockRouter {
+    async fn start() -> Self {
+        Self::start_on("127.0.0.1:0").await
+    }
+
+    async fn start_on(address: &str) -> Self {
+        let listener = TcpListener::bind(address).await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State {
+            answers: HashMap::new(),
+            commands: Vec::new(),
+            logins: 0,
+            hashed_only: false,
+            silent: Vec::new(),
+        }));
+        let shared = Arc::clone(&state);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, Arc::clone(&shared)));
+            }
+        });
+        Self { base_url, state }
+    }
+
+    fn answer(&self, command: &str, body: Value) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        state.answers.insert(command.to_string(), body);
+        self
+    }
+
+    fn hashed_only(&self) -> &Self {
+        self.state.lock().unwrap().hashed_only = true;
+        self
+    }
+
+    fn silent(&self, command: &str) -> &Self {
+        self.state.lock().unwrap().silent.push(command.to_string());
+        self
+    }
+
+    fn commands(&self) -> Vec<String> {
+        self.state.lock().unwrap().commands.clone()
+    }
+
+    fn logins(&self) -> usize {
+        self.state.lock().unwrap().logins
+    }
+
+    fn client(&self, password: &str) -> RouterClient {
+        client_for(self.base_url.clone(), password)
+    }
+}
+
+// No retries and no notices, so failures show up at once and quietly.
+fn client_for(base_url: String, password: &str) -> RouterClient {
+    let credentials = Credentials {
+        username: "admin".to_string(),
+        password: password.to_string(),
+    };
+    let http = build_http_client(false).unwrap();
+    let mut client = RouterClient::new(http, base_url, credentials, HeaderMap::new());
+    client.on_notice(|_| {});
+    client.set_retry(RetryPolicy {
+        max_retries: 0,
+        delay: Duration::ZERO,
+    });
+    client.set_login_attempts(1);
+    client
+}
+
+struct Request {
+    path: String,
+    token: Option<String>,
+    body: String,
+}
+
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Request> {
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 4096];
+    let head_end = loop {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        raw.extend_from_slice(&buf[..n]);
+        if let Some(i) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&raw[..head_end]).to_string();
+    let header = |name: &str| {
+        head.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name)
+                .then(|| value.trim().to_string())
+        })
+    };
+    let length: usize = header("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    while raw.len() < head_end + length {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        raw.extend_from_slice(&buf[..n]);
+    }
+    Ok(Request {
+        path: head.split_whitespace().nth(1).unwrap_or("/").to_string(),
+        token: header("leano_auth"),
+        body: String::from_utf8_lossy(&raw[head_end..]).to_string(),
+    })
+}
+
+// One request per connection; `Connection: close` keeps reqwest from
+// reusing it.
+async fn serve(mut stream: TcpStream, state: Arc<Mutex<State>>) {
+    let Ok(request) = read_request(&mut stream).await else {
+        return;
+    };
+    let (status, body) = {
+        let mut state = state.lock().unwrap();
+        if request.path == "/authenticate.leano" {
+            state.logins += 1;
+            let plain = format!("authenticate admin {}", PASSWORD);
+            let hashed = format!(
+                "authenticate admin {}",
+                sha256::hex_digest(format!("admin:{}", PASSWORD).as_bytes())
+            );
+            if request.body == plain && state.hashed_only {
+                (
+                    "200 OK",
+                    json!({ "status": "error", "reason": "password must be hashed" }),
+                )
+            } else if request.body == plain || request.body == hashed {
+                ("200 OK", json!({ "status": "success", "token": TOKEN }))
+            } else {
+                (
+                    "200 OK",
+                    json!({ "status": "error", "reason": "wrong password" }),
+                )
+            }
+        } else if request.path != "/api.leano" {
+            ("404 Not Found", json!({}))
+        } else if request.token.as_deref() != Some(TOKEN) {
+            ("401 Unauthorized", json!({ "status": "unauthorized" }))
+        } else {
+            state.commands.push(request.body.clone());
+            let name = request.body.split_whitespace().next().unwrap_or("");
+            let answer = state.answers.get(name).cloned();
+            (
+                "200 OK",
+                answer.unwrap_or_else(|| json!({ "status": "error", "reason": "unknown command" })),
+            )
+        }
+    };
+    let name = request.body.split_whitespace().next().unwrap_or("");
+    let silent = request.path == "/api.leano"
+        && status == "200 OK"
+        && state.lock().unwrap().silent.iter().any(|c| c == name);
+    if silent {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        return;
+    }
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+fn fixture(text: &str) -> Value {
+    serde_json::from_str(text).unwrap()
+}
+
+// ---------- authentication ----------
+#[tokio::test]
+async fn login_keeps_the_token_for_later_commands() {
+    let router = MockRouter::start().await;
+    router.answer("get_index_data", fixture(INDEX_DATA));
+    let client = router.client(PASSWORD);
+
+    client.authenticate().await.unwrap();
+    let data = client.api_request("get_index_data").await.unwrap();
+    assert_eq!(data["RSRP"], "-95");
+    assert_eq!(router.logins(), 1);
+}
+
+#[tokio::test]
+async fn refused_login_is_an_auth_error_with_the_reason() {
+    let router = MockRouter::start().await;
+    let client = router.client("wrong");
+
+    match client.authenticate().await {
+        Err(ZitelError::Auth(message)) => {
+            assert!(message.contains("wrong password"), "{}", message)
+        }
+        other => panic!("expected an auth error, got {:?}", other.map(|_| ())),
+    }
+    assert!(router.commands().is_empty());
+}
+
+#[tokio::test]
+async fn auto_mode_falls_back_to_a_hashed_password() {
+    let router = MockRouter::start().await;
+    router.hashed_only();
+    let mut client = router.client(PASSWORD);
+    client.set_auth_mode(AuthMode::Auto);
+
+    client.authenticate().await.unwrap();
+    assert_eq!(router.logins(), 2);
+    // the hashed scheme is remembered for the next login
+    client.authenticate().await.unwrap();
+    assert_eq!(router.logins(), 3);
+
+    let mut plain = router.client(PASSWORD);
+    plain.set_auth_mode(AuthMode::Plain);
+    assert!(matches!(
+        plain.authenticate().await,
+        Err(ZitelError::Auth(_))
+    ));
+}
+
+#[tokio::test]
+async fn auto_mode_does_not_try_a_wrong_password_twice() {
+    let router = MockRouter::start().await;
+    let mut client = router.client("wrong");
+    client.set_auth_mode(AuthMode::Auto);
+
+    assert!(matches!(
+        client.authenticate().await,
+        Err(ZitelError::Auth(_))
+    ));
+    assert_eq!(router.logins(), 1);
+}
+
+#[tokio::test]
+async fn login_waits_for_a_router_that_is_still_booting() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    drop(listener);
+    let mut client = client_for(format!("http://{}", address), PASSWORD);
+    client.set_login_attempts(3);
+    let notices = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&notices);
+    client.on_notice(move |n| seen.lock().unwrap().push(n.to_string()));
+
+    // comes up after the first attempt was refused
+    let booting = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let router = MockRouter::start_on(&address).await;
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        drop(router);
+    });
+    client.authenticate().await.unwrap();
+    let notices = notices.lock().unwrap();
+    assert_eq!(notices.len(), 1);
+    assert!(
+        notices[0].contains("waiting for the router"),
+        "{}",
+        notices[0]
+    );
+    booting.abort();
+}
+
+#[tokio::test]
+async fn rejected_session_logs_in_again_and_retries() {
+    let router = MockRouter::start().await;
+    router.answer("get_index_data", fixture(INDEX_DATA));
+    // no authenticate(): the first command goes out without a token
+    let client = router.client(PASSWORD);
+
+    let data = client.api_request("get_index_data").await.unwrap();
+    assert_eq!(data["SYSUP"], "90061");
+    assert_eq!(router.logins(), 1);
+    assert_eq!(router.commands(), ["get_index_data"]);
+}
+
+#[tokio::test]
+async fn a_wrong_api_path_is_reported_as_such() {
+    let router = MockRouter::start().await;
+    router.answer("get_index_data", fixture(INDEX_DATA));
+    let mut client = router.client(PASSWORD);
+    client.set_api_path("/cgi-bin/api".to_string());
+    client.authenticate().await.unwrap();
+
+    match client.api_request("get_index_data").await {
+        Err(e @ ZitelError::EndpointMissing { .. }) => {
+            assert!(e.to_string().contains("--api-path"), "{}", e)
+        }
+        other => panic!("expected a missing endpoint, got {:?}", other.err()),
+    }
+    assert!(router.commands().is_empty());
+}
+
+// ---------- reading ----------
+#[tokio::test]
+async fn dashboard_sections_read_the_fixture() {
+    let router = MockRouter::start().await;
+    router.answer("get_index_data", fixture(INDEX_DATA));
+    let client = router.client(PASSWORD);
+    client.authenticate().await.unwrap();
+
+    let data = client.api_request("get_index_data").await.unwrap();
+    let sections = dashboard::sections(&data);
+    let value = |name: &str| {
+        sections
+            .iter()
+            .flat_map(|s| &s.rows)
+            .find(|row| row.name == name)
+            .map(|row| row.value.clone())
+    };
+    assert_eq!(value("rsrp"), Some(json!("-95")));
+    assert_eq!(value("earfcn"), Some(json!("1850")));
+    assert_eq!(value("model"), Some(json!("ZLT X17")));
+}
+
+#[tokio::test]
+async fn index_data_without_rsrp_is_fetched_once_more() {
+    let router = MockRouter::start().await;
+    router.answer("get_index_data", json!({ "model": "ZLT X17" }));
+    let client = router.client(PASSWORD);
+    client.authenticate().await.unwrap();
+
+    let data = dashboard::fetch_index_data(&client).await.unwrap();
+    assert_eq!(data["model"], "ZLT X17");
+    assert_eq!(router.commands(), ["get_index_data", "get_index_data"]);
+
+    router.answer("get_index_data", fixture(INDEX_DATA));
+    dashboard::fetch_index_data(&client).await.unwrap();
+    assert_eq!(router.commands().len(), 3);
+}
+
+#[tokio::test]
+async fn neighbour_cells_come_ranked_against_the_serving_cell() {
+    let router = MockRouter::start().await;
+    router
+        .answer("get_index_data", fixture(INDEX_DATA))
+        .answer("get_neighbour_cell", fixture(NEIGHBOUR_CELL));
+    let client = router.client(PASSWORD);
+    client.authenticate().await.unwrap();
+
+    let cells = ranked_cells(&client.api_request("get_neighbour_cell").await.unwrap());
+    let serving = serving_rsrp(&client.api_request("get_index_data").await.unwrap());
+    let pcis: Vec<_> = cells.iter().map(|c| c.pcid.as_deref().unwrap()).collect();
+    assert_eq!(pcis, ["246", "301", "17"]);
+    assert_eq!(cells[0].delta_db(serving), Some(3.0));
+}
+
+// ---------- changing ----------
+#[tokio::test]
+async fn dmz_hosts_are_checked_before_sending() {
+    let router = MockRouter::start().await;
+    router.answer("set_dmz", json!({ "status": "success" }));
+    let client = router.client(PASSWORD);
+    client.authenticate().await.unwrap();
+
+    let resp = client.set_dmz(" 192.168.0.20 ", Protocol::Both).await;
+    assert!(resp.is_ok(), "{:?}", resp.err());
+    assert!(matches!(
+        client.set_dmz("192.168.0.300", Protocol::Both).await,
+        Err(ZitelError::Input(_))
+    ));
+    client.set_dmz("192.168.0.20", Protocol::Udp).await.unwrap();
+    assert_eq!(
+        router.commands(),
+        [
+            "set_dmz 1 tcpudp 192.168.0.20",
+            "set_dmz 1 udp 192.168.0.20"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn dry_runs_send_nothing() {
+    let router = MockRouter::start().await;
+    let mut client = router.client(PASSWORD);
+    client.set_dry_run(true);
+    client.authenticate().await.unwrap();
+
+    let resp = client
+        .set_dmz("192.168.0.20", Protocol::Both)
+        .await
+        .unwrap();
+    assert_eq!(resp["dry_run"], true);
+    assert!(router.commands().is_empty());
+}
+
+#[tokio::test]
+async fn cell_locks_are_checked_before_sending() {
+    let router = MockRouter::start().await;
+    router.answer("set_cell_lock", json!({ "status": "success" }));
+    let client = router.client(PASSWORD);
+    client.authenticate().await.unwrap();
+
+    for (earfcn, pci) in [
+        ("1850", 504),
+        ("1850 0; reboot", 17),
+        ("-1", 17),
+        ("262144", 17),
+    ] {
+        assert!(
+            matches!(
+                client.set_cell_lock(earfcn, pci).await,
+                Err(ZitelError::Input(_))
+            ),
+            "{} {}",
+            earfcn,
+            pci
+        );
+    }
+    client.set_cell_lock(" 1850 ", 17).await.unwrap();
+    assert_eq!(router.commands(), ["set_cell_lock 1850 17"]);
+}
+
+#[tokio::test]
+async fn raw_commands_are_sent_as_typed_but_changes_not_in_a_dry_run() {
+    let router = MockRouter::start().await;
+    router.answer("get_wan_info", json!({ "mtu": 1500 }));
+    let mut client = router.client(PASSWORD);
+    client.set_dry_run(true);
+    client.authenticate().await.unwrap();
+
+    let resp = client.raw_command(" get_wan_info ").await.unwrap();
+    assert_eq!(resp["mtu"], 1500);
+    let resp = client.raw_command("set_ttl 65").await.unwrap();
+    assert_eq!(resp["dry_run"], true);
+    assert_eq!(router.commands(), ["get_wan_info"]);
+    assert!(matches!(
+        client.raw_command("  ").await,
+        Err(ZitelError::Input(_))
+    ));
+}
+
+// ---------- metrics server ----------
+#[tokio::test]
+async fn health_answers_while_a_poll_hangs() {
+    let router = MockRouter::start().await;
+    router.silent("get_index_data");
+    let client = router.client(PASSWORD);
+    client.authenticate().await.unwrap();
+    let listen = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let probe = async {
+        loop {
+            match reqwest::get(format!("http://{}/health", listen)).await {
+                Ok(response) => return response.status(),
+                // the server is not listening yet
+                Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+            }
+        }
+    };
+    let serve = prometheus::run(
+        &client,
+        listen,
+        Duration::from_secs(60),
+        Duration::from_secs(180),
+    );
+    tokio::select! {
+        result = serve => panic!("serve-metrics ended: {:?}", result.err()),
+        status = tokio::time::timeout(Duration::from_secs(3), probe) => {
+            assert_eq!(status.expect("/health did not answer").as_u16(), 503);
+        }
+    }
+}
+
+// ---------- unreachable routers ----------
+#[tokio::test]
+async fn a_silent_router_is_a_timeout() {
+    // accepts the connection but never answers
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut open = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            open.push(stream);
+        }
+    });
+    let mut client = client_for(format!("http://{}", address), PASSWORD);
+    client.set_timeout(Duration::from_millis(200));
+
+    match client.authenticate().await {
+        Err(e @ ZitelError::Timeout(_)) => {
+            assert!(e.to_string().contains("not responding"), "{}", e)
+        }
+        other => panic!("expected a timeout, got {:?}", other.err()),
+    }
+}
+
+#[tokio::test]
+async fn only_reads_are_sent_again_after_a_timeout() {
+    let router = MockRouter::start().await;
+    router.silent("reboot").silent("get_index_data");
+    let mut client = router.client(PASSWORD);
+    client.set_retry(RetryPolicy {
+        max_retries: 2,
+        delay: Duration::ZERO,
+    });
+    client.authenticate().await.unwrap();
+    client.set_timeout(Duration::from_millis(200));
+
+    assert!(matches!(client.reboot().await, Err(ZitelError::Timeout(_))));
+    assert_eq!(router.commands(), ["reboot"]);
+    assert!(client.api_request("get_index_data").await.is_err());
+    assert_eq!(router.commands()[1..], ["get_index_data"; 3]);
+}
+
+#[tokio::test]
+async fn a_closed_port_is_a_refused_connection() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+    let client = client_for(format!("http://{}", address), PASSWORD);
+
+    match client.authenticate().await {
+        Err(ZitelError::ConnectionRefused(refused)) => assert_eq!(refused, address.to_string()),
+        other => panic!("expected a refused connection, got {:?}", other.err()),
+    }
+}
+
+#[tokio::test]
+async fn an_unknown_host_name_is_a_dns_failure() {
+    // .invalid never resolves (RFC 6761)
+    let client = client_for("http://router.invalid".to_string(), PASSWORD);
+
+    match client.authenticate().await {
+        Err(ZitelError::DnsFailure(host)) => assert_eq!(host, "router.invalid"),
+        other => panic!("expected a DNS failure, got {:?}", other.err()),
+    }
+}
+
+// ---------- replay ----------
+#[tokio::test]
+async fn replays_answer_from_saved_responses_without_a_router() {
+    let dir = std::env::temp_dir().join(format!("zitel-replay-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("capture.json"), INDEX_DATA).unwrap();
+    std::fs::write(dir.join("get_neighbour_cell.json"), NEIGHBOUR_CELL).unwrap();
+    // nothing listens on the discard port
+    let mut client = client_for("http://127.0.0.1:9".to_string(), PASSWORD);
+    client.set_replay(crate::replay::load(&dir).unwrap());
+
+    let data = client.api_request("get_index_data").await.unwrap();
+    assert_eq!(data["RSRP"], "-95");
+    let cells = ranked_cells(&client.api_request("get_neighbour_cell").await.unwrap());
+    assert_eq!(cells.len(), 3);
+    assert!(matches!(
+        client.set_dmz("192.168.0.20", Protocol::Both).await,
+        Err(ZitelError::Input(_))
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// ---------- discovery ----------
+#[tokio::test]
+async fn discovery_tells_the_router_from_other_servers() {
+    let router = MockRouter::start().await;
+    let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let closed_url = format!("http://{}", closed.local_addr().unwrap());
+    drop(closed);
+    let candidates = vec![
+        (closed_url, true),
+        // the mock's web server answers 404 off the Leano paths
+        (format!("{}/elsewhere", router.base_url), false),
+        (router.base_url.clone(), false),
+    ];
+
+    let http = build_http_client(false).unwrap();
+    let found = probe_all(&http, candidates).await;
+    assert!(matches!(found[0].probe, Probe::NoAnswer(_)));
+    assert!(matches!(found[1].probe, Probe::OtherServer(404)));
+    assert!(matches!(found[2].probe, Probe::Zitel));
+    assert!(found[0].gateway && !found[2].gateway);
+    assert_eq!(router.logins(), 1);
+}
+
+// ---------- JSON-RPC ----------
+#[tokio::test]
+async fn rpc_requests_share_one_session() {
+    let router = MockRouter::start().await;
+    router.answer("get_index_data", fixture(INDEX_DATA));
+    router.answer("set_dmz", json!({ "status": "success" }));
+    let client = router.client(PASSWORD);
+    client.authenticate().await.unwrap();
+
+    let call = |line: &'static str| rpc::handle_line(&client, line);
+    let response = call(r#"{"jsonrpc":"2.0","id":1,"method":"dashboard"}"#)
+        .await
+        .unwrap();
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["RSRP"], "-95");
+    let response =
+        call(r#"{"jsonrpc":"2.0","id":"a","method":"set_dmz","params":{"ip":"192.168.0.50"}}"#)
+            .await
+            .unwrap();
+    assert_eq!(response["result"]["status"], "success");
+    assert_eq!(router.commands()[1], "set_dmz 1 tcpudp 192.168.0.50");
+    assert_eq!(router.logins(), 1);
+
+    let error = |response: Option<Value>| response.unwrap()["error"]["code"].clone();
+    assert_eq!(error(call(r#"{"id":2,"method":"reboot"}"#).await), -32601);
+    assert_eq!(error(call(r#"{"id":3,"method":"bandlock"}"#).await), -32602);
+    let bad_ip = r#"{"id":4,"method":"set_dmz","params":{"ip":"router"}}"#;
+    assert_eq!(error(call(bad_ip).await), -32602);
+    assert_eq!(error(call("not json").await), -32700);
+    // a notification is carried out but not answered
+    assert!(call(r#"{"method":"dashboard"}"#).await.is_none());
+    assert_eq!(router.commands().len(), 3);
+}
+
+// ---------- doctor ----------
+#[tokio::test]
+async fn doctor_stops_at_the_first_failed_step() {
+    let router = MockRouter::start().await;
+    router.answer("get_index_data", serde_json::from_str(INDEX_DATA).unwrap());
+    let outcomes = |checks: Vec<Check>| -> Vec<(&'static str, Outcome)> {
+        checks.iter().map(|c| (c.name, c.outcome)).collect()
+    };
+
+    let checks = doctor::run_checks(&router.client(PASSWORD)).await;
+    assert_eq!(
+        outcomes(checks),
+        [
+            ("Name lookup", Outcome::Pass),
+            ("Web server", Outcome::Pass),
+            ("Login", Outcome::Pass),
+            ("API", Outcome::Pass),
+            // the mock sends no Date header
+            ("Clock", Outcome::Skip),
+        ]
+    );
+
+    let checks = doctor::run_checks(&router.client("wrong")).await;
+    assert!(checks[2].hint.is_some());
+    assert_eq!(
+        outcomes(checks)[2..4],
+        [("Login", Outcome::Fail), ("API", Outcome::Skip)]
+    );
+
+    let checks = doctor::run_checks(&client_for("http://127.0.0.1:9".to_string(), PASSWORD)).await;
+    assert_eq!(
+        outcomes(checks)[1..3],
+        [("Web server", Outcome::Fail), ("Login", Outcome::Skip)]
+    );
+}
+
+// ---------- band scan ----------
+fn scan_of(earfcns: &[u32]) -> ScanOptions {
+    ScanOptions {
+        earfcns: earfcns.to_vec(),
+        settle: Duration::ZERO,
+        restore: None,
+    }
+}
+
+#[tokio::test]
+async fn a_failed_reading_skips_to_the_next_earfcn() {
+    let router = MockRouter::start().await;
+    router
+        .answer("set_band_lock", json!({ "status": "success" }))
+        .answer("clear_band_lock", json!({ "status": "success" }))
+        .silent("get_index_data");
+    let mut client = router.client(PASSWORD);
+    client.set_timeout(Duration::from_millis(200));
+    client.authenticate().await.unwrap();
+    prompt::set_assume_yes(true);
+
+    band_scan::run(&client, scan_of(&[1850, 6300]), OutputFormat::Json)
+        .await
+        .unwrap();
+    assert_eq!(
+        router.commands(),
+        [
+            "set_band_lock 1850",
+            "get_index_data",
+            "set_band_lock 6300",
+            "get_index_data",
+            "clear_band_lock"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn a_failed_restore_is_reported_with_the_failed_scan() {
+    let router = MockRouter::start().await;
+    router
+        .answer(
+            "clear_band_lock",
+            json!({ "status": "error", "reason": "busy" }),
+        )
+        .silent("set_band_lock");
+    let mut client = router.client(PASSWORD);
+    client.set_timeout(Duration::from_millis(200));
+    client.authenticate().await.unwrap();
+    prompt::set_assume_yes(true);
+
+    let e = band_scan::run(&client, scan_of(&[1850]), OutputFormat::Json)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(e.contains("not responding"), "{}", e);
+    assert!(e.contains("Clearing the band lock failed"), "{}", e);
+}
+
+// ---------- snapshots ----------
+#[tokio::test]
+async fn a_snapshot_asks_for_everything_at_once() {
+    let router = MockRouter::start().await;
+    router
+        .answer("get_index_data", serde_json::from_str(INDEX_DATA).unwrap())
+        .answer(
+            "get_neighbour_cell",
+            serde_json::from_str(NEIGHBOUR_CELL).unwrap(),
+        )
+        .answer(
+            "get_dhcp_list",
+            json!({ "dhcp_list": [{ "ip": "192.168.0.20" }] }),
+        );
+    let client = router.client(PASSWORD);
+    client.authenticate().await.unwrap();
+
+    let snapshot = dashboard::fetch_snapshot(&client).await;
+    assert_eq!(snapshot.index_data.unwrap()["RSRP"], "-95");
+    assert_eq!(ranked_cells(&snapshot.neighbour_cells.unwrap()).len(), 3);
+    assert!(snapshot.devices.is_ok());
+    let mut commands = router.commands();
+    commands.sort();
+    assert_eq!(
+        commands,
+        ["get_dhcp_list", "get_index_data", "get_neighbour_cell"]
+    );
+
+    // each part fails on its own
+    let client = client_for("http://127.0.0.1:9".to_string(), PASSWORD);
+    let snapshot = dashboard::fetch_snapshot(&client).await;
+    assert!(snapshot.index_data.is_err() && snapshot.neighbour_cells.is_err());
+    assert!(snapshot.devices.is_err());
+}
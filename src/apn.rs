@@ -0,0 +1,201 @@
+use std::error::Error;
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::{is_success, RouterClient};
+use crate::dashboard::find_field;
+use crate::error::ZitelError;
+use crate::fields::field;
+use crate::prompt::{ask_secret, confirm_change};
+
+// ---------- access point name ----------
+// The carrier gateway the modem dials. A wrong one leaves the router with
+// signal but no internet, so a change is always read back.
+pub const MAX_APN_LEN: usize = 63;
+
+/// How the modem authenticates to the APN.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ApnAuth {
+    /// No user name or password, what most carriers use
+    None,
+    Pap,
+    Chap,
+}
+
+impl ApnAuth {
+    fn command_name(self) -> &'static str {
+        match self {
+            ApnAuth::None => "none",
+            ApnAuth::Pap => "pap",
+            ApnAuth::Chap => "chap",
+        }
+    }
+}
+
+/// Dot-separated labels of letters, digits and hyphens, at most 63
+/// characters, e.g. `fast.t-mobile.com`.
+pub fn validate_apn(apn: &str) -> Result<String, String> {
+    let apn = apn.trim();
+    if apn.is_empty() {
+        return Err("APN must not be empty".to_string());
+    }
+    if apn.len() > MAX_APN_LEN {
+        return Err(format!(
+            "APN must be at most {} characters, got {}",
+            MAX_APN_LEN,
+            apn.len()
+        ));
+    }
+    let label_ok = |label: &str| {
+        !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    if !apn.split('.').all(label_ok) {
+        return Err(format!(
+            "APN {:?} may only contain letters, digits, hyphens and single dots between them",
+            apn
+        ));
+    }
+    Ok(apn.to_string())
+}
+
+/// The set_apn command; the password goes last, where the log redaction
+/// looks for it.
+pub fn set_command(apn: &str, auth: ApnAuth, login: Option<(&str, &str)>) -> String {
+    match login {
+        Some((username, password)) if auth != ApnAuth::None => format!(
+            "set_apn {} {} {} {}",
+            apn,
+            auth.command_name(),
+            username,
+            password
+        ),
+        _ => format!("set_apn {} {}", apn, auth.command_name()),
+    }
+}
+
+/// The APN the router reports in `get_index_data`.
+pub fn current_apn(data: &Value) -> Option<String> {
+    find_field("apn").and_then(|f| field(data, f.spec).map(String::from))
+}
+
+pub async fn run_show(client: &RouterClient, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let data = client.api_request("get_index_data").await?;
+    let apn = current_apn(&data);
+    match format {
+        OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&data)?),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&json!({ "apn": apn }))?),
+        OutputFormat::Text => println!("APN: {}", apn.as_deref().unwrap_or("not reported")),
+    }
+    Ok(())
+}
+
+pub struct ApnChange {
+    pub apn: String,
+    pub auth: ApnAuth,
+    pub username: Option<String>,
+    /// Asked for when PAP or CHAP is chosen without one.
+    pub password: Option<String>,
+}
+
+pub async fn run_set(
+    client: &RouterClient,
+    change: ApnChange,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let apn = validate_apn(&change.apn).map_err(ZitelError::Input)?;
+    let login = match (change.auth, change.username) {
+        (ApnAuth::None, _) => None,
+        (_, None) => {
+            return Err(ZitelError::Input(format!(
+                "{} authentication needs --apn-username",
+                change.auth.command_name().to_uppercase()
+            ))
+            .into())
+        }
+        (_, Some(username)) => {
+            let password = match change.password {
+                Some(password) => password,
+                None => ask_secret("APN password")?,
+            };
+            if username.contains(char::is_whitespace) || password.contains(char::is_whitespace) {
+                return Err(ZitelError::Input(
+                    "APN user name and password cannot contain spaces".to_string(),
+                )
+                .into());
+            }
+            Some((username, password))
+        }
+    };
+
+    confirm_change(
+        client,
+        &format!(
+            "Change the APN to {}? The data connection drops until the modem reconnects",
+            apn
+        ),
+    )?;
+    let login_ref = login.as_ref().map(|(u, p)| (u.as_str(), p.as_str()));
+    let resp = client.set_apn(&apn, change.auth, login_ref).await?;
+    if !is_success(&resp) {
+        return Err(ZitelError::refused("Changing the APN failed", &resp).into());
+    }
+
+    // a dry run changed nothing, there is nothing to compare
+    let reported = match client.dry_run() {
+        true => None,
+        false => current_apn(&client.api_request("get_index_data").await?),
+    };
+    if let Some(reported) = &reported {
+        if !reported.eq_ignore_ascii_case(&apn) {
+            return Err(ZitelError::RouterStatus(format!(
+                "Router accepted APN {} but still reports {}",
+                apn, reported
+            ))
+            .into());
+        }
+    }
+    let summary = client.outcome(&format!("APN set to {}", apn));
+
+    match format {
+        OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&resp)?),
+        OutputFormat::Json => {
+            let result = json!({
+                "success": true,
+                "dry_run": client.dry_run(),
+                "apn": apn,
+                "auth": change.auth.command_name(),
+                "confirmed": reported.is_some(),
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text => println!("{}", summary),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apns_are_checked_before_sending() {
+        assert_eq!(
+            validate_apn(" fast.t-mobile.com "),
+            Ok("fast.t-mobile.com".to_string())
+        );
+        assert!(validate_apn("").is_err());
+        assert!(validate_apn("two words").is_err());
+        assert!(validate_apn("internet..net").is_err());
+        assert!(validate_apn(&"a".repeat(64)).is_err());
+        assert_eq!(
+            set_command("vzwinternet", ApnAuth::Chap, Some(("me", "pw"))),
+            "set_apn vzwinternet chap me pw"
+        );
+        assert_eq!(
+            set_command("internet", ApnAuth::None, Some(("me", "pw"))),
+            "set_apn internet none"
+        );
+    }
+}
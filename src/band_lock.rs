@@ -0,0 +1,331 @@
+use std::error::Error;
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::{failure_reason, is_success, lock_cleared, RouterClient};
+use crate::dashboard::{find_field, seconds};
+use crate::earfcn::{band_for_earfcn, describe, parse_earfcn};
+use crate::error::ZitelError;
+use crate::fields::field;
+use crate::history;
+use crate::neighbors::{pick_earfcn, ranked_cells, serving_rsrp};
+use crate::prompt::confirm_change;
+use crate::timestamp;
+
+/// What to tell the user about a `set_band_lock` answer; failures get a hint
+/// because the router's own reasons rarely mention the band/SIM mismatch.
+pub fn lock_message(earfcn: &str, resp: &Value) -> String {
+    if is_success(resp) {
+        return match parse_earfcn(earfcn) {
+            Ok(earfcn) => format!("Band lock set to {}", describe(earfcn)),
+            Err(_) => format!("Band lock set to {}", earfcn),
+        };
+    }
+    let reason = match failure_reason(resp) {
+        Some(reason) => format!("Band lock failed: {}", reason),
+        None => format!(
+            "Band lock failed: router answered with status {}",
+            resp["status"].as_str().unwrap_or("(none)")
+        ),
+    };
+    format!(
+        "{}\nHint: EARFCN {} may not be supported by the current band or SIM",
+        reason, earfcn
+    )
+}
+
+pub async fn run(
+    client: &RouterClient,
+    earfcn: u32,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    confirm_change(client, &format!("Lock the radio to {}?", describe(earfcn)))?;
+    eprintln!("Locking to {}", describe(earfcn));
+    let resp = client.set_band_lock(&earfcn.to_string()).await?;
+    let done = is_success(&resp);
+    let mut message = lock_message(&earfcn.to_string(), &resp);
+    if done {
+        message = client.outcome(&message);
+    }
+
+    match format {
+        OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&resp)?),
+        OutputFormat::Json => {
+            let normalized = json!({
+                "success": done,
+                "earfcn": earfcn,
+                "band": band_for_earfcn(earfcn),
+                "dry_run": client.dry_run(),
+                "message": message,
+            });
+            println!("{}", serde_json::to_string_pretty(&normalized)?);
+        }
+        OutputFormat::Text if done => println!("{}", message),
+        OutputFormat::Text => {}
+    }
+
+    if done {
+        Ok(())
+    } else {
+        Err(ZitelError::RouterStatus(message).into())
+    }
+}
+
+/// Locks to the EARFCN of the neighbour cell ranked `rank`, as the TUI's
+/// neighbour page and the watch table number them.
+pub async fn run_from_neighbor(
+    client: &RouterClient,
+    rank: usize,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let cells = ranked_cells(&client.api_request("get_neighbour_cell").await?);
+    let earfcn = pick_earfcn(&cells, rank).map_err(ZitelError::Input)?;
+    let serving = serving_rsrp(&client.api_request("get_index_data").await?);
+    eprintln!(
+        "Neighbour cell #{}: {}",
+        rank,
+        cells[rank - 1].summary(serving)
+    );
+    run(client, earfcn, format).await
+}
+
+// ---------- unlock ----------
+/// Band and EARFCN the radio reports in `get_index_data`.
+pub struct RadioBand {
+    pub band: Option<String>,
+    pub earfcn: Option<String>,
+}
+
+impl RadioBand {
+    fn read(data: &Value) -> Self {
+        let value = |name| find_field(name).and_then(|f| field(data, f.spec).map(String::from));
+        Self {
+            band: value("band"),
+            earfcn: value("earfcn"),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match (&self.band, &self.earfcn) {
+            (Some(band), Some(earfcn)) => format!("band {} (EARFCN {})", band, earfcn),
+            (Some(band), None) => format!("band {}", band),
+            (None, Some(earfcn)) => format!("EARFCN {}", earfcn),
+            (None, None) => "unknown".to_string(),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({ "band": self.band, "earfcn": self.earfcn })
+    }
+}
+
+/// Clears the band lock and reads the band back, returning what the radio
+/// was on before and what it picked afterwards.
+pub async fn unlock(client: &RouterClient) -> Result<(RadioBand, RadioBand), Box<dyn Error>> {
+    let before = RadioBand::read(&client.api_request("get_index_data").await?);
+    let resp = client.clear_band_lock().await?;
+    if !lock_cleared(&resp) {
+        return Err(ZitelError::refused("Band unlock failed", &resp).into());
+    }
+    let after = RadioBand::read(&client.api_request("get_index_data").await?);
+    Ok((before, after))
+}
+
+pub fn unlock_message(before: &RadioBand, after: &RadioBand) -> String {
+    format!(
+        "Band lock cleared, automatic selection: was {}, now {}",
+        before.describe(),
+        after.describe()
+    )
+}
+
+pub async fn run_unlock(client: &RouterClient, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    confirm_change(
+        client,
+        "Clear the band lock and return to automatic band selection?",
+    )?;
+
+    let (before, after) = unlock(client).await?;
+    match format {
+        OutputFormat::Json | OutputFormat::Raw => {
+            let result = json!({
+                "success": true,
+                "dry_run": client.dry_run(),
+                "before": before.to_json(),
+                "after": after.to_json(),
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text => println!("{}", client.outcome(&unlock_message(&before, &after))),
+    }
+    Ok(())
+}
+
+// ---------- lock status ----------
+// No command the router answers (see `zitel capabilities`) reports its band
+// lock, so what is shown is the last lock change zitel sent it, from the
+// change history, and worded as that rather than as the lock in effect. A
+// lock set from the router's web page or another computer is not seen, and
+// nothing is known while the history is off. The firmware forgets the lock
+// when it restarts: a reboot or factory reset in the history ends it, and so
+// does an uptime shorter than the time since it was set, which also catches
+// restarts zitel did not send.
+pub const STATUS_LABEL: &str = "Band lock last set by zitel";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LockStatus {
+    Locked {
+        earfcn: u32,
+        since: String,
+    },
+    /// Cleared, or dropped by a restart, as `how` says.
+    Cleared {
+        since: String,
+        how: &'static str,
+    },
+    Unknown,
+}
+
+impl LockStatus {
+    pub fn from_history(entries: &[history::Entry], router: &str) -> Self {
+        for entry in entries.iter().rev() {
+            if entry.router != router || !entry.success {
+                continue;
+            }
+            let since = entry.time.clone();
+            let how = match entry.action.as_str() {
+                "set_band_lock" => match parse_earfcn(&entry.params) {
+                    Ok(earfcn) => return LockStatus::Locked { earfcn, since },
+                    Err(_) => continue,
+                },
+                "clear_band_lock" => "cleared",
+                "reboot" => "dropped by a reboot",
+                "factory_reset" => "dropped by a factory reset",
+                _ => continue,
+            };
+            return LockStatus::Cleared { since, how };
+        }
+        LockStatus::Unknown
+    }
+
+    /// What the client's history says about the router it talks to.
+    pub fn read(client: &RouterClient) -> Self {
+        let Some(path) = client.history() else {
+            return LockStatus::Unknown;
+        };
+        match history::read(path) {
+            Ok(entries) => Self::from_history(&entries, client.base_url()),
+            Err(e) => {
+                log::warn!("Cannot read {}: {}", path.display(), e);
+                LockStatus::Unknown
+            }
+        }
+    }
+
+    /// The status once the router's uptime is known: a lock set before the
+    /// router last started is gone.
+    pub fn after_uptime(&self, uptime_secs: Option<u64>, now: DateTime<Utc>) -> Self {
+        let (LockStatus::Locked { since, .. }, Some(uptime)) = (self, uptime_secs) else {
+            return self.clone();
+        };
+        let Ok(set) = DateTime::parse_from_rfc3339(since) else {
+            return self.clone();
+        };
+        let started = now - chrono::Duration::seconds(i64::try_from(uptime).unwrap_or(i64::MAX));
+        if set >= started {
+            return self.clone();
+        }
+        LockStatus::Cleared {
+            since: timestamp::iso(started),
+            how: "dropped when the router restarted",
+        }
+    }
+
+    /// `after_uptime` with the uptime in a `get_index_data` answer.
+    pub fn checked(&self, data: &Value) -> Self {
+        let uptime = find_field("uptime_s").and_then(|f| seconds(data, f.spec));
+        self.after_uptime(uptime, Utc::now())
+    }
+
+    /// One line for the dashboard.
+    pub fn describe(&self) -> String {
+        match self {
+            LockStatus::Locked { earfcn, .. } => describe(*earfcn),
+            LockStatus::Cleared { how, .. } => how.to_string(),
+            LockStatus::Unknown => "unknown".to_string(),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            LockStatus::Locked { earfcn, since } => json!({
+                "locked": true,
+                "earfcn": earfcn,
+                "band": band_for_earfcn(*earfcn),
+                "since": since,
+            }),
+            LockStatus::Cleared { since, how } => json!({
+                "locked": false,
+                "since": since,
+                "cleared": how,
+            }),
+            LockStatus::Unknown => Value::Null,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(router: &str, command: &str, success: bool) -> history::Entry {
+        let status = if success { "success" } else { "fail" };
+        history::Entry::new(router, command, &Ok(json!({ "status": status })))
+    }
+
+    #[test]
+    fn the_last_accepted_lock_change_counts() {
+        let router = "http://192.168.0.1";
+        let mut entries = vec![
+            entry(router, "set_band_lock 1850", true),
+            entry(router, "set_band_lock 9999999", true),
+            entry(router, "set_band_lock 6300", false),
+            entry("http://10.0.0.1", "clear_band_lock", true),
+            entry(router, "reboot", false),
+        ];
+        let status = LockStatus::from_history(&entries, router);
+        assert!(matches!(status, LockStatus::Locked { earfcn: 1850, .. }));
+        assert_eq!(status.describe(), "EARFCN 1850 → Band 3");
+
+        entries.push(entry(router, "reboot", true));
+        let status = LockStatus::from_history(&entries, router);
+        assert_eq!(status.describe(), "dropped by a reboot");
+        entries.push(entry(router, "set_band_lock 1850", true));
+        entries.push(entry(router, "clear_band_lock", true));
+        let status = LockStatus::from_history(&entries, router);
+        assert_eq!(status.describe(), "cleared");
+        assert_eq!(status.to_json()["locked"], false);
+        assert_eq!(
+            LockStatus::from_history(&entries[..1], "http://x"),
+            LockStatus::Unknown
+        );
+    }
+
+    #[test]
+    fn a_restart_since_the_lock_ends_it() {
+        let now = DateTime::parse_from_rfc3339("2026-10-14T12:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        let lock = LockStatus::Locked {
+            earfcn: 1850,
+            since: "2026-10-14T11:00:00+00:00".to_string(),
+        };
+        assert_eq!(lock.after_uptime(Some(7200), now), lock);
+        assert_eq!(lock.after_uptime(None, now), lock);
+        let after = lock.after_uptime(Some(60), now);
+        assert_eq!(after.describe(), "dropped when the router restarted");
+        assert_eq!(after.to_json()["locked"], false);
+    }
+}
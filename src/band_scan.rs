@@ -0,0 +1,340 @@
+use std::error::Error;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::band_lock::lock_message;
+use crate::cli::OutputFormat;
+use crate::client::{is_success, lock_cleared, RouterClient};
+use crate::dashboard::{find_field, render_columns};
+use crate::earfcn::{band_for_earfcn, describe};
+use crate::error::ZitelError;
+use crate::fields::field;
+use crate::neighbors::ranked_cells;
+use crate::prompt::confirm_change;
+use crate::signal::SignalMetrics;
+use crate::terminal;
+
+// ---------- band scan ----------
+// Locks the radio to one EARFCN after the other and samples the signal on
+// each, for comparing bands while aiming an antenna. The router does not
+// report an existing lock, so the scan ends on automatic band selection
+// unless `restore` names the EARFCN to lock back to.
+pub const DEFAULT_SETTLE_SECS: u64 = 20;
+const SAMPLES: usize = 3;
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct ScanOptions {
+    /// Scanned in this order; empty means the serving and neighbour cells'.
+    pub earfcns: Vec<u32>,
+    /// How long the radio gets to attach after each lock.
+    pub settle: Duration,
+    pub restore: Option<u32>,
+}
+
+pub struct BandSample {
+    pub earfcn: u32,
+    /// Averages over the samples that had a value.
+    pub rsrp: Option<f64>,
+    pub sinr: Option<f64>,
+    /// What the radio reported while sampling, when not the locked EARFCN.
+    pub stayed_on: Option<String>,
+    /// Why samples are missing, e.g. the router refused the lock.
+    pub error: Option<String>,
+}
+
+impl BandSample {
+    fn to_json(&self) -> Value {
+        json!({
+            "earfcn": self.earfcn,
+            "band": band_for_earfcn(self.earfcn),
+            "rsrp": self.rsrp,
+            "sinr": self.sinr,
+            "stayed_on": self.stayed_on,
+            "error": self.error,
+        })
+    }
+}
+
+fn reported_earfcn(data: &Value) -> Option<String> {
+    find_field("earfcn").and_then(|f| field(data, f.spec).map(String::from))
+}
+
+fn average(values: impl Iterator<Item = Option<f64>>) -> Option<f64> {
+    let values: Vec<f64> = values.flatten().collect();
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Strongest RSRP first, SINR breaking ties; EARFCNs without a reading go
+/// last, in scan order.
+pub fn rank(samples: &mut [BandSample]) {
+    let key = |s: &BandSample| {
+        (
+            s.rsrp.is_none(),
+            -s.rsrp.unwrap_or(0.0),
+            -s.sinr.unwrap_or(0.0),
+        )
+    };
+    samples.sort_by(|a, b| {
+        key(a)
+            .partial_cmp(&key(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// The EARFCN being served and those of the neighbour cells, each once.
+async fn visible_earfcns(client: &RouterClient) -> Result<Vec<u32>, Box<dyn Error>> {
+    let serving = reported_earfcn(&client.api_request("get_index_data").await?);
+    let cells = ranked_cells(&client.api_request("get_neighbour_cell").await?);
+    let mut earfcns: Vec<u32> = Vec::new();
+    let found = serving
+        .into_iter()
+        .chain(cells.into_iter().filter_map(|c| c.earfcn));
+    for earfcn in found.filter_map(|e| e.trim().parse().ok()) {
+        if !earfcns.contains(&earfcn) {
+            earfcns.push(earfcn);
+        }
+    }
+    Ok(earfcns)
+}
+
+async fn sample(
+    client: &RouterClient,
+    earfcn: u32,
+    settle: Duration,
+) -> Result<BandSample, Box<dyn Error>> {
+    let resp = client.set_band_lock(&earfcn.to_string()).await?;
+    if !is_success(&resp) {
+        return Ok(BandSample {
+            earfcn,
+            rsrp: None,
+            sinr: None,
+            stayed_on: None,
+            error: Some(lock_message(&earfcn.to_string(), &resp)),
+        });
+    }
+    tokio::time::sleep(settle).await;
+
+    // a failed reading costs this EARFCN its remaining samples, not the scan
+    let mut readings = Vec::with_capacity(SAMPLES);
+    let mut error = None;
+    for i in 0..SAMPLES {
+        if i > 0 {
+            tokio::time::sleep(SAMPLE_INTERVAL).await;
+        }
+        match client.api_request("get_index_data").await {
+            Ok(data) => readings.push(data),
+            Err(e) => {
+                error = Some(format!(
+                    "Reading the signal failed after {} of {} samples: {}",
+                    i, SAMPLES, e
+                ));
+                break;
+            }
+        }
+    }
+    let metrics: Vec<SignalMetrics> = readings.iter().map(SignalMetrics::from_data).collect();
+    let stayed_on = readings
+        .iter()
+        .filter_map(reported_earfcn)
+        .find(|reported| reported.trim() != earfcn.to_string());
+    Ok(BandSample {
+        earfcn,
+        rsrp: average(metrics.iter().map(|m| m.get("rsrp"))),
+        sinr: average(metrics.iter().map(|m| m.get("sinr"))),
+        stayed_on,
+        error,
+    })
+}
+
+async fn scan(
+    client: &RouterClient,
+    options: &ScanOptions,
+    samples: &mut Vec<BandSample>,
+) -> Result<(), Box<dyn Error>> {
+    for (i, &earfcn) in options.earfcns.iter().enumerate() {
+        eprintln!(
+            "[{}/{}] {}, settling for {}s",
+            i + 1,
+            options.earfcns.len(),
+            describe(earfcn),
+            options.settle.as_secs()
+        );
+        samples.push(sample(client, earfcn, options.settle).await?);
+    }
+    Ok(())
+}
+
+async fn restore(client: &RouterClient, earfcn: Option<u32>) -> Result<String, Box<dyn Error>> {
+    match earfcn {
+        Some(earfcn) => {
+            let resp = client.set_band_lock(&earfcn.to_string()).await?;
+            if !is_success(&resp) {
+                return Err(ZitelError::refused("Restoring the band lock failed", &resp).into());
+            }
+            Ok(format!("Band lock restored to {}", describe(earfcn)))
+        }
+        None => {
+            let resp = client.clear_band_lock().await?;
+            if !lock_cleared(&resp) {
+                return Err(ZitelError::refused("Clearing the band lock failed", &resp).into());
+            }
+            Ok("Band lock cleared, automatic selection".to_string())
+        }
+    }
+}
+
+fn table_row(position: usize, sample: &BandSample) -> Vec<String> {
+    let number = |value: Option<f64>| value.map_or("-".to_string(), |v| format!("{:.1}", v));
+    let band = band_for_earfcn(sample.earfcn).map_or("?".to_string(), |b| b.to_string());
+    let note = match (&sample.error, &sample.stayed_on) {
+        (Some(_), _) => "failed, see below".to_string(),
+        (None, Some(reported)) => format!("radio stayed on {}", reported),
+        (None, None) => String::new(),
+    };
+    vec![
+        position.to_string(),
+        sample.earfcn.to_string(),
+        band,
+        number(sample.rsrp),
+        number(sample.sinr),
+        note,
+    ]
+}
+
+pub async fn run(
+    client: &RouterClient,
+    mut options: ScanOptions,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    if options.earfcns.is_empty() {
+        options.earfcns = visible_earfcns(client).await?;
+    }
+    if options.earfcns.is_empty() {
+        return Err(ZitelError::Input(
+            "No EARFCNs to scan: the router reports no cells, pass them or set \
+             band_scan_earfcns in the config"
+                .to_string(),
+        )
+        .into());
+    }
+    let end_state = match options.restore {
+        Some(earfcn) => format!("locked to {}", describe(earfcn)),
+        None => "on automatic band selection".to_string(),
+    };
+    let question = format!(
+        "Scan {} EARFCNs ({}) taking about {}s? The connection drops at every \
+         switch, the radio is left {}",
+        options.earfcns.len(),
+        options
+            .earfcns
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+        options.earfcns.len() as u64
+            * (options.settle.as_secs() + (SAMPLES as u64 - 1) * SAMPLE_INTERVAL.as_secs()),
+        end_state
+    );
+    confirm_change(client, &question)?;
+    if client.dry_run() {
+        println!("{}", client.outcome("Band scan skipped"));
+        return Ok(());
+    }
+
+    // Ctrl-C stops the scan, not the process: the radio must not stay
+    // locked to whatever band was being sampled
+    let mut samples = Vec::new();
+    let outcome = tokio::select! {
+        result = scan(client, &options, &mut samples) => result.map(|()| false),
+        _ = tokio::signal::ctrl_c() => Ok(true),
+    };
+    let restored = restore(client, options.restore).await;
+    let (interrupted, restored) = match (outcome, restored) {
+        (Ok(interrupted), Ok(restored)) => (interrupted, restored),
+        (Ok(_), Err(e)) => return Err(e),
+        // a radio left locked matters more than the scan, so neither error
+        // may hide the other
+        (Err(e), Err(restore_error)) => return Err(format!("{}\n{}", e, restore_error).into()),
+        (Err(e), Ok(restored)) => {
+            eprintln!("{}", restored);
+            return Err(e);
+        }
+    };
+    if interrupted {
+        eprintln!(
+            "Interrupted after {} of {} EARFCNs",
+            samples.len(),
+            options.earfcns.len()
+        );
+    }
+    rank(&mut samples);
+
+    match format {
+        OutputFormat::Json | OutputFormat::Raw => {
+            let result = json!({
+                "interrupted": interrupted,
+                "settle_secs": options.settle.as_secs(),
+                "results": samples.iter().map(BandSample::to_json).collect::<Vec<_>>(),
+                "restored": restored,
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text => {
+            let rows: Vec<Vec<String>> = samples
+                .iter()
+                .enumerate()
+                .map(|(i, sample)| table_row(i + 1, sample))
+                .collect();
+            print!(
+                "{}",
+                render_columns(
+                    "Band scan",
+                    &["#", "EARFCN", "Band", "RSRP", "SINR", "Note"],
+                    &rows,
+                    terminal::width()
+                )
+            );
+            for sample in &samples {
+                if let Some(error) = &sample.error {
+                    eprintln!("EARFCN {}: {}", sample.earfcn, error.replace('\n', "; "));
+                }
+            }
+            println!("{}", restored);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(earfcn: u32, rsrp: Option<f64>, sinr: Option<f64>) -> BandSample {
+        BandSample {
+            earfcn,
+            rsrp,
+            sinr,
+            stayed_on: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn strongest_band_ranks_first_and_unread_ones_last() {
+        let mut samples = vec![
+            sample(1850, Some(-101.0), Some(5.0)),
+            sample(9820, None, None),
+            sample(39150, Some(-92.0), Some(3.0)),
+            sample(6300, Some(-92.0), Some(11.0)),
+        ];
+        rank(&mut samples);
+        let order: Vec<u32> = samples.iter().map(|s| s.earfcn).collect();
+        assert_eq!(order, [6300, 39150, 1850, 9820]);
+        assert_eq!(
+            average([Some(-90.0), None, Some(-94.0)].into_iter()),
+            Some(-92.0)
+        );
+        assert_eq!(average([None, None].into_iter()), None);
+    }
+}
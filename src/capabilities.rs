@@ -0,0 +1,139 @@
+use std::error::Error;
+
+use serde_json::{json, Map, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::RouterClient;
+use crate::error::ZitelError;
+
+// Read-only commands that are safe to send while probing. Setters are never
+// probed since there is no way to call them without changing router state.
+const PROBE_COMMANDS: &[&str] = &[
+    "get_index_data",
+    "get_neighbour_cell",
+    "get_sim_status",
+    "get_dhcp_list",
+    "get_sms_list",
+    "get_wifi_status",
+    "get_port_forward",
+];
+
+// Status values some firmware uses to reject a command it does not know.
+const REJECTED_STATUSES: &[&str] = &["error", "fail", "failed", "unknown", "unsupported"];
+
+enum Capability {
+    Supported,
+    Unsupported,
+    Error(String),
+}
+
+impl Capability {
+    fn label(&self) -> &'static str {
+        match self {
+            Capability::Supported => "supported",
+            Capability::Unsupported => "unsupported",
+            Capability::Error(_) => "error",
+        }
+    }
+}
+
+struct ProbeResult {
+    command: &'static str,
+    capability: Capability,
+    // untouched router answer, for --format raw
+    response: Option<Value>,
+}
+
+fn classify(result: &Result<Value, ZitelError>) -> Capability {
+    match result {
+        Ok(Value::Null) => Capability::Unsupported,
+        Ok(Value::Object(map)) if map.is_empty() => Capability::Unsupported,
+        Ok(json) => {
+            let rejected = json["status"]
+                .as_str()
+                .map(|s| REJECTED_STATUSES.contains(&s.to_lowercase().as_str()))
+                .unwrap_or(false);
+            if rejected {
+                Capability::Unsupported
+            } else {
+                Capability::Supported
+            }
+        }
+        // The router answered, but not with JSON: it did not understand the command.
+        Err(ZitelError::Decode(e)) if !e.html => Capability::Unsupported,
+        Err(e) => Capability::Error(e.to_string()),
+    }
+}
+
+/// Whether a command's answer means the firmware does not know it.
+pub fn unsupported(result: &Result<Value, ZitelError>) -> bool {
+    matches!(classify(result), Capability::Unsupported)
+}
+
+async fn probe(client: &RouterClient) -> Vec<ProbeResult> {
+    let mut results = Vec::with_capacity(PROBE_COMMANDS.len());
+    for command in PROBE_COMMANDS {
+        let result = client.api_request(command).await;
+        results.push(ProbeResult {
+            command,
+            capability: classify(&result),
+            response: result.ok(),
+        });
+    }
+    results
+}
+
+fn to_json(base_url: &str, results: &[ProbeResult]) -> Value {
+    let mut commands = Map::new();
+    for result in results {
+        let mut entry = json!({ "status": result.capability.label() });
+        if let Capability::Error(e) = &result.capability {
+            entry["detail"] = Value::String(e.clone());
+        }
+        commands.insert(result.command.to_string(), entry);
+    }
+    json!({ "router": base_url, "commands": commands })
+}
+
+fn to_raw(results: &[ProbeResult]) -> Value {
+    let commands = results
+        .iter()
+        .map(|r| {
+            (
+                r.command.to_string(),
+                r.response.clone().unwrap_or_default(),
+            )
+        })
+        .collect::<Map<_, _>>();
+    Value::Object(commands)
+}
+
+fn render_text(base_url: &str, results: &[ProbeResult]) -> String {
+    let mut out = format!("Router capabilities ({})\n", base_url);
+    for result in results {
+        out.push_str(&match &result.capability {
+            Capability::Error(e) => format!("  {:20} error: {}\n", result.command, e),
+            other => format!("  {:20} {}\n", result.command, other.label()),
+        });
+    }
+    out
+}
+
+pub async fn run(client: &RouterClient, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let results = probe(client).await;
+
+    match format {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&to_json(client.base_url(), &results))?
+            );
+        }
+        OutputFormat::Raw => {
+            println!("{}", serde_json::to_string_pretty(&to_raw(&results))?);
+        }
+        OutputFormat::Text => print!("{}", render_text(client.base_url(), &results)),
+    }
+
+    Ok(())
+}
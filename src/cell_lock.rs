@@ -0,0 +1,57 @@
+use std::error::Error;
+
+use serde_json::json;
+
+use crate::cli::OutputFormat;
+use crate::client::{failure_reason, is_success, lock_cleared, RouterClient};
+use crate::error::ZitelError;
+use crate::prompt::confirm_change;
+
+pub enum CellLockAction {
+    Set { earfcn: u32, pci: u16 },
+    Clear,
+}
+
+pub async fn run(
+    client: &RouterClient,
+    action: CellLockAction,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let (resp, done, summary) = match action {
+        CellLockAction::Set { earfcn, pci } => {
+            let question = format!("Lock the radio to EARFCN {}, PCI {}?", earfcn, pci);
+            confirm_change(client, &question)?;
+            let resp = client.set_cell_lock(&earfcn.to_string(), pci).await?;
+            let done = is_success(&resp);
+            let summary = format!("Cell lock set to EARFCN {}, PCI {}", earfcn, pci);
+            let summary = client.outcome(&summary);
+            (resp, done, summary)
+        }
+        CellLockAction::Clear => {
+            confirm_change(client, "Clear the cell lock?")?;
+            let resp = client.clear_cell_lock().await?;
+            let done = lock_cleared(&resp);
+            (resp, done, client.outcome("Cell lock cleared"))
+        }
+    };
+
+    match format {
+        OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&resp)?),
+        OutputFormat::Json => {
+            let normalized = json!({
+                "success": done,
+                "dry_run": client.dry_run(),
+                "message": if done { Some(summary.as_str()) } else { failure_reason(&resp) },
+            });
+            println!("{}", serde_json::to_string_pretty(&normalized)?);
+        }
+        OutputFormat::Text if done => println!("{}", summary),
+        OutputFormat::Text => {}
+    }
+
+    if done {
+        Ok(())
+    } else {
+        Err(ZitelError::refused("Cell lock failed", &resp).into())
+    }
+}
@@ -0,0 +1,168 @@
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use ratatui::symbols::{bar, border};
+use unicode_width::UnicodeWidthChar;
+
+// ---------- character set ----------
+// Signal bars, sparklines, the TUI's borders and the mark on cut-off text
+// are Unicode block and box-drawing characters, garbage on a serial console
+// or a terminal without a UTF-8 locale. In ASCII mode each has a stand-in
+// as wide, and router text in the printed tables is brought down to ASCII
+// before it is measured, so the tables line up as before. Decided once at
+// startup from --ascii and the locale.
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+pub fn set_ascii(ascii: bool) {
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+pub fn ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+/// Whether the locale named by the environment uses UTF-8: the first of
+/// LC_ALL, LC_CTYPE and LANG that is set decides, none set is the C locale.
+pub fn locale_is_utf8(var: impl Fn(&str) -> Option<String>) -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| var(name).filter(|value| !value.is_empty()))
+        .is_some_and(|locale| {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+// pictographs, dingbats, flags and the joiners and selectors between them
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0xFE00..=0xFE0F | 0x200D | 0xE0020..=0xE007F
+    )
+}
+
+/// `text` as it can be shown: unchanged, or in ASCII mode with emoji left
+/// out and every other column of non-ASCII text shown as `?`.
+pub fn text(text: &str) -> Cow<'_, str> {
+    if !ascii() || text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(to_ascii(text))
+}
+
+fn to_ascii(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            c if c.is_ascii() => out.push(c),
+            c if is_emoji(c) => {}
+            c => out.extend(std::iter::repeat_n('?', c.width().unwrap_or(0))),
+        }
+    }
+    out
+}
+
+/// Put where text was cut short.
+pub fn ellipsis() -> &'static str {
+    if ascii() {
+        "~"
+    } else {
+        "…"
+    }
+}
+
+/// Between an EARFCN and its band.
+pub fn arrow() -> &'static str {
+    if ascii() {
+        "->"
+    } else {
+        "→"
+    }
+}
+
+/// The filled and the empty part of a signal bar.
+pub fn bar_parts() -> (char, char) {
+    if ascii() {
+        ('#', '-')
+    } else {
+        ('█', '░')
+    }
+}
+
+/// Sparkline levels, lowest first.
+pub fn spark_levels() -> [char; 8] {
+    if ascii() {
+        ['_', '.', ',', '-', '=', '+', '*', '#']
+    } else {
+        ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█']
+    }
+}
+
+/// The TUI's panel borders: ratatui's own lines, or the `+-|` boxes of the
+/// printed tables.
+pub fn border_set() -> border::Set {
+    if !ascii() {
+        return border::PLAIN;
+    }
+    border::Set {
+        top_left: "+",
+        top_right: "+",
+        bottom_left: "+",
+        bottom_right: "+",
+        vertical_left: "|",
+        vertical_right: "|",
+        horizontal_top: "-",
+        horizontal_bottom: "-",
+    }
+}
+
+/// The TUI sparklines' bars.
+pub fn sparkline_set() -> bar::Set {
+    if !ascii() {
+        return bar::NINE_LEVELS;
+    }
+    bar::Set {
+        full: "#",
+        seven_eighths: "#",
+        three_quarters: "*",
+        five_eighths: "+",
+        half: "=",
+        three_eighths: "-",
+        one_quarter: ",",
+        one_eighth: ".",
+        empty: " ",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_first_locale_variable_set_decides() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert!(locale_is_utf8(env(&[("LANG", "en_US.UTF-8")])));
+        assert!(locale_is_utf8(env(&[
+            ("LC_ALL", "de_DE.utf8"),
+            ("LANG", "C")
+        ])));
+        assert!(!locale_is_utf8(env(&[
+            ("LC_ALL", "C"),
+            ("LANG", "en_US.UTF-8")
+        ])));
+        assert!(locale_is_utf8(env(&[("LC_ALL", ""), ("LANG", "C.UTF-8")])));
+        assert!(!locale_is_utf8(env(&[])));
+    }
+
+    #[test]
+    fn wide_text_keeps_its_width_and_emoji_go() {
+        assert_eq!(to_ascii("路由器 📶 Zitel"), "??????  Zitel");
+        assert_eq!(to_ascii("Café 👍🏽"), "Caf? ");
+    }
+}
@@ -0,0 +1,722 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+
+use clap::builder::PossibleValuesParser;
+use clap::{Parser, Subcommand, ValueEnum};
+use reqwest::header::{HeaderName, HeaderValue};
+
+use crate::apn::{validate_apn, ApnAuth};
+use crate::client::{
+    parse_api_path, parse_base_url, parse_header, DEFAULT_LOGIN_ATTEMPTS, MAX_PCI,
+};
+use crate::credentials::AuthMode;
+use crate::dashboard::SECTION_COUNT;
+use crate::data_cap::parse_cap;
+use crate::dmz::parse_host;
+use crate::earfcn::parse_earfcn;
+use crate::jitter;
+use crate::monitor::GRAPH_FIELDS;
+use crate::neighbors::MAX_NEIGHBOR_CELLS;
+use crate::network_mode::{parse_mode, NetworkMode};
+use crate::ping::{validate_host, MAX_PING_COUNT};
+use crate::port_forward::{validate_id as validate_rule_id, Protocol};
+use crate::sms::validate_number;
+use crate::throughput::MAX_WINDOW_SECS;
+use crate::wifi::{validate_passphrase, validate_ssid, Radio};
+
+// ---------- command line ----------
+#[derive(Parser)]
+#[command(
+    name = "zitel",
+    version,
+    about = "Terminal manager for Zitel LTE routers",
+    after_help = "Without a command the interactive dashboard starts.\n\n\
+                  Exit codes:\n  \
+                  0  success\n  \
+                  1  other failure (e.g. cancelled, file not writable)\n  \
+                  2  invalid arguments or input\n  \
+                  3  login failed or the session could not be renewed\n  \
+                  4  router unreachable (connection, timeout, TLS)\n  \
+                  5  router refused the command or sent an unexpected answer"
+)]
+pub struct Cli {
+    /// Output format for one-shot commands
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Shorthand for --format json: only JSON on stdout, messages go to
+    /// stderr. Without a command prints the dashboard once instead of
+    /// starting the interactive view
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Config file [default: ~/.config/zitel/config.toml]
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Router address [default: http://192.168.0.1]
+    #[arg(long, global = true, env = "ZITEL_BASE_URL", value_parser = parse_base_url)]
+    pub base_url: Option<String>,
+
+    /// Path API commands are posted to, for firmware that serves the API
+    /// somewhere else [default: /api.leano]
+    #[arg(long, global = true, value_name = "PATH", value_parser = parse_api_path)]
+    pub api_path: Option<String>,
+
+    /// Answer commands from saved JSON instead of a router: a captured
+    /// get_index_data response, or a directory of responses named after
+    /// their commands (e.g. get_neighbour_cell.json). Nothing is sent
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        conflicts_with = "auto_discover"
+    )]
+    pub replay: Option<PathBuf>,
+
+    /// Write every API response to this directory as <time>_<command>.json,
+    /// e.g. to attach to a bug report or to --replay later
+    #[arg(long, global = true, value_name = "DIR")]
+    pub save_responses: Option<PathBuf>,
+
+    /// Mask IMSI, ICCID and IMEI to their last four digits in all output,
+    /// not only in CSV/HTML exports and saved responses
+    #[arg(long, global = true)]
+    pub redact: bool,
+
+    /// Keep IMSI, ICCID and IMEI in full everywhere, exports included
+    #[arg(long, global = true, conflicts_with = "redact")]
+    pub no_redact: bool,
+
+    /// Accept an invalid or self-signed HTTPS certificate from the router
+    #[arg(long, global = true)]
+    pub insecure: bool,
+
+    /// Router login name, prompted for when not given
+    #[arg(long, global = true, env = "ZITEL_USER")]
+    pub username: Option<String>,
+
+    /// Router password [default: the one `zitel login` stored, else the
+    /// config file's, else prompted for without echo]
+    #[arg(long, global = true, env = "ZITEL_PASS", hide_env_values = true)]
+    pub password: Option<String>,
+
+    /// How the password is sent at login; auto tries plain first and falls
+    /// back to hashed when the router asks for it
+    #[arg(long, global = true, value_enum, default_value_t = AuthMode::Plain)]
+    pub auth_mode: AuthMode,
+
+    /// Always log in, ignoring and not updating the cached session token
+    #[arg(long, global = true)]
+    pub fresh_login: bool,
+
+    /// Seconds a cached session token is reused for [default: 600]
+    #[arg(long, global = true, value_name = "SECONDS")]
+    pub token_ttl: Option<u64>,
+
+    /// Use the default gateway as the router address (falls back to
+    /// 192.168.0.1). Takes precedence over --base-url
+    #[arg(long, global = true)]
+    pub auto_discover: bool,
+
+    /// Extra or replacement HTTP header, e.g. "Referer: http://192.168.0.1/".
+    /// Merged over the defaults and the config file's [headers]; Leano_Auth
+    /// is still set from the session token unless given here explicitly.
+    /// May be repeated.
+    #[arg(long = "header", global = true, value_name = "NAME: VALUE", value_parser = parse_header)]
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+
+    /// No colours in printed output (also off with NO_COLOR or when stdout is
+    /// not a terminal)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Print long output (the dashboard, neighbour cells, the history)
+    /// straight to the terminal instead of through $PAGER or less
+    #[arg(long, global = true)]
+    pub no_pager: bool,
+
+    /// Print the commands that would change the router (DMZ, locks, SMS,
+    /// PIN, reboot) instead of sending them. Reads still go to the router
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Record every change sent to the router in this file [default:
+    /// history_file from the config, else ~/.config/zitel/history.jsonl]
+    #[arg(long, global = true, value_name = "PATH")]
+    pub history_file: Option<PathBuf>,
+
+    /// Do not ask before changing the router (for scripts; without a
+    /// terminal, changes are refused unless this is given) or before
+    /// leaving the interactive view
+    #[arg(long, short, global = true)]
+    pub yes: bool,
+
+    /// Times a command is resent after a connection error, or a read after a
+    /// timeout; a change that timed out may have been made and is not resent
+    #[arg(long, global = true, value_name = "N", default_value_t = 2)]
+    pub max_retries: u32,
+
+    /// Seconds to wait for the router to answer a login or command
+    /// [default: 10 for logins, 30 for commands]
+    #[arg(long, global = true, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub timeout: Option<u64>,
+
+    /// Times a login is tried while the router cannot be reached (e.g.
+    /// still booting), 2 seconds apart; a wrong password is not retried
+    #[arg(long, global = true, value_name = "N", default_value_t = DEFAULT_LOGIN_ATTEMPTS, value_parser = clap::value_parser!(u32).range(1..))]
+    pub login_attempts: u32,
+
+    /// Milliseconds before the first retry, doubled after each one
+    #[arg(long, global = true, value_name = "MS", default_value_t = 500)]
+    pub retry_delay: u64,
+
+    /// Move every wait between polls, and before a retry, by up to this
+    /// percentage either way, so several clients watching one router do
+    /// not poll in step [default: 0, at most 50]
+    #[arg(long, global = true, value_name = "PERCENT", value_parser = clap::value_parser!(u8).range(0..=jitter::MAX_PERCENT as i64))]
+    pub jitter: Option<u8>,
+
+    /// Draw bars, sparklines and borders with ASCII characters only and
+    /// leave emoji out, for serial consoles and terminals without UTF-8;
+    /// the default when the locale is not UTF-8
+    #[arg(long, global = true)]
+    pub ascii: bool,
+
+    /// Print and log all timestamps in UTC instead of local time
+    #[arg(long, global = true)]
+    pub utc: bool,
+
+    /// Seconds between automatic refreshes of the current page in the
+    /// interactive view [default: 1]. Toggle auto-refresh with `r`
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub refresh_interval: Option<u64>,
+
+    /// Tabs of the interactive view, in this order, e.g. dashboard,neighbors
+    /// [default: dashboard, neighbors, bandlock, dmz, devices, sms, all]
+    #[arg(long, value_name = "PAGES", value_delimiter = ',')]
+    pub pages: Vec<String>,
+
+    /// Print the dashboard once instead of starting the interactive view,
+    /// the same as the `dashboard` command
+    #[arg(long, conflicts_with = "refresh_interval")]
+    pub once: bool,
+
+    /// With --once, print only this dashboard section (see `dashboard --page`)
+    #[arg(long, value_name = "N", requires = "once", value_parser = clap::value_parser!(u8).range(1..=SECTION_COUNT as i64))]
+    pub page: Option<u8>,
+
+    /// Never enable bracketed paste (for terminals that echo its escape codes)
+    #[arg(long)]
+    pub no_bracketed_paste: bool,
+
+    /// Log requests and HTTP statuses to stderr; twice (-vv) adds the
+    /// response bodies. Passwords, PINs and tokens are never logged. Ignored
+    /// by the interactive view, whose screen it would overwrite
+    #[arg(long, short, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// On exit, print where time went: login, each API command and rendering
+    #[arg(long, global = true)]
+    pub profile_timing: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    pub fn output_format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else {
+            self.format
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Print the dashboard once, every section stacked
+    ///
+    /// All sections (data usage, connection, network, cell, IP, system) come
+    /// from a single get_index_data request; the TUI dashboard shows the same
+    /// data on one screen.
+    Dashboard {
+        /// Append the values as one CSV row to this file instead of printing
+        /// them (the header is written when the file is new)
+        #[arg(long, value_name = "PATH")]
+        csv: Option<PathBuf>,
+        /// Write the dashboard as a self-contained HTML report to this file
+        /// instead of printing it
+        #[arg(long, value_name = "PATH", conflicts_with = "csv")]
+        html: Option<PathBuf>,
+        /// Print only one section: 1 data usage, 2 connection, 3 network,
+        /// 4 cell, 5 IP, 6 system
+        #[arg(long, value_name = "N", conflicts_with_all = ["csv", "html"], value_parser = clap::value_parser!(u8).range(1..=SECTION_COUNT as i64))]
+        page: Option<u8>,
+        /// Add the neighbour cells and connected devices, asked for at the
+        /// same time as the dashboard; what fails is left out with a warning
+        #[arg(long, conflicts_with_all = ["csv", "html", "page"])]
+        all: bool,
+    },
+    /// Point the DMZ at a LAN host, turn it off or show it
+    Dmz {
+        /// Host to expose [default: default_dmz_ip from the config, else 192.168.0.92]
+        #[arg(value_parser = parse_host)]
+        ip: Option<Ipv4Addr>,
+        /// Traffic forwarded to the host: tcp, udp or both (also tcpudp)
+        #[arg(long, value_enum, default_value_t = Protocol::Both, conflicts_with_all = ["off", "show"])]
+        protocol: Protocol,
+        /// Turn DMZ off
+        #[arg(long, conflicts_with_all = ["ip", "show"])]
+        off: bool,
+        /// Print the configured DMZ host and protocol
+        #[arg(long, conflicts_with = "ip")]
+        show: bool,
+    },
+    /// List, add or delete port forwards, the per-port alternative to the DMZ
+    #[command(alias = "port-forward")]
+    Forward {
+        #[command(subcommand)]
+        action: ForwardCommand,
+    },
+    /// Lock the radio to one EARFCN
+    #[command(name = "bandlock", alias = "band-lock")]
+    BandLock {
+        /// EARFCN to lock to, 0-262143, e.g. 42490
+        #[arg(value_parser = parse_earfcn, required_unless_present = "neighbor")]
+        earfcn: Option<u32>,
+        /// Lock to the EARFCN of the neighbour cell with this rank, 1 being
+        /// the strongest
+        #[arg(long, value_name = "RANK", conflicts_with = "earfcn", value_parser = clap::value_parser!(u16).range(1..=MAX_NEIGHBOR_CELLS as i64))]
+        neighbor: Option<u16>,
+    },
+    /// Clear the band lock and return to automatic band selection
+    #[command(name = "bandunlock", alias = "band-unlock")]
+    BandUnlock,
+    /// Lock to one EARFCN after the other and rank them by signal
+    ///
+    /// Every EARFCN gets the settle time and then three samples. The band
+    /// lock is cleared at the end, also after Ctrl-C or an error.
+    #[command(name = "bandscan", alias = "band-scan")]
+    BandScan {
+        /// EARFCNs to compare [default: band_scan_earfcns from the config,
+        /// else those of the serving and neighbour cells]
+        #[arg(value_parser = parse_earfcn)]
+        earfcns: Vec<u32>,
+        /// Seconds the radio gets to attach after each lock [default:
+        /// band_scan_settle from the config, else 20]
+        #[arg(long, value_name = "SECS")]
+        settle: Option<u64>,
+        /// Lock to this EARFCN at the end instead of clearing the lock, the
+        /// router does not say what it was locked to before
+        #[arg(long, value_name = "EARFCN", value_parser = parse_earfcn)]
+        restore: Option<u32>,
+    },
+    /// Restrict the modem to 4G or 5G, or show what it is connected via
+    ///
+    /// The connection drops briefly while the modem re-registers; the
+    /// connection type is read back afterwards.
+    #[command(name = "network-mode", alias = "netmode")]
+    NetworkMode {
+        /// auto, 4g, 5g-sa or 5g-nsa [default: show the connection type]
+        #[arg(value_parser = parse_mode)]
+        mode: Option<NetworkMode>,
+    },
+    /// Sample the signal until Ctrl-C, then print min/max/average
+    Monitor {
+        /// Seconds between samples
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// Append samples as CSV lines to this file
+        #[arg(long, value_name = "PATH")]
+        log: Option<PathBuf>,
+        /// Metric to plot as a sparkline under each sample
+        #[arg(long, default_value = "rsrp", value_parser = PossibleValuesParser::new(GRAPH_FIELDS))]
+        graph: String,
+        /// Do not plot a sparkline
+        #[arg(long, conflicts_with = "graph")]
+        no_graph: bool,
+        /// Number of samples the sparkline shows
+        #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u16).range(2..))]
+        history: u16,
+        /// Warn as the data used this cycle nears this many GB [default:
+        /// data_cap_gb from the config]
+        ///
+        /// The cycle is kept in ~/.config/zitel/usage.json, so it goes on
+        /// across router reboots; `zitel usage reset` starts a new one.
+        #[arg(long, value_name = "GB", value_parser = parse_cap)]
+        data_cap: Option<f64>,
+        /// Percentages of the cap to warn at, each once per cycle [default:
+        /// data_cap_alerts from the config, else 80,100]
+        #[arg(long, value_name = "PCT", value_delimiter = ',', value_parser = clap::value_parser!(u8).range(1..))]
+        cap_alerts: Vec<u8>,
+        /// Shell command run at each warning, with ZITEL_THRESHOLD,
+        /// ZITEL_USAGE_PERCENT, ZITEL_USAGE_BYTES and ZITEL_CAP_BYTES set
+        #[arg(long, value_name = "CMD")]
+        cap_hook: Option<String>,
+    },
+    /// Poll until a signal metric crosses a target, e.g. while aiming an
+    /// antenna; exits non-zero when the time runs out
+    Wait {
+        /// Metric to watch
+        #[arg(long, default_value = "rsrp", value_parser = PossibleValuesParser::new(GRAPH_FIELDS))]
+        metric: String,
+        /// Wait until the metric is above this value
+        #[arg(
+            long,
+            allow_negative_numbers = true,
+            required_unless_present = "below",
+            conflicts_with = "below"
+        )]
+        above: Option<f64>,
+        /// Wait until the metric is below this value (for metrics where lower is better)
+        #[arg(long, allow_negative_numbers = true)]
+        below: Option<f64>,
+        /// Seconds to wait before giving up
+        #[arg(long, value_name = "SECONDS", default_value_t = 300, value_parser = clap::value_parser!(u64).range(1..))]
+        max_wait: u64,
+        /// Seconds between polls
+        #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// Ring the terminal bell when the target is reached
+        #[arg(long)]
+        bell: bool,
+    },
+    /// Publish the signal, traffic and uptime to an MQTT broker
+    Mqtt {
+        /// Broker host name or address
+        #[arg(long)]
+        host: String,
+        #[arg(long, default_value_t = 1883)]
+        port: u16,
+        /// Broker username (not the router's)
+        #[arg(long)]
+        broker_user: Option<String>,
+        /// Broker password; MQTT 3.1.1 only sends one along with a username
+        #[arg(
+            long,
+            env = "ZITEL_MQTT_PASS",
+            hide_env_values = true,
+            requires = "broker_user"
+        )]
+        broker_password: Option<String>,
+        #[arg(long, default_value = "zitel")]
+        client_id: String,
+        /// Metrics go to <prefix>/signal/rsrp and so on
+        #[arg(long, default_value = "zitel")]
+        topic_prefix: String,
+        /// Where Home Assistant looks for sensor configs
+        #[arg(long, default_value = "homeassistant")]
+        discovery_prefix: String,
+        /// Do not publish Home Assistant discovery configs
+        #[arg(long)]
+        no_discovery: bool,
+        /// Seconds between polls of the router
+        #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+    },
+    /// Serve the signal, traffic and uptime as Prometheus metrics
+    ///
+    /// GET /metrics for Prometheus, GET /health for liveness probes: 200
+    /// while the last poll of the router succeeded within --max-age, 503
+    /// otherwise, each with the last poll time and error as JSON.
+    #[command(name = "serve-metrics")]
+    ServeMetrics {
+        /// Address and port to listen on
+        #[arg(long, default_value = "127.0.0.1:9877")]
+        listen: SocketAddr,
+        /// Seconds between polls of the router
+        #[arg(long, default_value_t = 15, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// Seconds after a successful poll that /health still answers 200
+        /// [default: three intervals]
+        #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+        max_age: Option<u64>,
+    },
+    /// Answer JSON-RPC requests from other local tools over one session
+    ///
+    /// One request per line, e.g. {"jsonrpc": "2.0", "id": 1, "method":
+    /// "dashboard"}; the result is the router's answer. Methods: dashboard,
+    /// neighbors, dmz, set_dmz ({"ip": ..., "protocol": "tcp"} or
+    /// {"enabled": false}), bandlock ({"earfcn": ...}), bandunlock,
+    /// refresh_session and methods. Changes are not confirmed, --dry-run
+    /// still applies.
+    Serve {
+        /// Unix socket to listen on, readable by you only; not on Windows
+        /// [default: $XDG_RUNTIME_DIR/zitel.sock, else ~/.config/zitel/zitel.sock]
+        #[arg(long, value_name = "PATH", conflicts_with = "listen")]
+        socket: Option<PathBuf>,
+        /// Listen on this TCP address instead, e.g. 127.0.0.1:9878 (the
+        /// default where there are no Unix sockets)
+        #[arg(long)]
+        listen: Option<SocketAddr>,
+    },
+    /// Estimate throughput from the router's byte counters
+    #[command(alias = "speed")]
+    Throughput {
+        /// Seconds between the two counter readings
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..=MAX_WINDOW_SECS))]
+        window: u64,
+    },
+    /// Log in and store the password, so later runs need neither the
+    /// prompt nor a password in the config file
+    ///
+    /// The password is saved for this router and user in the system
+    /// keyring (Keychain, Credential Manager or Secret Service). Without
+    /// one it goes to ~/.config/zitel/secrets.json, readable by you only.
+    Login,
+    /// Forget the passwords stored for this router
+    Logout,
+    /// List the changes sent to routers, newest last, no login needed
+    ///
+    /// Every DMZ, lock, APN, Wi-Fi, SMS, PIN and reboot request is recorded
+    /// with its time, parameters and the router's answer. PINs and
+    /// passwords are masked.
+    History {
+        /// How many of the latest changes to show
+        #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u64).range(1..))]
+        limit: u64,
+    },
+    /// Look for the router at the usual addresses, no login needed
+    ///
+    /// Tries the default gateway, 192.168.0.1, 192.168.1.1 and 10.0.0.1 at
+    /// once and offers to save the one that answers like a Zitel router.
+    Discover,
+    /// Check the router address, login and API one step at a time, with
+    /// hints for whatever fails
+    ///
+    /// Looks up the router's name, asks its web server, logs in, reads the
+    /// dashboard and compares the router's clock with this computer's.
+    /// Exits non-zero when any step but the clock fails.
+    Doctor,
+    /// Print one compact status line, e.g. for a tmux or polybar widget
+    ///
+    /// Exits non-zero when the router cannot be reached, so the widget can
+    /// show that.
+    Status {
+        /// Values in braces: {net} (4G/5G), {operator}, {uptime}, {rx}, {tx},
+        /// {total} or any field name the JSON dashboard uses, e.g. {rsrp}
+        /// [default: status_template from the config, else
+        /// "{net} B{band} RSRP={rsrp} SINR={sinr} up={uptime} rx={rx}"]
+        #[arg(long)]
+        template: Option<String>,
+    },
+    /// Reboot the router and wait until it is back online
+    Reboot,
+    /// Restore the router's factory settings (asks twice)
+    #[command(name = "factory-reset")]
+    FactoryReset {
+        /// Allow --yes to skip the questions, for scripts
+        #[arg(long)]
+        force: bool,
+    },
+    /// Probe which API commands this router supports
+    Capabilities,
+    /// Send any API command and print the router's JSON answer
+    ///
+    /// For firmware features zitel has no command for, e.g.
+    /// `zitel raw get_wan_info`. Nothing is checked; commands other than
+    /// `get_...` reads are asked about first, skipped with --dry-run and
+    /// recorded in the history.
+    Raw {
+        /// The command and its arguments, as the router's API takes them
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// List LAN clients with their DHCP leases
+    Devices,
+    /// Ping a host from the router and show latency and packet loss
+    Ping {
+        /// Host name or IP address; asked for when left out
+        #[arg(value_parser = validate_host)]
+        host: Option<String>,
+        /// Packets to send
+        #[arg(long, short, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..=MAX_PING_COUNT as i64))]
+        count: u32,
+    },
+    /// Scan neighbour cells and print each scan as a JSON line
+    Neighbors {
+        /// Keep scanning; on a terminal, redraw a table sorted by signal until
+        /// a key is pressed, otherwise print scans until Ctrl-C
+        #[arg(long)]
+        watch: bool,
+        /// Seconds between scans in watch mode
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// Append scans to this file instead of printing them
+        #[arg(long, value_name = "PATH")]
+        log_jsonl: Option<PathBuf>,
+        /// Command printing "lat lon" to tag each scan with a position
+        #[arg(long, value_name = "CMD")]
+        gps_cmd: Option<String>,
+    },
+    /// Lock the radio to one cell (EARFCN + PCI), or clear the lock
+    CellLock {
+        /// EARFCN the cell transmits on
+        #[arg(required_unless_present = "clear", value_parser = parse_earfcn)]
+        earfcn: Option<u32>,
+        /// Physical cell id, 0-503
+        #[arg(required_unless_present = "clear", value_parser = clap::value_parser!(u16).range(0..=MAX_PCI as i64))]
+        pci: Option<u16>,
+        /// Remove any cell lock (succeeds if none is active)
+        #[arg(long, conflicts_with_all = ["earfcn", "pci"])]
+        clear: bool,
+    },
+    /// Show the SIM state or unlock it with a PIN
+    Sim {
+        #[command(subcommand)]
+        action: SimCommand,
+    },
+    /// Show or change the WiFi networks
+    Wifi {
+        #[command(subcommand)]
+        action: WifiCommand,
+    },
+    /// Read SMS received by the router's SIM
+    Sms {
+        #[command(subcommand)]
+        action: SmsCommand,
+    },
+    /// Show or change the APN the modem dials
+    Apn {
+        #[command(subcommand)]
+        action: ApnCommand,
+    },
+    /// Show the data used since the counters were reset, or reset them
+    Usage {
+        #[command(subcommand)]
+        action: UsageCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SmsCommand {
+    /// List received messages, newest as the router orders them
+    Inbox {
+        /// Page to show, starting at 1
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+        page: u64,
+        /// Messages per page
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        per_page: u64,
+    },
+    /// Send an SMS; asks for the number and text when they are not given
+    Send {
+        /// Destination, e.g. +491701234567
+        #[arg(long, value_parser = validate_number)]
+        to: Option<String>,
+        /// Message text; longer than 160 characters goes out in parts
+        #[arg(long)]
+        message: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ForwardCommand {
+    /// Show the current rules
+    List,
+    /// Forward an external port to a LAN host
+    Add {
+        #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+        external_port: u16,
+        /// LAN host to forward to
+        #[arg(long)]
+        ip: Ipv4Addr,
+        /// Port on the LAN host [default: the external port]
+        #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+        internal_port: Option<u16>,
+        #[arg(long, value_enum, default_value_t = Protocol::Tcp)]
+        protocol: Protocol,
+    },
+    /// Delete a rule by the id `forward list` shows
+    Delete {
+        #[arg(value_parser = validate_rule_id)]
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WifiCommand {
+    /// SSID, security and channel of each radio
+    Status,
+    /// Set a new SSID and passphrase; connected clients are disconnected
+    Set {
+        #[arg(long, value_enum, default_value_t = WifiBand::Both)]
+        band: WifiBand,
+        /// New SSID [default: keep the current one]
+        #[arg(long, value_parser = validate_ssid)]
+        ssid: Option<String>,
+        /// 8 to 63 characters, asked for when not given
+        #[arg(long, env = "ZITEL_WIFI_PASS", hide_env_values = true, value_parser = validate_passphrase)]
+        passphrase: Option<String>,
+    },
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum WifiBand {
+    #[value(name = "2.4")]
+    TwoFour,
+    #[value(name = "5")]
+    Five,
+    Both,
+}
+
+impl WifiBand {
+    pub fn radios(self) -> &'static [Radio] {
+        match self {
+            WifiBand::TwoFour => &[Radio::TwoFour],
+            WifiBand::Five => &[Radio::Five],
+            WifiBand::Both => &Radio::ALL,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum SimCommand {
+    /// Report ready / PIN required / PUK required / absent
+    Status,
+    /// Submit the SIM PIN
+    Unlock {
+        /// 4 to 8 digits
+        #[arg(long)]
+        pin: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ApnCommand {
+    /// The APN in use
+    Show,
+    /// Dial another APN; the data connection drops until the modem has
+    /// reconnected, then the new APN is read back
+    Set {
+        /// e.g. fast.t-mobile.com, at most 63 characters
+        #[arg(value_parser = validate_apn)]
+        apn: String,
+        #[arg(long, value_enum, default_value_t = ApnAuth::None)]
+        auth: ApnAuth,
+        /// User name for PAP or CHAP
+        #[arg(long)]
+        apn_username: Option<String>,
+        /// Password for PAP or CHAP, asked for when not given
+        #[arg(long, env = "ZITEL_APN_PASS", hide_env_values = true)]
+        apn_password: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum UsageCommand {
+    /// Received, sent and total bytes
+    Show,
+    /// Zero the router's counters, e.g. when a billing period starts
+    Reset,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    /// The router's response, untouched
+    Raw,
+}
@@ -0,0 +1,995 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_TYPE, DATE};
+use reqwest::{Client, Response, StatusCode};
+use serde_json::{json, Value};
+
+use crate::apn::{self, ApnAuth};
+use crate::credentials::{AuthMode, Credentials};
+use crate::dmz;
+use crate::earfcn::parse_earfcn;
+use crate::error::ZitelError;
+use crate::history;
+use crate::jitter;
+use crate::logging::{redact_body, redact_command, redacted};
+use crate::network_mode::NetworkMode;
+use crate::port_forward::{validate_id as validate_rule_id, NewRule, Protocol};
+use crate::timestamp;
+use crate::timing::Timings;
+use crate::token_cache;
+use crate::wifi::{self, Radio};
+
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded; charset=UTF-8";
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+const API_TIMEOUT: Duration = Duration::from_secs(30);
+const REACHABLE_TIMEOUT: Duration = Duration::from_secs(3);
+pub const DEFAULT_LOGIN_ATTEMPTS: u32 = 5;
+const LOGIN_RETRY_DELAY: Duration = Duration::from_secs(2);
+pub const MAX_PCI: u16 = 503;
+const BODY_SNIPPET_LEN: usize = 200;
+pub const DEFAULT_API_PATH: &str = "/api.leano";
+const LOGIN_PATH: &str = "/authenticate.leano";
+
+// ---------- base url ----------
+/// Validates a router address given on the command line or in the
+/// environment, returning it without a trailing slash.
+pub fn parse_base_url(s: &str) -> Result<String, String> {
+    let url = reqwest::Url::parse(s.trim()).map_err(|e| {
+        format!(
+            "{:?} is not a valid URL ({}), expected e.g. http://192.168.1.1",
+            s, e
+        )
+    })?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("{:?} must use http:// or https://", s));
+    }
+    if url.host_str().is_none() {
+        return Err(format!("{:?} has no host", s));
+    }
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
+/// Validates the path API commands are posted to, returning it with a
+/// leading slash.
+pub fn parse_api_path(s: &str) -> Result<String, String> {
+    let path = s.trim().trim_start_matches('/');
+    if path.is_empty() || path.contains(|c: char| c.is_whitespace() || c == '?' || c == '#') {
+        return Err(format!("{:?} is not a path, expected e.g. /api.leano", s));
+    }
+    Ok(format!("/{}", path))
+}
+
+// ---------- headers ----------
+/// Parses a `Name: Value` header given on the command line.
+pub fn parse_header(s: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected \"Name: Value\", got {:?}", s))?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|e| format!("invalid header name {:?}: {}", name.trim(), e))?;
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|e| format!("invalid header value for {}: {}", name, e))?;
+    Ok((name, value))
+}
+
+fn auth_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(FORM_CONTENT_TYPE));
+    headers
+}
+
+fn api_headers(auth_header: &str) -> Result<HeaderMap, ZitelError> {
+    let token = HeaderValue::from_str(auth_header).map_err(|_| {
+        ZitelError::Auth("router returned a session token that is not a valid header".to_string())
+    })?;
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(FORM_CONTENT_TYPE));
+    headers.insert("Leano_Auth", token);
+    headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
+    headers.insert(
+        "X-Requested-With",
+        HeaderValue::from_static("XMLHttpRequest"),
+    );
+    Ok(headers)
+}
+
+// User overrides replace a default of the same name (names are case
+// insensitive) and anything not in the defaults is added.
+pub fn merge_headers(mut defaults: HeaderMap, overrides: &HeaderMap) -> HeaderMap {
+    for name in overrides.keys() {
+        defaults.remove(name);
+    }
+    for (name, value) in overrides {
+        defaults.append(name, value.clone());
+    }
+    defaults
+}
+
+// ---------- responses ----------
+pub fn is_success(resp: &Value) -> bool {
+    resp["status"] == "success"
+}
+
+/// A cell-lock clear counts as done when the router reports there was no
+/// lock to clear, so clearing twice is not an error.
+pub fn lock_cleared(resp: &Value) -> bool {
+    if is_success(resp) {
+        return true;
+    }
+    failure_reason(resp)
+        .map(|r| r.to_lowercase())
+        .is_some_and(|r| r.contains("not locked") || r.contains("no lock"))
+}
+
+// Fields different firmware revisions use to explain a rejected command.
+const REASON_KEYS: &[&str] = &["reason", "message", "msg", "error", "info"];
+
+/// Picks the human readable reason out of a failed command response, if any.
+pub fn failure_reason(resp: &Value) -> Option<&str> {
+    REASON_KEYS
+        .iter()
+        .filter_map(|key| resp[*key].as_str())
+        .map(str::trim)
+        .find(|reason| !reason.is_empty())
+}
+
+// ---------- response bodies ----------
+/// The router answered with something other than JSON: typically its HTML
+/// login page once the session has expired, or an error page on a 500.
+#[derive(Debug)]
+pub struct NotJsonError {
+    pub status: StatusCode,
+    pub html: bool,
+    /// Start of the body, whitespace collapsed onto one line.
+    pub snippet: String,
+}
+
+impl fmt::Display for NotJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.html {
+            write!(
+                f,
+                "router answered HTTP {} with an HTML page instead of JSON (session expired?): {}",
+                self.status, self.snippet
+            )
+        } else {
+            write!(
+                f,
+                "router answered HTTP {} with a body that is not JSON: {}",
+                self.status, self.snippet
+            )
+        }
+    }
+}
+
+impl Error for NotJsonError {}
+
+pub fn decode_body(status: StatusCode, body: &str) -> Result<Value, NotJsonError> {
+    serde_json::from_str(body).map_err(|_| {
+        let snippet = body
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(BODY_SNIPPET_LEN)
+            .collect();
+        NotJsonError {
+            status,
+            html: body.trim_start().starts_with('<'),
+            snippet,
+        }
+    })
+}
+
+// The web server answered but there is nothing at the endpoint: other
+// firmware, or not a Zitel router at all.
+fn endpoint_missing(response: &Response, hint: &'static str) -> Option<ZitelError> {
+    matches!(
+        response.status(),
+        StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+    )
+    .then(|| ZitelError::EndpointMissing {
+        url: response.url().to_string(),
+        status: response.status(),
+        hint,
+    })
+}
+
+async fn read_json(response: Response) -> Result<Value, ZitelError> {
+    let status = response.status();
+    let body = response.text().await?;
+    log::trace!("body: {}", redact_body(&body));
+    Ok(decode_body(status, &body)?)
+}
+
+// ---------- router client ----------
+/// One HTTP client for the whole run, so connections to the router are pooled
+/// instead of being set up again for every dashboard refresh.
+/// `insecure` accepts self-signed (or otherwise invalid) HTTPS certificates.
+pub fn build_http_client(insecure: bool) -> reqwest::Result<Client> {
+    Client::builder()
+        .danger_accept_invalid_certs(insecure)
+        .build()
+}
+
+// reqwest reports certificate problems, refused connections and failed
+// lookups all as plain connect errors, the detail is only in the source chain.
+fn explain_send_error(e: reqwest::Error, timeout: Duration) -> ZitelError {
+    if e.is_timeout() {
+        return ZitelError::Timeout(timeout);
+    }
+    let host = e
+        .url()
+        .and_then(|url| Some((url.host_str()?.to_string(), url.port_or_known_default())))
+        .map(|(host, port)| match port {
+            Some(port) => (host.clone(), format!("{}:{}", host, port)),
+            None => (host.clone(), host),
+        });
+    let mut source = e.source();
+    while let Some(cause) = source {
+        let text = cause.to_string().to_lowercase();
+        if text.contains("certificate") || text.contains("self signed") {
+            return ZitelError::Certificate(format!("TLS error: {}", cause));
+        }
+        let refused = cause
+            .downcast_ref::<io::Error>()
+            .is_some_and(|io| io.kind() == io::ErrorKind::ConnectionRefused);
+        if let Some((host, address)) = &host {
+            if refused || text.contains("connection refused") {
+                return ZitelError::ConnectionRefused(address.clone());
+            }
+            if text.contains("dns error") || text.contains("failed to lookup address") {
+                return ZitelError::DnsFailure(host.clone());
+            }
+        }
+        source = cause.source();
+    }
+    e.into()
+}
+
+// Status values firmware uses when the Leano_Auth token is no longer valid.
+const EXPIRED_STATUSES: &[&str] = &[
+    "unauthorized",
+    "unauthenticated",
+    "expired",
+    "invalid_token",
+    "auth_failed",
+    "login",
+];
+
+// A 401/403, the HTML login page in place of JSON, or an explicit status.
+fn session_expired(result: &Result<Value, ZitelError>) -> bool {
+    match result {
+        Ok(json) => json["status"]
+            .as_str()
+            .is_some_and(|s| EXPIRED_STATUSES.contains(&s.to_lowercase().as_str())),
+        Err(ZitelError::SessionExpired(_)) => true,
+        Err(ZitelError::Decode(e)) => e.html,
+        Err(_) => false,
+    }
+}
+
+type NoticeHandler = Box<dyn Fn(&str) + Send + Sync>;
+
+// ---------- login ----------
+fn login_token(json: &Value) -> Result<String, ZitelError> {
+    if json["status"] == "success" {
+        Ok(json["token"].as_str().unwrap_or("").to_string())
+    } else {
+        Err(ZitelError::Auth(match failure_reason(json) {
+            Some(reason) => format!("Authentication failed: {}", reason),
+            None => "Authentication failed".to_string(),
+        }))
+    }
+}
+
+// Firmware that wants a hashed password says so instead of reporting a wrong
+// password; only then does `Auto` send a second login, so a mistyped
+// password is not tried twice.
+const HASHED_LOGIN_STATUSES: &[&str] = &["unsupported", "invalid_format", "hash_required"];
+
+fn wants_hashed_login(json: &Value) -> bool {
+    let status = json["status"].as_str().unwrap_or("").to_lowercase();
+    let reason = failure_reason(json).unwrap_or("").to_lowercase();
+    HASHED_LOGIN_STATUSES.contains(&status.as_str())
+        || reason.contains("hash")
+        || reason.contains("format")
+}
+
+/// Whether an API command only reads: the router's reads are all `get_...`.
+pub fn is_read_command(command: &str) -> bool {
+    command.trim_start().starts_with("get_")
+}
+
+// ---------- retries ----------
+/// How often a command is resent after a connection error or, for reads, a
+/// timeout. The delay doubles after every retry.
+#[derive(Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            delay: Duration::from_millis(500),
+        }
+    }
+}
+
+// Only transport failures: an answer of any kind, including a rejected
+// session, would come back the same on a retry. A timeout may have been
+// after the router acted, so only a command that is safe to repeat (a read,
+// a login) is sent again; a change is, only when it never got through.
+fn retryable(e: &ZitelError, repeatable: bool) -> bool {
+    match e {
+        ZitelError::ConnectionRefused(_) => true,
+        ZitelError::Timeout(_) => repeatable,
+        ZitelError::Http(e) => e.is_connect() || (repeatable && e.is_timeout()),
+        _ => false,
+    }
+}
+
+pub struct RouterClient {
+    http: Client,
+    base_url: String,
+    credentials: Credentials,
+    header_overrides: HeaderMap,
+    timings: Timings,
+    token: Mutex<String>,
+    notice: NoticeHandler,
+    retry: RetryPolicy,
+    // where to keep the session token between runs, and for how long
+    token_cache: Option<(PathBuf, Duration)>,
+    // print changes instead of sending them
+    dry_run: bool,
+    // settles on Plain or Hashed after the first successful `Auto` login
+    auth_mode: Mutex<AuthMode>,
+    auth_timeout: Duration,
+    api_timeout: Duration,
+    api_path: String,
+    // recorded answers by command name; when set nothing goes to the network
+    replay: Option<HashMap<String, Value>>,
+    // every answer is also written here
+    save_responses: Option<PathBuf>,
+    // every change sent is recorded here
+    history: Option<PathBuf>,
+    login_attempts: u32,
+}
+
+impl RouterClient {
+    pub fn new(
+        http: Client,
+        base_url: String,
+        credentials: Credentials,
+        header_overrides: HeaderMap,
+    ) -> Self {
+        Self {
+            http,
+            base_url,
+            credentials,
+            header_overrides,
+            timings: Timings::default(),
+            token: Mutex::new(String::new()),
+            notice: Box::new(|message| eprintln!("Warning: {}", message)),
+            retry: RetryPolicy::default(),
+            token_cache: None,
+            dry_run: false,
+            auth_mode: Mutex::new(AuthMode::Plain),
+            auth_timeout: AUTH_TIMEOUT,
+            api_timeout: API_TIMEOUT,
+            api_path: DEFAULT_API_PATH.to_string(),
+            replay: None,
+            save_responses: None,
+            history: None,
+            login_attempts: DEFAULT_LOGIN_ATTEMPTS,
+        }
+    }
+
+    /// Where one-line warnings go (stderr unless replaced, e.g. by the TUI
+    /// which cannot have stray output on its screen).
+    pub fn on_notice(&mut self, handler: impl Fn(&str) + Send + Sync + 'static) {
+        self.notice = Box::new(handler);
+    }
+
+    pub fn set_retry(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    pub fn set_token_cache(&mut self, path: PathBuf, ttl: Duration) {
+        self.token_cache = Some((path, ttl));
+    }
+
+    /// One timeout for logins and API commands instead of the defaults
+    /// (10s and 30s).
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.auth_timeout = timeout;
+        self.api_timeout = timeout;
+    }
+
+    /// Where API commands are posted instead of `/api.leano`, already
+    /// checked by `parse_api_path`.
+    pub fn set_api_path(&mut self, path: String) {
+        self.api_path = path;
+    }
+
+    /// Answers every command from `responses` (see `replay::load`) instead
+    /// of the router; commands without a recorded answer fail.
+    pub fn set_replay(&mut self, responses: HashMap<String, Value>) {
+        self.replay = Some(responses);
+    }
+
+    /// Writes every API response to `dir` as `<time>_<command>.json`, the
+    /// names `--replay` reads. Identifiers are masked as for exports.
+    pub fn save_responses(&mut self, dir: PathBuf) -> io::Result<()> {
+        fs::create_dir_all(&dir)?;
+        self.save_responses = Some(dir);
+        Ok(())
+    }
+
+    fn save_response(&self, name: &str, response: &Value) {
+        let Some(dir) = &self.save_responses else {
+            return;
+        };
+        let response = redacted(response, true);
+        let path = dir.join(format!("{}_{}.json", timestamp::now_file_name(), name));
+        let text = serde_json::to_string_pretty(&*response).unwrap_or_default();
+        if let Err(e) = fs::write(&path, text + "\n") {
+            (self.notice)(&format!("cannot save {}: {}", path.display(), e));
+        }
+    }
+
+    /// Appends every change sent from now on to the history file at `path`.
+    pub fn set_history(&mut self, path: PathBuf) {
+        self.history = Some(path);
+    }
+
+    pub fn history(&self) -> Option<&Path> {
+        self.history.as_deref()
+    }
+
+    fn record_change(&self, command: &str, outcome: &Result<Value, ZitelError>) {
+        let Some(path) = &self.history else {
+            return;
+        };
+        let entry = history::Entry::new(&self.base_url, command, outcome);
+        if let Err(e) = history::append(path, &entry) {
+            (self.notice)(&format!(
+                "cannot record the change in {}: {}",
+                path.display(),
+                e
+            ));
+        }
+    }
+
+    /// How many times a login is tried while the router cannot be reached,
+    /// e.g. because it is still booting. A refused password is never retried.
+    pub fn set_login_attempts(&mut self, attempts: u32) {
+        self.login_attempts = attempts.max(1);
+    }
+
+    pub fn set_auth_mode(&mut self, mode: AuthMode) {
+        self.auth_mode = Mutex::new(mode);
+    }
+
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// `summary` of a change, marked when the change was only printed.
+    pub fn outcome(&self, summary: &str) -> String {
+        if self.dry_run {
+            format!("{} (dry run, nothing was changed)", summary)
+        } else {
+            summary.to_string()
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn timings(&self) -> Timings {
+        self.timings.clone()
+    }
+
+    /// Logs in and keeps the session token for the following requests (and
+    /// in the token cache, if there is one).
+    pub async fn authenticate(&self) -> Result<(), ZitelError> {
+        let started = Instant::now();
+        let result = self.login_with_retry().await;
+        self.timings.record("authenticate", started.elapsed());
+        let token = result?;
+        if let Some((path, _)) = &self.token_cache {
+            let username = &self.credentials.username;
+            if let Err(e) = token_cache::save(path, &self.base_url, username, &token) {
+                (self.notice)(&format!("cannot cache the session token: {}", e));
+            }
+        }
+        *self.token.lock().unwrap_or_else(|e| e.into_inner()) = token;
+        Ok(())
+    }
+
+    /// Picks up a cached token if the router still accepts it, otherwise
+    /// logs in.
+    pub async fn start_session(&self) -> Result<(), ZitelError> {
+        if let Some((path, ttl)) = &self.token_cache {
+            let username = &self.credentials.username;
+            if let Some(token) = token_cache::load(path, &self.base_url, username, *ttl) {
+                *self.token.lock().unwrap_or_else(|e| e.into_inner()) = token;
+                // a cheap read, without the automatic re-login of api_request
+                let valid = matches!(
+                    self.timed_command("get_index_data").await,
+                    ref result if result.is_ok() && !session_expired(result)
+                );
+                if valid {
+                    return Ok(());
+                }
+            }
+        }
+        self.authenticate().await
+    }
+
+    /// Sends one API command. If the router says the session has expired,
+    /// logs in again with the startup credentials and retries once.
+    pub async fn api_request(&self, command: &str) -> Result<Value, ZitelError> {
+        match self.timed_command(command).await {
+            result if !session_expired(&result) => return result,
+            _ => {}
+        }
+        self.authenticate().await.map_err(|e| {
+            ZitelError::Auth(format!(
+                "Session expired and logging in again failed: {}",
+                e
+            ))
+        })?;
+        (self.notice)("router session expired, logged in again");
+        self.timed_command(command).await
+    }
+
+    async fn timed_command(&self, command: &str) -> Result<Value, ZitelError> {
+        let started = Instant::now();
+        // keyed by command name only, arguments would split the statistics
+        let name = command.split_whitespace().next().unwrap_or(command);
+        let result = self.send_with_retry(name, command).await;
+        self.timings.record(name, started.elapsed());
+        if let Ok(response) = &result {
+            self.save_response(name, response);
+        }
+        result
+    }
+
+    async fn send_with_retry(&self, name: &str, command: &str) -> Result<Value, ZitelError> {
+        let mut delay = self.retry.delay;
+        let repeatable = is_read_command(command);
+        for retry in 1.. {
+            let wait = jitter::apply(delay);
+            match self.send_command(command).await {
+                Err(e) if retry <= self.retry.max_retries && retryable(&e, repeatable) => {
+                    (self.notice)(&format!(
+                        "{} failed ({}), retry {}/{} in {} ms",
+                        name,
+                        e,
+                        retry,
+                        self.retry.max_retries,
+                        wait.as_millis()
+                    ))
+                }
+                result => return result,
+            }
+            tokio::time::sleep(wait).await;
+            delay *= 2;
+        }
+        unreachable!("the retry loop only ends by returning")
+    }
+
+    async fn login_with_retry(&self) -> Result<String, ZitelError> {
+        for attempt in 1.. {
+            match self.login().await {
+                Err(e) if attempt < self.login_attempts && retryable(&e, true) => {
+                    (self.notice)(&format!(
+                        "waiting for the router to come up... (login attempt {}/{}: {})",
+                        attempt, self.login_attempts, e
+                    ))
+                }
+                result => return result,
+            }
+            tokio::time::sleep(jitter::apply(LOGIN_RETRY_DELAY)).await;
+        }
+        unreachable!("the retry loop only ends by returning")
+    }
+
+    async fn login(&self) -> Result<String, ZitelError> {
+        let mode = *self.auth_mode.lock().unwrap_or_else(|e| e.into_inner());
+        if mode != AuthMode::Auto {
+            return login_token(&self.post_login(mode).await?);
+        }
+
+        let plain = self.post_login(AuthMode::Plain).await?;
+        let mode = if wants_hashed_login(&plain) {
+            (self.notice)("router rejected the plain login, trying a hashed password");
+            AuthMode::Hashed
+        } else {
+            AuthMode::Plain
+        };
+        let token = match mode {
+            AuthMode::Hashed => login_token(&self.post_login(mode).await?)?,
+            _ => login_token(&plain)?,
+        };
+        *self.auth_mode.lock().unwrap_or_else(|e| e.into_inner()) = mode;
+        Ok(token)
+    }
+
+    async fn post_login(&self, mode: AuthMode) -> Result<Value, ZitelError> {
+        let url = format!("{}{}", self.base_url, LOGIN_PATH);
+        let xml_data = self.credentials.auth_body(mode);
+
+        log::debug!("POST {} (login, credentials not logged)", url);
+        let response = self
+            .http
+            .post(&url)
+            .timeout(self.auth_timeout)
+            .headers(merge_headers(auth_headers(), &self.header_overrides))
+            .body(xml_data)
+            .send()
+            .await
+            .map_err(|e| explain_send_error(e, self.auth_timeout))?;
+        log::debug!("login -> HTTP {}", response.status());
+        if let Some(e) = endpoint_missing(&response, "the firmware may not use the Zitel login") {
+            return Err(e);
+        }
+
+        read_json(response).await
+    }
+
+    async fn send_command(&self, command: &str) -> Result<Value, ZitelError> {
+        if let Some(responses) = &self.replay {
+            let name = command.split_whitespace().next().unwrap_or(command);
+            return responses.get(name).cloned().ok_or_else(|| {
+                ZitelError::Input(format!("No recorded {} response to replay", name))
+            });
+        }
+        let url = format!("{}{}", self.base_url, self.api_path);
+        let token = self.token.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let headers = merge_headers(api_headers(&token)?, &self.header_overrides);
+
+        log::debug!("POST {} {}", url, redact_command(command));
+        let response = self
+            .http
+            .post(&url)
+            .timeout(self.api_timeout)
+            .headers(headers)
+            .body(command.to_string())
+            .send()
+            .await
+            .map_err(|e| explain_send_error(e, self.api_timeout))?;
+
+        let status = response.status();
+        log::debug!("{} -> HTTP {}", redact_command(command), status);
+        if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+            return Err(ZitelError::SessionExpired(status));
+        }
+        if let Some(e) = endpoint_missing(
+            &response,
+            "this firmware may use another API path, see --api-path",
+        ) {
+            return Err(e);
+        }
+        read_json(response).await
+    }
+
+    /// Sends a command that changes the router, or in a dry run only says
+    /// what would be sent and answers with a success of its own.
+    async fn change(&self, command: &str) -> Result<Value, ZitelError> {
+        if !self.dry_run {
+            let outcome = self.api_request(command).await;
+            self.record_change(command, &outcome);
+            return outcome;
+        }
+        (self.notice)(&format!(
+            "dry run, not sent: POST {}{} {}",
+            self.base_url,
+            self.api_path,
+            redact_command(command)
+        ));
+        Ok(json!({ "status": "success", "dry_run": true }))
+    }
+
+    /// Sends `command` as typed, for what zitel has no support for yet.
+    /// Anything but a read may change the router, so it is treated like the
+    /// known changes: only printed in a dry run, recorded in the history.
+    pub async fn raw_command(&self, command: &str) -> Result<Value, ZitelError> {
+        let command = command.trim();
+        if command.is_empty() {
+            return Err(ZitelError::Input("No command to send".to_string()));
+        }
+        match is_read_command(command) {
+            true => self.api_request(command).await,
+            false => self.change(command).await,
+        }
+    }
+
+    /// Rejects EARFCNs outside 0-262143 before sending.
+    pub async fn set_band_lock(&self, earfcn: &str) -> Result<Value, ZitelError> {
+        let earfcn = parse_earfcn(earfcn).map_err(ZitelError::Input)?;
+        self.change(&format!("set_band_lock {}", earfcn)).await
+    }
+
+    pub async fn clear_band_lock(&self) -> Result<Value, ZitelError> {
+        self.change("clear_band_lock").await
+    }
+
+    pub async fn set_network_mode(&self, mode: NetworkMode) -> Result<Value, ZitelError> {
+        self.change(&mode.command()).await
+    }
+
+    /// Rejects anything that cannot be a LAN host before sending.
+    pub async fn set_dmz(&self, ip: &str, protocol: Protocol) -> Result<Value, ZitelError> {
+        let ip = dmz::parse_host(ip).map_err(ZitelError::Input)?;
+        let command = format!("set_dmz 1 {} {}", protocol.command_name(), ip);
+        self.change(&command).await
+    }
+
+    pub async fn disable_dmz(&self) -> Result<Value, ZitelError> {
+        self.change("set_dmz 0 tcpudp").await
+    }
+
+    pub async fn dmz_status(&self) -> Result<Value, ZitelError> {
+        self.api_request("get_dmz").await
+    }
+
+    /// Rejects EARFCNs outside 0-262143 and PCIs above 503 before sending.
+    pub async fn set_cell_lock(&self, earfcn: &str, pci: u16) -> Result<Value, ZitelError> {
+        let earfcn = parse_earfcn(earfcn).map_err(ZitelError::Input)?;
+        if pci > MAX_PCI {
+            return Err(ZitelError::Input(format!(
+                "PCI {} is out of range (0-{})",
+                pci, MAX_PCI
+            )));
+        }
+        let command = format!("set_cell_lock {} {}", earfcn, pci);
+        self.change(&command).await
+    }
+
+    pub async fn clear_cell_lock(&self) -> Result<Value, ZitelError> {
+        self.change("clear_cell_lock").await
+    }
+
+    pub async fn connected_devices(&self) -> Result<Value, ZitelError> {
+        self.api_request("get_dhcp_list").await
+    }
+
+    pub async fn sms_inbox(&self) -> Result<Value, ZitelError> {
+        self.api_request("get_sms_list").await
+    }
+
+    /// The text goes last, the router takes the rest of the command as the body.
+    pub async fn send_sms(&self, to: &str, text: &str) -> Result<Value, ZitelError> {
+        self.change(&format!("send_sms {} {}", to, text)).await
+    }
+
+    pub async fn ping(&self, host: &str, count: u32) -> Result<Value, ZitelError> {
+        self.api_request(&format!("ping {} {}", host, count)).await
+    }
+
+    pub async fn port_forwards(&self) -> Result<Value, ZitelError> {
+        self.api_request("get_port_forward").await
+    }
+
+    pub async fn add_port_forward(&self, rule: &NewRule) -> Result<Value, ZitelError> {
+        self.change(&rule.add_command()?).await
+    }
+
+    pub async fn delete_port_forward(&self, id: &str) -> Result<Value, ZitelError> {
+        let id = validate_rule_id(id).map_err(ZitelError::Input)?;
+        self.change(&format!("del_port_forward {}", id)).await
+    }
+
+    pub async fn wifi_status(&self) -> Result<Value, ZitelError> {
+        self.api_request("get_wifi_status").await
+    }
+
+    pub async fn set_wifi(
+        &self,
+        radio: Radio,
+        ssid: &str,
+        passphrase: &str,
+    ) -> Result<Value, ZitelError> {
+        self.change(&wifi::set_command(radio, ssid, passphrase)?)
+            .await
+    }
+
+    /// Rejects an APN the modem could not dial before sending.
+    pub async fn set_apn(
+        &self,
+        name: &str,
+        auth: ApnAuth,
+        login: Option<(&str, &str)>,
+    ) -> Result<Value, ZitelError> {
+        let name = apn::validate_apn(name).map_err(ZitelError::Input)?;
+        self.change(&apn::set_command(&name, auth, login)).await
+    }
+
+    pub async fn sim_status(&self) -> Result<Value, ZitelError> {
+        self.api_request("get_sim_status").await
+    }
+
+    pub async fn unlock_sim(&self, pin: &str) -> Result<Value, ZitelError> {
+        self.change(&format!("set_sim_pin {}", pin)).await
+    }
+
+    pub async fn reboot(&self) -> Result<Value, ZitelError> {
+        self.change("reboot").await
+    }
+
+    /// Zeroes the data usage counters (`recieve`/`sentt`).
+    pub async fn reset_usage(&self) -> Result<Value, ZitelError> {
+        self.change("reset_data_usage").await
+    }
+
+    pub async fn factory_reset(&self) -> Result<Value, ZitelError> {
+        self.change("factory_reset").await
+    }
+
+    /// Whether the router's web server answers at all, whatever the status.
+    pub async fn reachable(&self) -> bool {
+        self.http
+            .get(&self.base_url)
+            .timeout(REACHABLE_TIMEOUT)
+            .send()
+            .await
+            .is_ok()
+    }
+
+    /// The clock of the router's web server, from the `Date` header of its
+    /// start page; `None` when it sends none.
+    pub async fn server_date(&self) -> Result<Option<DateTime<Utc>>, ZitelError> {
+        let response = self
+            .http
+            .get(&self.base_url)
+            .timeout(REACHABLE_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| explain_send_error(e, REACHABLE_TIMEOUT))?;
+        Ok(response
+            .headers()
+            .get(DATE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+            .map(|date| date.with_timezone(&Utc)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(list: &[&str]) -> HeaderMap {
+        list.iter()
+            .map(|h| parse_header(h).unwrap())
+            .collect::<HeaderMap>()
+    }
+
+    #[test]
+    fn parse_header_trims_name_and_value() {
+        let (name, value) = parse_header(" Referer :  http://192.168.0.1/ ").unwrap();
+        assert_eq!(name, "referer");
+        assert_eq!(value, "http://192.168.0.1/");
+    }
+
+    #[test]
+    fn parse_header_rejects_missing_colon() {
+        assert!(parse_header("Referer http://192.168.0.1/").is_err());
+    }
+
+    #[test]
+    fn api_paths_get_one_leading_slash() {
+        assert_eq!(
+            parse_api_path("cgi-bin/api.leano").unwrap(),
+            "/cgi-bin/api.leano"
+        );
+        assert_eq!(parse_api_path(" //api.leano").unwrap(), "/api.leano");
+        assert!(parse_api_path("/").is_err());
+        assert!(parse_api_path("/api?x=1").is_err());
+    }
+
+    #[test]
+    fn defaults_are_kept_without_overrides() {
+        let merged = merge_headers(api_headers("token").unwrap(), &HeaderMap::new());
+        assert_eq!(merged["content-type"], FORM_CONTENT_TYPE);
+        assert_eq!(merged["leano_auth"], "token");
+        assert_eq!(merged["x-requested-with"], "XMLHttpRequest");
+    }
+
+    #[test]
+    fn overrides_replace_defaults_case_insensitively() {
+        let merged = merge_headers(
+            api_headers("token").unwrap(),
+            &overrides(&["content-type: text/plain", "X-REQUESTED-WITH: zitel"]),
+        );
+        assert_eq!(merged.get_all(CONTENT_TYPE).iter().count(), 1);
+        assert_eq!(merged[CONTENT_TYPE], "text/plain");
+        assert_eq!(merged["x-requested-with"], "zitel");
+        assert_eq!(merged["leano_auth"], "token");
+    }
+
+    #[test]
+    fn extra_headers_are_added() {
+        let merged = merge_headers(
+            api_headers("token").unwrap(),
+            &overrides(&["Referer: http://192.168.0.1/"]),
+        );
+        assert_eq!(merged["referer"], "http://192.168.0.1/");
+        assert_eq!(merged[ACCEPT], "*/*");
+    }
+
+    #[test]
+    fn json_bodies_decode() {
+        let json = decode_body(StatusCode::OK, r#"{"status":"success"}"#).unwrap();
+        assert_eq!(json["status"], "success");
+    }
+
+    #[test]
+    fn html_login_page_is_reported_with_status_and_snippet() {
+        let body = "<!DOCTYPE html>\n<html>\n  <title>Login</title>\n</html>";
+        let err = decode_body(StatusCode::OK, body).unwrap_err();
+        assert!(err.html);
+        assert_eq!(
+            err.snippet,
+            "<!DOCTYPE html> <html> <title>Login</title> </html>"
+        );
+        let message = err.to_string();
+        assert!(message.contains("200 OK"), "{}", message);
+        assert!(message.contains("session expired"), "{}", message);
+    }
+
+    #[test]
+    fn error_bodies_are_truncated() {
+        let body = "Internal Server Error ".repeat(50);
+        let err = decode_body(StatusCode::INTERNAL_SERVER_ERROR, &body).unwrap_err();
+        assert!(!err.html);
+        assert_eq!(err.snippet.chars().count(), BODY_SNIPPET_LEN);
+        assert!(err.to_string().contains("500 Internal Server Error"));
+    }
+
+    #[test]
+    fn explicit_leano_auth_wins_over_token() {
+        let merged = merge_headers(
+            api_headers("session-token").unwrap(),
+            &overrides(&["Leano_Auth: fixed"]),
+        );
+        assert_eq!(merged["leano_auth"], "fixed");
+    }
+
+    #[test]
+    fn session_expiry_is_told_apart_from_other_errors() {
+        let page = decode_body(StatusCode::OK, "<html>login</html>").unwrap_err();
+        let garbage = decode_body(StatusCode::OK, "oops").unwrap_err();
+        assert!(session_expired(&Err(ZitelError::SessionExpired(
+            StatusCode::UNAUTHORIZED
+        ))));
+        assert!(session_expired(&Err(ZitelError::Decode(page))));
+        assert!(!session_expired(&Err(ZitelError::Decode(garbage))));
+        assert!(!session_expired(&Err(ZitelError::Auth("no".to_string()))));
+        assert!(session_expired(&Ok(
+            serde_json::json!({ "status": "Expired" })
+        )));
+        assert!(!session_expired(&Ok(
+            serde_json::json!({ "status": "success" })
+        )));
+    }
+}
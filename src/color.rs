@@ -0,0 +1,46 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crossterm::style::Stylize;
+
+use crate::network_mode::Technology;
+use crate::signal::Quality;
+
+// ---------- colour ----------
+// Only for printed (one-shot) output; decided once at startup from
+// --no-color, NO_COLOR and whether stdout is a terminal.
+static USE_COLOR: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    USE_COLOR.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    USE_COLOR.load(Ordering::Relaxed)
+}
+
+/// `text` in green, yellow or red, or unchanged when colour is off.
+pub fn paint(text: &str, quality: Quality) -> String {
+    if !enabled() {
+        return text.to_string();
+    }
+    match quality {
+        Quality::Good => text.green().to_string(),
+        Quality::Fair => text.yellow().to_string(),
+        Quality::Poor => text.red().to_string(),
+    }
+}
+
+/// The connection type's badge in bold: green for 5G, cyan for 4G, yellow
+/// and red for the older networks a modem falls back to.
+pub fn badge(text: &str, technology: Technology) -> String {
+    if !enabled() {
+        return text.to_string();
+    }
+    let text = text.bold();
+    match technology {
+        Technology::NrSa | Technology::NrNsa => text.green().to_string(),
+        Technology::Lte | Technology::LteAdvanced => text.cyan().to_string(),
+        Technology::Umts => text.yellow().to_string(),
+        Technology::Gsm => text.red().to_string(),
+    }
+}
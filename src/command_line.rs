@@ -0,0 +1,305 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::network_mode::{parse_mode, NetworkMode};
+
+// ---------- command line ----------
+// The `:` prompt of the interactive view: named commands as an alternative
+// to the number keys, with Tab completion and a history kept across runs.
+pub const COMMANDS: &[&str] = &[
+    "dashboard",
+    "all",
+    "neighbors",
+    "bandlock",
+    "mode",
+    "dmz",
+    "devices",
+    "sms",
+    "raw",
+    "reboot",
+    "quit",
+];
+
+const MAX_HISTORY: usize = 500;
+
+/// What a line typed at the prompt asks for.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TuiCommand {
+    /// Show a page, by its position in the tab bar (0-based).
+    Page(usize),
+    /// Show a page by name.
+    Show(&'static str),
+    /// Make the host the DMZ host, after confirmation.
+    Dmz(String),
+    /// Lock to the EARFCN, after confirmation.
+    BandLock(String),
+    /// Switch the network mode, after confirmation.
+    NetworkMode(NetworkMode),
+    /// Send an API command as typed; anything but a read after confirmation.
+    Raw(String),
+    Reboot,
+    Quit,
+}
+
+pub fn parse(line: &str) -> Result<TuiCommand, String> {
+    let mut words = line.split_whitespace();
+    let Some(name) = words.next() else {
+        return Err("Type a command, Tab completes".to_string());
+    };
+    // the rest of the line is the router's command, arguments and all
+    if name.eq_ignore_ascii_case("raw") {
+        let command = line.trim_start()[name.len()..].trim();
+        if command.is_empty() {
+            return Err("raw needs an API command, e.g. raw get_wan_info".to_string());
+        }
+        return Ok(TuiCommand::Raw(command.to_string()));
+    }
+    let argument = words.next().map(String::from);
+    if words.next().is_some() {
+        return Err(format!("{} takes at most one argument", name));
+    }
+    // the number keys work at the prompt as well
+    if let Ok(n) = name.parse::<usize>() {
+        return match n {
+            0 => Err("Pages are numbered from 1".to_string()),
+            _ => Ok(TuiCommand::Page(n - 1)),
+        };
+    }
+    let command = match (name.to_lowercase().as_str(), argument) {
+        ("dashboard", None) => TuiCommand::Show("dashboard"),
+        ("all", None) => TuiCommand::Show("all"),
+        ("neighbors" | "neighbours", None) => TuiCommand::Show("neighbors"),
+        ("bandlock", None) => TuiCommand::Show("bandlock"),
+        ("bandlock", Some(earfcn)) => TuiCommand::BandLock(earfcn),
+        ("mode", Some(mode)) => TuiCommand::NetworkMode(parse_mode(&mode)?),
+        ("mode", None) => return Err("mode needs auto, 4g, 5g-sa or 5g-nsa".to_string()),
+        ("dmz", None) => TuiCommand::Show("dmz"),
+        ("dmz", Some(ip)) => TuiCommand::Dmz(ip),
+        ("devices", None) => TuiCommand::Show("devices"),
+        ("sms", None) => TuiCommand::Show("sms"),
+        ("reboot", None) => TuiCommand::Reboot,
+        ("quit" | "q" | "exit", None) => TuiCommand::Quit,
+        (known, Some(_)) if COMMANDS.contains(&known) => {
+            return Err(format!("{} takes no argument", known))
+        }
+        (unknown, _) => {
+            return Err(format!(
+                "Unknown command {:?}, try {}",
+                unknown,
+                COMMANDS.join(", ")
+            ))
+        }
+    };
+    Ok(command)
+}
+
+/// The command names starting with the first word of `input`.
+pub fn candidates(input: &str) -> Vec<&'static str> {
+    let word = input.trim_start().to_lowercase();
+    if word.contains(' ') {
+        return Vec::new();
+    }
+    COMMANDS
+        .iter()
+        .copied()
+        .filter(|c| c.starts_with(&word))
+        .collect()
+}
+
+/// `input` completed as far as the candidates agree: the whole name plus a
+/// space when only one matches.
+pub fn complete(input: &str) -> Option<String> {
+    let matches = candidates(input);
+    match matches.as_slice() {
+        [] => None,
+        [only] => Some(format!("{} ", only)),
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.len(), |len, c| {
+                first
+                    .bytes()
+                    .zip(c.bytes())
+                    .take(len)
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            });
+            (common > input.trim_start().len()).then(|| first[..common].to_string())
+        }
+    }
+}
+
+pub fn default_history_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("zitel").join("history"))
+}
+
+pub struct CommandLine {
+    /// Open while the prompt is shown, keys go to it instead of the pages.
+    pub active: bool,
+    pub input: String,
+    history: Vec<String>,
+    // position while walking the history with Up/Down, None = new line
+    browsing: Option<usize>,
+    path: Option<PathBuf>,
+}
+
+impl CommandLine {
+    /// A prompt with the history from `path`; no file means an empty history
+    /// that is not saved.
+    pub fn new(path: Option<PathBuf>) -> Self {
+        let history = path
+            .as_deref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|text| {
+                text.lines()
+                    .filter(|l| !l.trim().is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            active: false,
+            input: String::new(),
+            history,
+            browsing: None,
+            path,
+        }
+    }
+
+    pub fn open(&mut self) {
+        self.active = true;
+        self.input.clear();
+        self.browsing = None;
+    }
+
+    pub fn close(&mut self) {
+        self.active = false;
+        self.input.clear();
+        self.browsing = None;
+    }
+
+    /// Closes the prompt and returns the line, remembered in the history.
+    pub fn submit(&mut self) -> String {
+        let line = self.input.trim().to_string();
+        self.close();
+        if !line.is_empty() && self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+            if self.history.len() > MAX_HISTORY {
+                self.history.remove(0);
+            }
+            if let Some(path) = &self.path {
+                if let Err(e) = save_history(path, &self.history) {
+                    log::warn!("Cannot save the command history: {}", e);
+                }
+            }
+        }
+        line
+    }
+
+    pub fn previous(&mut self) {
+        let position = match self.browsing {
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+            Some(i) => i.saturating_sub(1),
+        };
+        self.browsing = Some(position);
+        self.input = self.history[position].clone();
+    }
+
+    pub fn next(&mut self) {
+        match self.browsing {
+            Some(i) if i + 1 < self.history.len() => {
+                self.browsing = Some(i + 1);
+                self.input = self.history[i + 1].clone();
+            }
+            _ => {
+                self.browsing = None;
+                self.input.clear();
+            }
+        }
+    }
+
+    pub fn complete(&mut self) {
+        if let Some(completed) = complete(&self.input) {
+            self.input = completed;
+        }
+    }
+}
+
+fn save_history(path: &Path, history: &[String]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = fs::File::create(path)?;
+    for line in history {
+        writeln!(file, "{}", line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_numbers_and_arguments_parse() {
+        assert_eq!(parse("neighbours"), Ok(TuiCommand::Show("neighbors")));
+        assert_eq!(parse("All"), Ok(TuiCommand::Show("all")));
+        assert_eq!(parse(" 4 "), Ok(TuiCommand::Page(3)));
+        assert_eq!(
+            parse("dmz 192.168.0.20"),
+            Ok(TuiCommand::Dmz("192.168.0.20".to_string()))
+        );
+        assert_eq!(
+            parse("bandlock 1850"),
+            Ok(TuiCommand::BandLock("1850".to_string()))
+        );
+        assert_eq!(
+            parse("mode 4g"),
+            Ok(TuiCommand::NetworkMode(NetworkMode::Lte))
+        );
+        assert!(parse("mode 3g").is_err());
+        assert_eq!(
+            parse("raw  set_ttl 65 "),
+            Ok(TuiCommand::Raw("set_ttl 65".to_string()))
+        );
+        assert!(parse("raw").is_err());
+        assert_eq!(parse("Quit"), Ok(TuiCommand::Quit));
+        assert!(parse("sms 3").is_err());
+        assert!(parse("0").is_err());
+        assert!(parse("reset").is_err());
+    }
+
+    #[test]
+    fn tab_completes_as_far_as_the_names_agree() {
+        assert_eq!(complete("ne").as_deref(), Some("neighbors "));
+        // dashboard, devices and dmz share only the d
+        assert_eq!(complete("d"), None);
+        assert_eq!(complete("da").as_deref(), Some("dashboard "));
+        assert_eq!(candidates("d"), ["dashboard", "dmz", "devices"]);
+        assert_eq!(complete("x"), None);
+    }
+
+    #[test]
+    fn history_is_walked_and_kept_across_runs() {
+        let path = std::env::temp_dir().join(format!("zitel-history-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut line = CommandLine::new(Some(path.clone()));
+        for command in ["dmz", "sms", "sms"] {
+            line.open();
+            line.input = command.to_string();
+            line.submit();
+        }
+
+        let mut line = CommandLine::new(Some(path.clone()));
+        line.open();
+        line.previous();
+        assert_eq!(line.input, "sms");
+        line.previous();
+        line.previous();
+        assert_eq!(line.input, "dmz");
+        line.next();
+        line.next();
+        assert_eq!(line.input, "");
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+
+use crate::client::{parse_api_path, parse_base_url, parse_header};
+
+// ---------- config file ----------
+// Everything here is optional: command line flags (and their environment
+// variables) win over the file, the file wins over built-in defaults.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub base_url: Option<String>,
+    /// Where API commands are posted, for firmware other than the usual.
+    pub api_path: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Accept a self-signed HTTPS certificate.
+    pub insecure: bool,
+    pub default_dmz_ip: Option<String>,
+    pub utc: bool,
+    /// Only ASCII in tables and the interactive view, see --ascii.
+    pub ascii: bool,
+    /// Seconds a cached session token is reused for.
+    pub token_ttl: Option<u64>,
+    /// Seconds between refreshes in the interactive view.
+    pub refresh_interval: Option<u64>,
+    /// Percentage by which waits between polls are moved, see --jitter.
+    pub jitter: Option<u8>,
+    /// Tabs of the interactive view, by name and in order.
+    pub pages: Vec<String>,
+    /// What `zitel status` prints, see `zitel status --help`.
+    pub status_template: Option<String>,
+    /// What `zitel bandscan` compares when no EARFCNs are given.
+    pub band_scan_earfcns: Vec<u32>,
+    /// Seconds `zitel bandscan` waits after each lock.
+    pub band_scan_settle: Option<u64>,
+    /// The plan's data cap in GB, for `zitel monitor`.
+    pub data_cap_gb: Option<f64>,
+    /// Percentages of the cap `zitel monitor` warns at.
+    pub data_cap_alerts: Vec<u8>,
+    /// Run at every data cap warning, see `zitel monitor --help`.
+    pub data_cap_hook: Option<String>,
+    /// Where changes sent to the router are recorded, see `zitel history`.
+    pub history_file: Option<PathBuf>,
+    /// Extra or replacement HTTP headers, `Name = "Value"`.
+    pub headers: BTreeMap<String, String>,
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config").join("zitel").join("config.toml"))
+}
+
+/// Sets `base_url` in the config file, creating it if need be. Only that
+/// line is touched, the rest of the file and its comments stay as they are.
+pub fn save_base_url(path: &Path, base_url: &str) -> Result<(), Box<dyn Error>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Cannot read {}: {}", path.display(), e).into()),
+    };
+    let line = format!("base_url = {:?}", base_url);
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    // keys before the first [table] are the top-level ones
+    let top_level = lines
+        .iter()
+        .position(|l| l.trim_start().starts_with('['))
+        .unwrap_or(lines.len());
+    let existing = lines[..top_level].iter().position(|l| {
+        l.split_once('=')
+            .is_some_and(|(key, _)| key.trim() == "base_url")
+    });
+    match existing {
+        Some(i) => lines[i] = line,
+        None => lines.insert(0, line),
+    }
+    let text = format!("{}\n", lines.join("\n"));
+    toml::from_str::<Config>(&text)
+        .map_err(|e| format!("Not saving, {} would not be valid: {}", path.display(), e))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, text).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+impl Config {
+    /// Loads `path`, or the default location when none is given. A missing
+    /// file at the default location just means "no config".
+    pub fn load(path: Option<&Path>) -> Result<Self, Box<dyn Error>> {
+        let (path, explicit) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !explicit => {
+                return Ok(Self::default())
+            }
+            Err(e) => return Err(format!("Cannot read {}: {}", path.display(), e).into()),
+        };
+
+        let mut config: Config = toml::from_str(&text)
+            .map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+        if let Some(base_url) = &config.base_url {
+            let base_url = parse_base_url(base_url)
+                .map_err(|e| format!("{}: base_url {}", path.display(), e))?;
+            config.base_url = Some(base_url);
+        }
+        if let Some(api_path) = &config.api_path {
+            let api_path = parse_api_path(api_path)
+                .map_err(|e| format!("{}: api_path {}", path.display(), e))?;
+            config.api_path = Some(api_path);
+        }
+        Ok(config)
+    }
+
+    pub fn header_map(&self) -> Result<HeaderMap, Box<dyn Error>> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let (name, value) = parse_header(&format!("{}: {}", name, value))?;
+            headers.append(name, value);
+        }
+        Ok(headers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saving_the_base_url_keeps_the_rest_of_the_file() {
+        let path = std::env::temp_dir().join(format!("zitel-config-{}.toml", std::process::id()));
+        let original = "# my router\nusername = \"admin\"\nbase_url = \"http://10.0.0.1\"\n\n[headers]\nbase_url = \"kept\"\n";
+        fs::write(&path, original).unwrap();
+        save_base_url(&path, "http://192.168.1.1").unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            text,
+            original.replace("http://10.0.0.1", "http://192.168.1.1")
+        );
+
+        fs::remove_file(&path).unwrap();
+        save_base_url(&path, "http://192.168.0.1").unwrap();
+        let config = Config::load(Some(&path)).unwrap();
+        assert_eq!(config.base_url.as_deref(), Some("http://192.168.0.1"));
+        fs::remove_file(&path).unwrap();
+    }
+}
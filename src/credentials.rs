@@ -0,0 +1,79 @@
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+
+use clap::ValueEnum;
+
+use crate::sha256;
+
+// ---------- login credentials ----------
+// Deliberately no Debug/Display so the password cannot end up in a log line.
+#[derive(Clone)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// How the password goes into the login body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum AuthMode {
+    /// `authenticate USER PASSWORD`, what most firmware expects
+    Plain,
+    /// `authenticate USER SHA256(USER:PASSWORD)` in hex, for newer firmware
+    Hashed,
+    /// Plain, then hashed if the router rejects the plain login's format
+    Auto,
+}
+
+impl Credentials {
+    /// The body `authenticate.leano` expects; `Auto` starts out plain.
+    pub fn auth_body(&self, mode: AuthMode) -> String {
+        match mode {
+            AuthMode::Plain | AuthMode::Auto => {
+                format!("authenticate {} {}", self.username, self.password)
+            }
+            AuthMode::Hashed => {
+                let secret = format!("{}:{}", self.username, self.password);
+                format!(
+                    "authenticate {} {}",
+                    self.username,
+                    sha256::hex_digest(secret.as_bytes())
+                )
+            }
+        }
+    }
+}
+
+fn prompt_username() -> io::Result<String> {
+    eprint!("Router username: ");
+    io::stderr().flush()?;
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+/// Flags and environment first, then prompts for whatever is still missing.
+pub fn resolve(
+    username: Option<String>,
+    password: Option<String>,
+) -> Result<Credentials, Box<dyn Error>> {
+    let username = match username {
+        Some(username) => username,
+        None => prompt_username()?,
+    };
+    if username.trim().is_empty() {
+        return Err("Username must not be empty (use --username or ZITEL_USER)".into());
+    }
+    if username.contains(char::is_whitespace) {
+        return Err("Username must not contain spaces".into());
+    }
+
+    let password = match password {
+        Some(password) => password,
+        None => rpassword::prompt_password(format!("Password for {}: ", username))?,
+    };
+    if password.is_empty() {
+        return Err("Password must not be empty (use --password or ZITEL_PASS)".into());
+    }
+
+    Ok(Credentials { username, password })
+}
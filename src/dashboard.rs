@@ -0,0 +1,1024 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::iter;
+use std::path::Path;
+use std::time::Duration;
+
+use serde_json::{json, Map, Value};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::band_lock::{LockStatus, STATUS_LABEL};
+use crate::charset;
+use crate::cli::OutputFormat;
+use crate::client::RouterClient;
+use crate::color;
+use crate::devices;
+use crate::error::ZitelError;
+use crate::fields::{aliased_parse, field, field_parse};
+use crate::logging::redacted;
+use crate::neighbors;
+use crate::network_mode::Technology;
+use crate::operator;
+use crate::pager;
+use crate::report;
+use crate::signal::{self, Quality};
+use crate::sim::parse_sim_status;
+use crate::terminal;
+use crate::timestamp;
+use crate::usage::Usage;
+
+// ---------- dashboard fields ----------
+// Every plain value on the dashboard, shared by the TUI panels and the
+// one-shot `dashboard` command so both read the same keys. `name` is the key
+// used in JSON output.
+pub const RSRP_KEY: &str = "RSRP|lte.rsrp";
+// The byte counters are misspelled on the firmware we know; the corrected
+// spellings are read too so a firmware fix does not hide the data usage.
+pub const RECEIVED_KEY: &str = "recieve|receive|received";
+pub const SENT_KEY: &str = "sentt|sent";
+
+pub struct Field {
+    pub label: &'static str,
+    pub name: &'static str,
+    pub spec: &'static str,
+}
+
+const fn f(label: &'static str, name: &'static str, spec: &'static str) -> Field {
+    Field { label, name, spec }
+}
+
+pub const CONNECTION_FIELDS: &[Field] = &[f("Type", "type", "TYPE"), f("APN", "apn", "APN|apn")];
+pub const INTERNET_KEY: &str = "INTERNET";
+
+pub const NETWORK_FIELDS: &[Field] = &[
+    f("Band", "band", "BAND|lte.band"),
+    f("CSQ", "csq", "CSQ"),
+    f("RSRP", "rsrp", RSRP_KEY),
+    f("RSRQ", "rsrq", "RSRQ|lte.rsrq"),
+    f("SINR", "sinr", "SINR|lte.sinr"),
+    f("RSSI", "rssi", "RSSI|lte.rssi"),
+];
+
+pub const CELL_FIELDS: &[Field] = &[
+    f("Modem Call Control", "mcc", "MCC"),
+    f("MNC", "mnc", "MNC"),
+    f("PCI", "pci", "PCID|lte.pci"),
+    f("EARFCN", "earfcn", "EARFCN|lte.earfcn"),
+    f("Technical Assistance Center (TAC)", "tac", "TAC"),
+    f("eNodeB", "enodeb", "ENODE"),
+    f("Cell ID", "cell_id", "CELL"),
+];
+
+pub const IP_FIELDS: &[Field] = &[
+    f("Public IP", "ipv4", "IPV4|wan_ipaddr"),
+    f("IPv6", "ipv6", "IPV6|wan_ipv6addr"),
+];
+
+pub const SYSTEM_FIELDS: &[Field] = &[
+    f("Model", "model", "model"),
+    f("Serial", "serial", "serial"),
+    f("IMEI", "imei", "IMEI|imei"),
+    f("IMSI", "imsi", "IMSI|imsi"),
+    f("ICCID", "iccid", "ICCID|iccid"),
+    f("Hardware", "hardware", "hardv"),
+    f("Software", "software", "sofv"),
+    f("RAM (MB)", "ram_mb", "ram"),
+    f("CPU1 %", "cpu1_pct", "cpu1"),
+    f("CPU2 %", "cpu2_pct", "cpu2"),
+];
+
+// Interface counters beyond bytes; only some firmware reports them, rows
+// missing from the response are left out rather than shown empty.
+pub const WAN_STAT_FIELDS: &[Field] = &[
+    f(
+        "RX Packets",
+        "rx_packets",
+        "rx_packets|wan_rx_packets|wan.rx_packets",
+    ),
+    f(
+        "TX Packets",
+        "tx_packets",
+        "tx_packets|wan_tx_packets|wan.tx_packets",
+    ),
+    f(
+        "RX Errors",
+        "rx_errors",
+        "rx_errors|wan_rx_errors|wan.rx_errors",
+    ),
+    f(
+        "TX Errors",
+        "tx_errors",
+        "tx_errors|wan_tx_errors|wan.tx_errors",
+    ),
+    f(
+        "RX Drops",
+        "rx_dropped",
+        "rx_dropped|rx_drops|wan_rx_dropped|wan.rx_dropped",
+    ),
+    f(
+        "TX Drops",
+        "tx_dropped",
+        "tx_dropped|tx_drops|wan_tx_dropped|wan.tx_dropped",
+    ),
+];
+
+// Seconds on the wire, shown as "3d 4h 12m 5s".
+pub const UPTIME_FIELDS: &[Field] = &[
+    f("Uptime", "uptime_s", "SYSUP"),
+    f("WAN Uptime", "wan_uptime_s", "WANUP"),
+];
+
+/// The field with this JSON `name`, from any dashboard section.
+pub fn find_field(name: &str) -> Option<&'static Field> {
+    [
+        CONNECTION_FIELDS,
+        NETWORK_FIELDS,
+        CELL_FIELDS,
+        IP_FIELDS,
+        SYSTEM_FIELDS,
+        UPTIME_FIELDS,
+        WAN_STAT_FIELDS,
+    ]
+    .into_iter()
+    .flatten()
+    .find(|f| f.name == name)
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+    const GB: u64 = MB * 1024;
+
+    if bytes >= GB {
+        format!("{:.2} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.2} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.2} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+/// A bit rate in decimal units, the way link speeds are quoted.
+pub fn format_rate(bits_per_sec: f64) -> String {
+    const KBPS: f64 = 1_000.0;
+    const MBPS: f64 = KBPS * 1_000.0;
+    const GBPS: f64 = MBPS * 1_000.0;
+
+    if bits_per_sec >= GBPS {
+        format!("{:.2} Gbps", bits_per_sec / GBPS)
+    } else if bits_per_sec >= MBPS {
+        format!("{:.2} Mbps", bits_per_sec / MBPS)
+    } else if bits_per_sec >= KBPS {
+        format!("{:.2} kbps", bits_per_sec / KBPS)
+    } else {
+        format!("{:.0} bps", bits_per_sec)
+    }
+}
+
+/// "3d 4h 12m 5s", starting at the largest unit that is not zero.
+pub fn format_duration(secs: u64) -> String {
+    let (days, hours, minutes, secs) = (
+        secs / 86400,
+        secs % 86400 / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+    );
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", secs),
+        (0, 0, _) => format!("{}m {}s", minutes, secs),
+        (0, _, _) => format!("{}h {}m {}s", hours, minutes, secs),
+        _ => format!("{}d {}h {}m {}s", days, hours, minutes, secs),
+    }
+}
+
+/// Whole seconds at `spec`; fractions are dropped and negatives refused.
+pub fn seconds(data: &Value, spec: &str) -> Option<u64> {
+    field_parse::<f64>(data, spec)
+        .filter(|s| s.is_finite() && *s >= 0.0)
+        .map(|s| s as u64)
+}
+
+pub fn cpu_average(data: &Value) -> Option<f64> {
+    let c1 = field_parse::<f64>(data, "cpu1")?;
+    let c2 = field_parse::<f64>(data, "cpu2")?;
+    Some((c1 + c2) / 2.0)
+}
+
+// ---------- sections ----------
+pub struct Row {
+    pub label: &'static str,
+    pub name: &'static str,
+    /// What the JSON output carries: the router's value, or a number for
+    /// derived rows.
+    pub value: Value,
+    /// What the text table shows, `None` when the router left it out.
+    pub text: Option<String>,
+}
+
+impl Row {
+    fn from_field(data: &Value, field_def: &Field) -> Self {
+        let text = field(data, field_def.spec).map(String::from);
+        Self {
+            label: field_def.label,
+            name: field_def.name,
+            value: text.clone().map_or(Value::Null, Value::String),
+            text,
+        }
+    }
+
+    fn derived(
+        label: &'static str,
+        name: &'static str,
+        value: Value,
+        text: Option<String>,
+    ) -> Self {
+        Self {
+            label,
+            name,
+            value,
+            text,
+        }
+    }
+}
+
+pub struct Section {
+    pub title: &'static str,
+    pub name: &'static str,
+    pub rows: Vec<Row>,
+}
+
+/// Shown instead of a section, or a TUI panel, the router sent nothing for.
+pub const NOT_AVAILABLE: &str = "(data not available yet)";
+
+impl Section {
+    /// Whether the router reported any of the rows; right after the modem
+    /// attaches whole groups of keys can be missing.
+    pub fn has_data(&self) -> bool {
+        self.rows.iter().any(|row| row.text.is_some())
+    }
+}
+
+/// Whether `data` has any of `fields`, for the TUI panels.
+pub fn any_reported(data: &Value, fields: &[Field]) -> bool {
+    fields.iter().any(|f| field(data, f.spec).is_some())
+}
+
+fn field_rows(data: &Value, fields: &[Field]) -> Vec<Row> {
+    fields.iter().map(|f| Row::from_field(data, f)).collect()
+}
+
+fn duration_row(data: &Value, field_def: &Field) -> Row {
+    let secs = seconds(data, field_def.spec);
+    Row::derived(
+        field_def.label,
+        field_def.name,
+        secs.into(),
+        secs.map(format_duration),
+    )
+}
+
+fn bytes_row(data: &Value, label: &'static str, name: &'static str, spec: &str) -> Row {
+    let bytes = aliased_parse::<u64>(data, spec);
+    Row::derived(label, name, bytes.into(), bytes.map(format_bytes))
+}
+
+pub const SECTION_COUNT: usize = 6;
+
+/// The dashboard as six sections of label/value rows: data usage,
+/// connection, network, cell, IP and system.
+pub fn sections(data: &Value) -> Vec<Section> {
+    let mut usage = vec![
+        bytes_row(data, "Received", "received_bytes", RECEIVED_KEY),
+        bytes_row(data, "Sent", "sent_bytes", SENT_KEY),
+        {
+            let total = Usage::read(data).total();
+            Row::derived(
+                "Total",
+                "total_bytes",
+                total.into(),
+                total.map(format_bytes),
+            )
+        },
+    ];
+
+    // a firmware reports these always or never, so CSV columns stay put
+    usage.extend(
+        field_rows(data, WAN_STAT_FIELDS)
+            .into_iter()
+            .filter(|row| row.text.is_some()),
+    );
+
+    let mut connection = field_rows(data, CONNECTION_FIELDS);
+    connection.push(Row::from_field(
+        data,
+        &f("Internet", "internet", INTERNET_KEY),
+    ));
+    // always present (null when not reported) so CSV columns stay fixed
+    let sim = parse_sim_status(data);
+    connection.push(Row::derived(
+        "SIM",
+        "sim",
+        sim.as_ref().map_or(Value::Null, |sim| sim.label().into()),
+        sim.map(|sim| sim.description()),
+    ));
+
+    let operator = operator::describe(data);
+    let mut network = vec![Row::derived(
+        "Operator",
+        "operator",
+        operator.clone().map_or(Value::Null, Value::String),
+        operator,
+    )];
+    network.extend(field_rows(data, NETWORK_FIELDS));
+
+    let mut system = field_rows(data, SYSTEM_FIELDS);
+    system.extend(UPTIME_FIELDS.iter().map(|f| duration_row(data, f)));
+    let cpu_avg = cpu_average(data);
+    system.push(Row::derived(
+        "CPU Avg %",
+        "cpu_avg_pct",
+        cpu_avg.into(),
+        cpu_avg.map(|avg| format!("{:.1}", avg)),
+    ));
+
+    vec![
+        Section {
+            title: "Data Usage",
+            name: "data_usage",
+            rows: usage,
+        },
+        Section {
+            title: "Connection",
+            name: "connection",
+            rows: connection,
+        },
+        Section {
+            title: "Network",
+            name: "network",
+            rows: network,
+        },
+        Section {
+            title: "Cell",
+            name: "cell",
+            rows: field_rows(data, CELL_FIELDS),
+        },
+        Section {
+            title: "IP",
+            name: "ip",
+            rows: field_rows(data, IP_FIELDS),
+        },
+        Section {
+            title: "System",
+            name: "system",
+            rows: system,
+        },
+    ]
+}
+
+// ---------- text tables ----------
+// Column widths on a wide terminal, and when the width is unknown (piped
+// output). Narrower terminals get both columns shrunk, down to the minimum.
+const LABEL_WIDTH: usize = 34;
+const VALUE_WIDTH: usize = 28;
+const MIN_COLUMN_WIDTH: usize = 8;
+// "| " + " | " + " |" around every row
+const ROW_FRAME: usize = 7;
+
+/// Terminal columns `text` takes up: CJK characters and most emoji take two,
+/// combining marks none.
+fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// Cuts `text` to `width` terminal columns, marking the cut with an ellipsis.
+fn fit(text: &str, width: usize) -> Cow<'_, str> {
+    if display_width(text) <= width {
+        return Cow::Borrowed(text);
+    }
+    let room = width.saturating_sub(1);
+    let mut kept = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > room {
+            break;
+        }
+        used += w;
+        kept.push(c);
+    }
+    Cow::Owned(format!("{}{}", kept, charset::ellipsis()))
+}
+
+/// `text` cut or padded to exactly `width` columns; `{:<width$}` would count
+/// characters, which puts the border off after a wide one.
+fn pad(text: &str, width: usize) -> String {
+    let text = charset::text(text);
+    let text = fit(&text, width);
+    let fill = width.saturating_sub(display_width(&text));
+    format!("{}{}", text, " ".repeat(fill))
+}
+
+/// Label and value widths for a terminal `width` columns wide. The value
+/// column gives way first, it is mostly short numbers.
+fn table_widths(width: Option<usize>) -> (usize, usize) {
+    let Some(width) = width else {
+        return (LABEL_WIDTH, VALUE_WIDTH);
+    };
+    let room = width.saturating_sub(ROW_FRAME);
+    let value = VALUE_WIDTH
+        .min(room.saturating_sub(LABEL_WIDTH))
+        .max(20.min(room / 2));
+    let label = LABEL_WIDTH.min(room.saturating_sub(value));
+    (label.max(MIN_COLUMN_WIDTH), value.max(MIN_COLUMN_WIDTH))
+}
+
+// The value is padded before it is coloured so escape codes do not count
+// towards the column width.
+fn table_row(
+    (label_width, value_width): (usize, usize),
+    label: &str,
+    value: &str,
+    quality: Option<Quality>,
+) -> String {
+    let value = pad(value, value_width);
+    let value = match quality {
+        Some(quality) => color::paint(&value, quality),
+        None => value,
+    };
+    format!("| {} | {} |", pad(label, label_width), value)
+}
+
+// The connection type as its badge, with what the router sent after it when
+// that reads differently. Only the badge is coloured, not the padding.
+fn type_row((label_width, value_width): (usize, usize), label: &str, reported: &str) -> String {
+    let Some(technology) = Technology::parse(reported) else {
+        return table_row((label_width, value_width), label, reported, None);
+    };
+    let badge = technology.label();
+    let text = match technology.spelling(reported) {
+        Some(spelling) => format!("{} ({})", badge, spelling),
+        None => badge.to_string(),
+    };
+    let text = pad(&text, value_width);
+    let value = match text.strip_prefix(badge) {
+        Some(rest) => format!("{}{}", color::badge(badge, technology), rest),
+        // cut short by a narrow terminal
+        None => text,
+    };
+    format!("| {} | {} |", pad(label, label_width), value)
+}
+
+// ---------- band lock ----------
+/// Adds the band lock zitel last set after the connection type, for the
+/// printed and JSON dashboards; the CSV and HTML exports keep their columns.
+pub fn add_band_lock(sections: &mut [Section], data: &Value, lock: &LockStatus) {
+    let Some(connection) = sections.iter_mut().find(|s| s.name == "connection") else {
+        return;
+    };
+    let at = connection
+        .rows
+        .iter()
+        .position(|row| row.name == "type")
+        .map_or(0, |i| i + 1);
+    let lock = lock.checked(data);
+    let text = (lock != LockStatus::Unknown).then(|| lock.describe());
+    let row = Row::derived(STATUS_LABEL, "last_band_lock", lock.to_json(), text);
+    connection.rows.insert(at, row);
+}
+
+/// The colour a row's value is shown in, if any: a band lock is yellow so
+/// it is not missed.
+pub fn row_quality(row: &Row, text: &str) -> Option<Quality> {
+    match row.name {
+        "last_band_lock" => (row.value["locked"] == true).then_some(Quality::Fair),
+        name => signal::quality(name, text),
+    }
+}
+
+/// The sections as boxed tables no wider than `width` terminal columns,
+/// the fixed default width when it is `None`.
+pub fn render_text(sections: &[Section], width: Option<usize>) -> String {
+    let widths = table_widths(width);
+    let (label_width, value_width) = widths;
+    let inner = label_width + value_width + 5;
+    let rule = format!(
+        "+{}+{}+\n",
+        "-".repeat(label_width + 2),
+        "-".repeat(value_width + 2)
+    );
+    let mut out = String::new();
+    for section in sections {
+        out.push_str(&format!("+{}+\n", "-".repeat(inner)));
+        out.push_str(&format!("| {} |\n", pad(section.title, inner - 2)));
+        if !section.has_data() {
+            let full_rule = format!("+{}+\n", "-".repeat(inner));
+            out.push_str(&full_rule);
+            out.push_str(&format!("| {} |\n", pad(NOT_AVAILABLE, inner - 2)));
+            out.push_str(&full_rule);
+            out.push('\n');
+            continue;
+        }
+        out.push_str(&rule);
+        for row in &section.rows {
+            let text = row.text.as_deref().unwrap_or("-");
+            let quality = row_quality(row, text);
+            if row.name == "type" && row.text.is_some() {
+                out.push_str(&type_row(widths, row.label, text));
+                out.push('\n');
+                continue;
+            }
+            match signal::bar_range(row.name) {
+                Some((min, max)) => {
+                    let gauge = format!("{:<8} {}", text, signal::signal_bar(text, min, max));
+                    out.push_str(&table_row(widths, row.label, &gauge, quality));
+                }
+                None => out.push_str(&table_row(widths, row.label, text, quality)),
+            }
+            out.push('\n');
+        }
+        out.push_str(&rule);
+        out.push('\n');
+    }
+    out
+}
+
+/// A boxed table in the same style with a header row and any number of
+/// columns, each as wide as its widest cell. When that is wider than
+/// `width`, the widest columns are narrowed and their cells cut.
+pub fn render_columns(
+    title: &str,
+    headers: &[&str],
+    rows: &[Vec<String>],
+    width: Option<usize>,
+) -> String {
+    let mut widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .map(|cell| display_width(cell))
+                .fold(display_width(header), usize::max)
+        })
+        .collect();
+    if let Some(width) = width {
+        let frame = 3 * widths.len() + 1;
+        while widths.iter().sum::<usize>() + frame > width {
+            match widths.iter_mut().max() {
+                Some(widest) if *widest > MIN_COLUMN_WIDTH => *widest -= 1,
+                _ => break,
+            }
+        }
+    }
+
+    let rule = widths.iter().fold("+".to_string(), |rule, w| {
+        format!("{}{}+", rule, "-".repeat(w + 2))
+    });
+    let line = |cells: &mut dyn Iterator<Item = &str>| {
+        widths.iter().fold("|".to_string(), |line, w| {
+            format!("{} {} |", line, pad(cells.next().unwrap_or(""), *w))
+        })
+    };
+    let inner = rule.len() - 2;
+
+    let mut out = format!("+{}+\n", "-".repeat(inner));
+    out.push_str(&format!("| {} |\n", pad(title, inner - 2)));
+    out.push_str(&format!("{}\n", rule));
+    out.push_str(&format!("{}\n", line(&mut headers.iter().copied())));
+    out.push_str(&format!("{}\n", rule));
+    for row in rows {
+        out.push_str(&format!("{}\n", line(&mut row.iter().map(String::as_str))));
+    }
+    out.push_str(&format!("{}\n", rule));
+    out
+}
+
+pub fn to_json(sections: &[Section]) -> Value {
+    let mut out = Map::new();
+    for section in sections {
+        let rows = section
+            .rows
+            .iter()
+            .map(|row| (row.name.to_string(), row.value.clone()))
+            .collect::<Map<_, _>>();
+        out.insert(section.name.to_string(), Value::Object(rows));
+    }
+    Value::Object(out)
+}
+
+// ---------- CSV ----------
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn csv_line(fields: impl IntoIterator<Item = String>) -> String {
+    let fields: Vec<String> = fields.into_iter().map(|f| csv_field(&f)).collect();
+    format!("{}\n", fields.join(","))
+}
+
+/// Appends the dashboard as one row, `time` first and then every known field.
+/// A new or empty file gets the header line first.
+pub fn export_csv(path: &Path, sections: &[Section]) -> Result<(), Box<dyn Error>> {
+    let rows = || sections.iter().flat_map(|section| &section.rows);
+    let header = csv_line(iter::once("time".to_string()).chain(rows().map(|r| r.name.to_string())));
+
+    let existing = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Cannot read {}: {}", path.display(), e).into()),
+    };
+    if let Some(first) = existing.lines().next() {
+        if format!("{}\n", first) != header {
+            return Err(format!(
+                "{} already has different columns, use a new file",
+                path.display()
+            )
+            .into());
+        }
+    }
+
+    let values = rows().map(|row| match &row.value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    });
+    let mut out = String::new();
+    if existing.is_empty() {
+        out.push_str(&header);
+    }
+    out.push_str(&csv_line(iter::once(timestamp::now_iso()).chain(values)));
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(out.as_bytes())?;
+    Ok(())
+}
+
+// ---------- one-shot command ----------
+const PARTIAL_DATA_RETRY: Duration = Duration::from_secs(1);
+
+/// `get_index_data`, asked for once more after a moment when it lacks RSRP:
+/// while the connection comes up some firmware answers before the radio
+/// values are in. Whatever the second answer has is used.
+pub async fn fetch_index_data(client: &RouterClient) -> Result<Value, ZitelError> {
+    let data = client.api_request("get_index_data").await?;
+    if field(&data, RSRP_KEY).is_some() {
+        return Ok(data);
+    }
+    log::debug!("get_index_data has no RSRP yet, asking again");
+    tokio::time::sleep(PARTIAL_DATA_RETRY).await;
+    client.api_request("get_index_data").await
+}
+
+/// Prints the dashboard, or with `page` (1-based) only that section.
+pub async fn run(
+    client: &RouterClient,
+    csv: Option<&Path>,
+    html: Option<&Path>,
+    page: Option<u8>,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let response = fetch_index_data(client).await?;
+    let export = redacted(&response, true);
+    if let Some(path) = csv {
+        export_csv(path, &sections(&export))?;
+        eprintln!("Appended dashboard row to {}", path.display());
+        return Ok(());
+    }
+    if let Some(path) = html {
+        let generated_at = timestamp::now_display();
+        report::export_html(path, &sections(&export), client.base_url(), &generated_at)?;
+        eprintln!("Wrote dashboard report to {}", path.display());
+        return Ok(());
+    }
+    let data = redacted(&response, false);
+    let mut shown = sections(&data);
+    add_band_lock(&mut shown, &data, &LockStatus::read(client));
+    if let Some(page) = page {
+        shown = shown
+            .into_iter()
+            .skip(usize::from(page) - 1)
+            .take(1)
+            .collect();
+    }
+    match format {
+        OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&*data)?),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&to_json(&shown))?),
+        OutputFormat::Text => pager::print(&render_text(&shown, terminal::width())),
+    }
+    Ok(())
+}
+
+/// The dashboard with the neighbour cells and connected devices, each
+/// answer on its own: one that fails does not lose the others.
+pub struct Snapshot {
+    pub index_data: Result<Value, ZitelError>,
+    pub neighbour_cells: Result<Value, ZitelError>,
+    pub devices: Result<Value, ZitelError>,
+}
+
+/// Asks for all three at once, so a snapshot takes about as long as the
+/// slowest answer rather than the sum of them.
+pub async fn fetch_snapshot(client: &RouterClient) -> Snapshot {
+    let (index_data, neighbour_cells, devices) = tokio::join!(
+        fetch_index_data(client),
+        client.api_request("get_neighbour_cell"),
+        client.connected_devices()
+    );
+    Snapshot {
+        index_data,
+        neighbour_cells,
+        devices,
+    }
+}
+
+/// `dashboard --all`: prints what the snapshot got, warns about the rest and
+/// fails only when nothing could be read.
+pub async fn run_all(client: &RouterClient, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let snapshot = fetch_snapshot(client).await;
+    let parts = [
+        ("Dashboard", &snapshot.index_data),
+        ("Neighbour cells", &snapshot.neighbour_cells),
+        ("Connected devices", &snapshot.devices),
+    ];
+    for (name, result) in parts {
+        if let Err(e) = result {
+            eprintln!("Warning: {} left out: {}", name, e);
+        }
+    }
+    if parts.iter().all(|(_, result)| result.is_err()) {
+        return Err("The router answered none of the requests".into());
+    }
+
+    let data = snapshot
+        .index_data
+        .as_ref()
+        .ok()
+        .map(|data| redacted(data, false).into_owned());
+    let serving = data.as_ref().and_then(neighbors::serving_rsrp);
+    let cells = snapshot
+        .neighbour_cells
+        .as_ref()
+        .ok()
+        .map(neighbors::ranked_cells);
+    let devices = snapshot.devices.as_ref().ok().map(devices::parse_devices);
+    // a part that failed is there as its error
+    let or_error = |part: Option<Value>, result: &Result<Value, ZitelError>| match result {
+        Ok(answer) => part.unwrap_or_else(|| answer.clone()),
+        Err(e) => json!({ "error": e.to_string() }),
+    };
+    match format {
+        OutputFormat::Raw => {
+            let result = json!({
+                "get_index_data": or_error(data.clone(), &snapshot.index_data),
+                "get_neighbour_cell": or_error(None, &snapshot.neighbour_cells),
+                "get_dhcp_list": or_error(None, &snapshot.devices),
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Json => {
+            let dashboard = data.as_ref().map(|data| to_json(&sections(data)));
+            let cells = cells
+                .as_ref()
+                .map(|cells| cells.iter().map(|c| c.to_json()).collect());
+            let devices = devices
+                .as_ref()
+                .map(|list| list.iter().map(|d| d.to_json()).collect());
+            let result = json!({
+                "dashboard": or_error(dashboard, &snapshot.index_data),
+                "neighbour_cells": or_error(cells, &snapshot.neighbour_cells),
+                "devices": or_error(devices, &snapshot.devices),
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text => {
+            let parts = [
+                data.map(|data| render_text(&sections(&data), terminal::width())),
+                cells.map(|cells| neighbors::table(&cells, serving)),
+                devices.map(|devices| devices::table(&devices)),
+            ];
+            let parts: Vec<&str> = parts.iter().flatten().map(|p| p.trim_end()).collect();
+            pager::print(&format!("{}\n", parts.join("\n\n")));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("-95"), "-95");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn csv_header_is_written_once() {
+        let path = env::temp_dir().join(format!("zitel-csv-test-{}.csv", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let data = json!({ "RSRP": "-95", "SINR": 12, "recieve": "2048" });
+
+        export_csv(&path, &sections(&data)).unwrap();
+        export_csv(&path, &sections(&data)).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("time,received_bytes,sent_bytes,total_bytes,type,"));
+        assert!(lines[1].contains(",2048,,"));
+        assert_eq!(lines[1].split(',').count(), lines[0].split(',').count());
+        assert!(lines[2].contains("-95"));
+    }
+
+    #[test]
+    fn columns_are_as_wide_as_their_widest_cell() {
+        let rows = vec![vec!["laptop".to_string(), "192.168.0.20".to_string()]];
+        let table = render_columns("Devices", &["Hostname", "IP"], &rows, None);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[2], "+----------+--------------+");
+        assert_eq!(lines[3], "| Hostname | IP           |");
+        assert_eq!(lines[5], "| laptop   | 192.168.0.20 |");
+        assert!(lines.iter().all(|l| l.len() == lines[0].len()));
+    }
+
+    #[test]
+    fn durations_roll_over_at_unit_boundaries() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(59), "59s");
+        assert_eq!(format_duration(60), "1m 0s");
+        assert_eq!(format_duration(3599), "59m 59s");
+        assert_eq!(format_duration(3600), "1h 0m 0s");
+        assert_eq!(format_duration(86399), "23h 59m 59s");
+        assert_eq!(format_duration(86400), "1d 0h 0m 0s");
+        assert_eq!(
+            format_duration(3 * 86400 + 4 * 3600 + 12 * 60 + 5),
+            "3d 4h 12m 5s"
+        );
+    }
+
+    #[test]
+    fn uptime_keeps_raw_seconds_for_json() {
+        let data = json!({ "SYSUP": "90061", "WANUP": "-5" });
+        let json = to_json(&sections(&data));
+        assert_eq!(json["system"]["uptime_s"], 90061);
+        assert_eq!(json["system"]["wan_uptime_s"], Value::Null);
+        assert_eq!(seconds(&json!({ "SYSUP": 12.9 }), "SYSUP"), Some(12));
+    }
+
+    #[test]
+    fn tables_shrink_to_the_terminal() {
+        let data =
+            json!({ "RSRP": "-95", "model": "a model name that is far too long for the column" });
+        for width in [40, 60, 80, 200] {
+            let text = render_text(&sections(&data), Some(width));
+            assert!(
+                text.lines().all(|l| l.chars().count() <= width.max(69)),
+                "{}",
+                width
+            );
+            let lengths: Vec<usize> = text
+                .lines()
+                .filter(|l| !l.is_empty())
+                .map(|l| l.chars().count())
+                .collect();
+            assert!(lengths.iter().all(|&n| n == lengths[0]), "{}", width);
+        }
+        assert!(render_text(&sections(&data), None).contains("| Model "));
+        assert_eq!(fit("abcdef", 4), "abc…");
+    }
+
+    #[test]
+    fn wide_characters_keep_the_borders_straight() {
+        assert_eq!(fit("路由器路由器", 5), "路由…");
+        assert_eq!(pad("路由器", 7), "路由器 ");
+        // a wide character that does not fit whole leaves a space
+        assert_eq!(pad("路由器路由", 6), "路由… ");
+        assert_eq!(display_width("cafe\u{301}"), 4);
+
+        let data = json!({ "model": "路由器 📶 Zitel", "sofv": "ファームウェア".repeat(6) });
+        for width in [None, Some(50)] {
+            let text = render_text(&sections(&data), width);
+            let widths: Vec<usize> = text
+                .lines()
+                .filter(|l| !l.is_empty())
+                .map(display_width)
+                .collect();
+            assert!(widths.iter().all(|&w| w == widths[0]), "{}", text);
+            assert!(text.contains("路由器 📶 Zitel"));
+        }
+
+        let rows = vec![
+            vec!["📱 phone".to_string(), "ok".to_string()],
+            vec!["ノートパソコン".to_string(), "ok".to_string()],
+        ];
+        let table = render_columns("Devices", &["Hostname", "State"], &rows, None);
+        let widths: Vec<usize> = table.lines().map(display_width).collect();
+        assert!(widths.iter().all(|&w| w == widths[0]), "{}", table);
+    }
+
+    #[test]
+    fn wide_columns_give_way_first() {
+        let rows = vec![vec!["tv".to_string(), "x".repeat(60)]];
+        let table = render_columns("Devices", &["Hostname", "Message"], &rows, Some(40));
+        assert!(table.lines().all(|l| l.chars().count() == 40));
+        assert!(table.contains("| Hostname |"));
+    }
+
+    #[test]
+    fn one_response_fills_every_section() {
+        let data = json!({ "RSRP": "-95", "MCC": "262", "model": "ZLT", "IPV4": "10.0.0.2" });
+        let sections = sections(&data);
+        let names: Vec<&str> = sections.iter().map(|s| s.name).collect();
+        assert_eq!(names.len(), SECTION_COUNT);
+        assert_eq!(
+            names,
+            [
+                "data_usage",
+                "connection",
+                "network",
+                "cell",
+                "ip",
+                "system"
+            ]
+        );
+        let text = render_text(&sections, None);
+        for section in &sections {
+            assert!(text.contains(&format!("| {} ", section.title)));
+        }
+        assert!(text.contains("262") && text.contains("ZLT") && text.contains("10.0.0.2"));
+        assert!(text.contains("unknown (262)"));
+    }
+
+    #[test]
+    fn empty_sections_say_so_instead_of_dashes() {
+        let data = json!({ "RSRP": "-95" });
+        let text = render_text(&sections(&data), None);
+        let cell = text.split("| Cell ").nth(1).unwrap();
+        let cell = &cell[..cell.find("\n\n").unwrap()];
+        assert!(cell.contains(NOT_AVAILABLE), "{}", cell);
+        assert!(!cell.contains("| -"), "{}", cell);
+        let network = text.split("| Network ").nth(1).unwrap();
+        assert!(!network[..network.find("\n\n").unwrap()].contains(NOT_AVAILABLE));
+    }
+
+    #[test]
+    fn wan_counters_show_only_when_reported() {
+        let usage = |data: &Value| -> Vec<&'static str> {
+            sections(data)[0].rows.iter().map(|r| r.name).collect()
+        };
+        assert_eq!(
+            usage(&json!({})),
+            ["received_bytes", "sent_bytes", "total_bytes"]
+        );
+        let data = json!({ "wan": { "rx_packets": 1200, "rx_dropped": 3 } });
+        assert_eq!(usage(&data)[3..], ["rx_packets", "rx_dropped"]);
+    }
+
+    #[test]
+    fn the_band_lock_follows_the_connection_type() {
+        let data = json!({ "TYPE": "LTE", "SYSUP": "86400" });
+        let lock = LockStatus::Locked {
+            earfcn: 1850,
+            since: timestamp::now_iso(),
+        };
+        let mut shown = sections(&data);
+        add_band_lock(&mut shown, &data, &lock);
+        let names: Vec<&str> = shown[1].rows.iter().map(|r| r.name).collect();
+        assert_eq!(names[..2], ["type", "last_band_lock"]);
+        let json = to_json(&shown);
+        assert_eq!(json["connection"]["last_band_lock"]["band"], 3);
+        let row = &shown[1].rows[1];
+        assert_eq!(row_quality(row, ""), Some(Quality::Fair));
+        let text = render_text(&shown, None);
+        assert!(text.contains("| Band lock last set by zitel") && text.contains("EARFCN 1850"));
+
+        // restarted since, so the lock is gone
+        let data = json!({ "TYPE": "LTE", "SYSUP": "0" });
+        let lock = LockStatus::Locked {
+            earfcn: 1850,
+            since: "2026-01-01T00:00:00+00:00".to_string(),
+        };
+        let mut shown = sections(&data);
+        add_band_lock(&mut shown, &data, &lock);
+        assert_eq!(row_quality(&shown[1].rows[1], ""), None);
+
+        let mut shown = sections(&data);
+        add_band_lock(&mut shown, &data, &LockStatus::Unknown);
+        assert_eq!(shown[1].rows[1].text, None);
+    }
+}
@@ -0,0 +1,272 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::process::Command;
+
+use crate::dashboard::format_bytes;
+
+// ---------- data cap ----------
+// Usage against a plan's cap, for `monitor --data-cap`. The router's
+// counters start over when it reboots, so the last total seen is kept on
+// disk: a smaller total means a reset, and what was counted before it is
+// carried over. `zitel usage reset` starts a new cycle and drops the carry.
+pub const DEFAULT_ALERTS: [u8; 2] = [80, 100];
+// GB as the dashboard shows them, so 80% of 50 GB shows as 40.00 GB there
+const GB: f64 = (1u64 << 30) as f64;
+
+pub fn default_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config").join("zitel").join("usage.json"))
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct UsageFile {
+    routers: Vec<CycleState>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct CycleState {
+    base_url: String,
+    /// The router's total at the last sample.
+    last_total: u64,
+    /// Counted before the router's counters last started over.
+    carried: u64,
+    /// Thresholds already warned about this cycle.
+    alerted: Vec<u8>,
+}
+
+fn read(path: &Path) -> io::Result<UsageFile> {
+    match fs::read_to_string(path) {
+        Ok(text) => {
+            serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(UsageFile::default()),
+        Err(e) => Err(e),
+    }
+}
+
+fn write(path: &Path, file: &UsageFile) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(file)?)
+}
+
+/// Parses `--data-cap`, in GB.
+pub fn parse_cap(text: &str) -> Result<f64, String> {
+    match text.trim().parse::<f64>() {
+        Ok(gb) if gb.is_finite() && gb > 0.0 => Ok(gb),
+        _ => Err(format!(
+            "{:?} is not a data cap in GB, e.g. 50 or 2.5",
+            text
+        )),
+    }
+}
+
+/// A threshold crossed by the last sample.
+pub struct Alert {
+    pub threshold: u8,
+    pub used: u64,
+    pub cap: u64,
+}
+
+impl Alert {
+    pub fn percent(&self) -> f64 {
+        self.used as f64 * 100.0 / self.cap as f64
+    }
+
+    pub fn message(&self) -> String {
+        format!(
+            "Data usage at {:.0}% of the cap: {} of {}",
+            self.percent(),
+            format_bytes(self.used),
+            format_bytes(self.cap)
+        )
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "alert": "data_cap",
+            "threshold_pct": self.threshold,
+            "used_pct": self.percent(),
+            "used_bytes": self.used,
+            "cap_bytes": self.cap,
+        })
+    }
+
+    /// The environment the hook command runs with.
+    pub fn env(&self) -> [(&'static str, String); 4] {
+        [
+            ("ZITEL_THRESHOLD", self.threshold.to_string()),
+            ("ZITEL_USAGE_PERCENT", format!("{:.0}", self.percent())),
+            ("ZITEL_USAGE_BYTES", self.used.to_string()),
+            ("ZITEL_CAP_BYTES", self.cap.to_string()),
+        ]
+    }
+}
+
+pub struct DataCap {
+    cap: u64,
+    /// Ascending, each warned about once per cycle.
+    thresholds: Vec<u8>,
+    state: CycleState,
+    path: Option<PathBuf>,
+}
+
+impl DataCap {
+    /// Picks up where the last monitor run on this router left off; without
+    /// a `path` usage is only tracked for this run.
+    pub fn new(cap_gb: f64, thresholds: &[u8], base_url: &str, path: Option<PathBuf>) -> Self {
+        let mut thresholds = thresholds.to_vec();
+        thresholds.sort_unstable();
+        thresholds.dedup();
+        let state = path
+            .as_deref()
+            .and_then(|path| match read(path) {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    log::warn!("Cannot read {}, counting from zero: {}", path.display(), e);
+                    None
+                }
+            })
+            .and_then(|file| file.routers.into_iter().find(|r| r.base_url == base_url))
+            .unwrap_or_else(|| CycleState {
+                base_url: base_url.to_string(),
+                ..CycleState::default()
+            });
+        Self {
+            cap: (cap_gb * GB) as u64,
+            thresholds,
+            state,
+            path,
+        }
+    }
+
+    /// Usage this cycle up to the last sample.
+    fn used(&self) -> u64 {
+        self.state.carried.saturating_add(self.state.last_total)
+    }
+
+    /// Takes the router's total, returning the highest threshold it crossed;
+    /// any lower ones crossed with it are not warned about separately.
+    pub fn update(&mut self, total: u64) -> Option<Alert> {
+        if total < self.state.last_total {
+            log::info!(
+                "Usage counters started over ({} -> {}), carrying {}",
+                self.state.last_total,
+                total,
+                self.state.last_total
+            );
+            self.state.carried = self.state.carried.saturating_add(self.state.last_total);
+        }
+        self.state.last_total = total;
+
+        let used = self.used();
+        let percent = used as f64 * 100.0 / self.cap as f64;
+        let mut alert = None;
+        for &threshold in &self.thresholds {
+            if percent >= f64::from(threshold) && !self.state.alerted.contains(&threshold) {
+                self.state.alerted.push(threshold);
+                alert = Some(Alert {
+                    threshold,
+                    used,
+                    cap: self.cap,
+                });
+            }
+        }
+        if let Err(e) = self.save() {
+            log::warn!("Cannot save the data usage: {}", e);
+        }
+        alert
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut file = read(path).unwrap_or_default();
+        file.routers.retain(|r| r.base_url != self.state.base_url);
+        file.routers.push(self.state.clone());
+        write(path, &file)
+    }
+}
+
+/// Runs the `--cap-hook` command through the shell with the alert in its
+/// environment. A failing hook is reported, the monitor carries on.
+pub async fn run_hook(hook: &str, alert: &Alert) {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", hook]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-c", hook]);
+        command
+    };
+    match command.envs(alert.env()).status().await {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("Data cap hook exited with {}", status),
+        Err(e) => eprintln!("Cannot run the data cap hook: {}", e),
+    }
+}
+
+/// Starts a new cycle for the router after its counters were reset on
+/// purpose.
+pub fn forget(path: &Path, base_url: &str) -> io::Result<()> {
+    let mut file = read(path)?;
+    let before = file.routers.len();
+    file.routers.retain(|r| r.base_url != base_url);
+    match file.routers.len() == before {
+        true => Ok(()),
+        false => write(path, &file),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds_fire_once_and_resets_carry_over() {
+        let mut cap = DataCap::new(1.0, &[100, 50], "http://192.168.0.1", None);
+        let gb = GB as u64;
+        assert!(cap.update(gb / 4).is_none());
+        assert_eq!(cap.update(gb / 2).map(|a| a.threshold), Some(50));
+        assert!(cap.update(gb * 3 / 4).is_none());
+
+        // reboot: the router counts from zero, the cycle does not
+        assert!(cap.update(gb / 8).is_none());
+        let alert = cap.update(gb / 4).unwrap();
+        assert_eq!((alert.threshold, alert.used), (100, gb));
+        assert_eq!(
+            alert.message(),
+            "Data usage at 100% of the cap: 1.00 GB of 1.00 GB"
+        );
+
+        // both crossed at once: one warning, for the higher
+        let mut cap = DataCap::new(1.0, &[50, 100], "http://192.168.0.1", None);
+        assert_eq!(cap.update(gb * 2).map(|a| a.threshold), Some(100));
+        assert!(cap.update(gb * 3).is_none());
+    }
+
+    #[test]
+    fn the_cycle_survives_restarts() {
+        let path = std::env::temp_dir().join(format!("zitel-usage-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let url = "http://192.168.0.1";
+        let gb = GB as u64;
+
+        let mut cap = DataCap::new(1.0, &DEFAULT_ALERTS, url, Some(path.clone()));
+        assert!(cap.update(gb * 81 / 100).is_some());
+        let mut cap = DataCap::new(1.0, &DEFAULT_ALERTS, url, Some(path.clone()));
+        assert!(cap.update(gb * 82 / 100).is_none());
+
+        forget(&path, url).unwrap();
+        let mut cap = DataCap::new(1.0, &DEFAULT_ALERTS, url, Some(path.clone()));
+        assert!(cap.update(gb * 82 / 100).is_some());
+        let _ = fs::remove_file(&path);
+        assert!(parse_cap("0").is_err() && parse_cap("2.5") == Ok(2.5));
+    }
+}
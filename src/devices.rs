@@ -0,0 +1,149 @@
+use std::error::Error;
+
+use serde_json::{json, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::RouterClient;
+use crate::dashboard::{format_duration, render_columns};
+use crate::fields::{field, list_records};
+use crate::terminal;
+
+// ---------- parsing ----------
+const LIST_KEYS: &[&str] = &["devices", "dhcp_list", "clients", "list"];
+const MAX_DEVICES: usize = 256;
+
+const HOSTNAME_SPEC: &str = "hostname|host_name|name";
+const IP_SPEC: &str = "ip|ipaddr|ip_address";
+const MAC_SPEC: &str = "mac|macaddr|mac_address";
+const LEASE_SPEC: &str = "lease|lease_time|expires";
+
+pub struct Device {
+    pub hostname: Option<String>,
+    pub ip: Option<String>,
+    pub mac: Option<String>,
+    /// Remaining lease as the router reports it, seconds on most firmware.
+    pub lease: Option<String>,
+}
+
+impl Device {
+    fn from_entry(entry: &Value) -> Self {
+        let value = |spec| field(entry, spec).map(String::from);
+        Self {
+            hostname: value(HOSTNAME_SPEC),
+            ip: value(IP_SPEC),
+            mac: value(MAC_SPEC),
+            lease: value(LEASE_SPEC),
+        }
+    }
+
+    /// The lease as "1h 2m 5s" when it is a number of seconds.
+    pub fn lease_text(&self) -> Option<String> {
+        let lease = self.lease.as_deref()?;
+        Some(match lease.trim().parse::<u64>() {
+            Ok(secs) => format_duration(secs),
+            Err(_) => lease.to_string(),
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "hostname": self.hostname,
+            "ip": self.ip,
+            "mac": self.mac,
+            "lease": self.lease,
+        })
+    }
+}
+
+pub fn parse_devices(data: &Value) -> Vec<Device> {
+    list_records(data, LIST_KEYS, MAX_DEVICES)
+        .iter()
+        .map(Device::from_entry)
+        .collect()
+}
+
+pub const HEADERS: [&str; 4] = ["Hostname", "IP", "MAC", "Lease"];
+
+/// One row per device in HEADERS order, "-" for anything not reported.
+pub fn device_rows(devices: &[Device]) -> Vec<Vec<String>> {
+    devices
+        .iter()
+        .map(|d| {
+            [
+                d.hostname.clone(),
+                d.ip.clone(),
+                d.mac.clone(),
+                d.lease_text(),
+            ]
+            .into_iter()
+            .map(|v| v.unwrap_or_else(|| "-".to_string()))
+            .collect()
+        })
+        .collect()
+}
+
+/// The devices as a printed table.
+pub fn table(devices: &[Device]) -> String {
+    if devices.is_empty() {
+        return "No devices connected\n".to_string();
+    }
+    render_columns(
+        "Connected Devices",
+        &HEADERS,
+        &device_rows(devices),
+        terminal::width(),
+    )
+}
+
+// ---------- one-shot command ----------
+pub async fn run(client: &RouterClient, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let resp = client.connected_devices().await?;
+    let devices = parse_devices(&resp);
+    match format {
+        OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&resp)?),
+        OutputFormat::Json => {
+            let list: Vec<Value> = devices.iter().map(Device::to_json).collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&json!({ "devices": list }))?
+            );
+        }
+        OutputFormat::Text => print!("{}", table(&devices)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_lease_arrays() {
+        let data = json!({ "dhcp_list": [
+            { "hostname": "laptop", "ip": "192.168.0.20", "mac": "aa:bb:cc:dd:ee:ff", "lease": 3725 },
+            { "name": "phone", "ipaddr": "192.168.0.21" },
+        ] });
+        let devices = parse_devices(&data);
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].lease_text().as_deref(), Some("1h 2m 5s"));
+        assert_eq!(devices[1].hostname.as_deref(), Some("phone"));
+        assert_eq!(devices[1].ip.as_deref(), Some("192.168.0.21"));
+        assert_eq!(device_rows(&devices)[1][2], "-");
+    }
+
+    #[test]
+    fn reads_numbered_keys() {
+        let data =
+            json!({ "lenghtt": "1", "hostname1": "tv", "ip1": "192.168.0.30", "lease1": "45" });
+        let devices = parse_devices(&data);
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].hostname.as_deref(), Some("tv"));
+        assert_eq!(devices[0].lease_text().as_deref(), Some("45s"));
+    }
+
+    #[test]
+    fn no_list_means_no_devices() {
+        assert!(parse_devices(&json!({ "status": "ok" })).is_empty());
+        assert!(parse_devices(&Value::Null).is_empty());
+    }
+}
@@ -0,0 +1,305 @@
+use std::error::Error;
+use std::io::{self, IsTerminal};
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use reqwest::Client;
+use serde_json::{json, Value};
+use tokio::process::Command;
+
+use crate::cli::OutputFormat;
+use crate::client::RouterClient;
+use crate::config;
+use crate::credentials::Credentials;
+use crate::error::ZitelError;
+use crate::prompt;
+use crate::DEFAULT_BASE_URL;
+
+// ---------- default gateway lookup ----------
+#[cfg(target_os = "linux")]
+async fn default_gateway() -> Option<Ipv4Addr> {
+    // default via 192.168.0.1 dev wlan0 proto dhcp metric 600
+    let output = Command::new("ip")
+        .args(["-4", "route", "show", "default"])
+        .output()
+        .await
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.lines().find_map(|line| {
+        let mut words = line.split_whitespace();
+        words.find(|w| *w == "via")?;
+        words.next()?.parse().ok()
+    })
+}
+
+#[cfg(target_os = "windows")]
+async fn default_gateway() -> Option<Ipv4Addr> {
+    // Network Destination  Netmask  Gateway  Interface  Metric
+    //           0.0.0.0    0.0.0.0  192.168.0.1  192.168.0.45  25
+    let output = Command::new("route")
+        .args(["print", "-4", "0.0.0.0"])
+        .output()
+        .await
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.lines().find_map(|line| {
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        match cols.as_slice() {
+            ["0.0.0.0", "0.0.0.0", gateway, ..] => gateway.parse().ok(),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+async fn default_gateway() -> Option<Ipv4Addr> {
+    //     gateway: 192.168.0.1
+    let output = Command::new("route")
+        .args(["-n", "get", "default"])
+        .output()
+        .await
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.lines().find_map(|line| {
+        let value = line.trim().strip_prefix("gateway:")?;
+        value.trim().parse().ok()
+    })
+}
+
+// ---------- discovery ----------
+/// Try the default gateway as the router address, confirming it with a login
+/// before committing to it. Falls back to the stock address otherwise.
+/// `configure` applies the command line settings to each client tried.
+pub async fn discover_router(
+    http: Client,
+    credentials: Credentials,
+    header_overrides: HeaderMap,
+    configure: impl Fn(&mut RouterClient),
+) -> Result<RouterClient, ZitelError> {
+    match default_gateway().await {
+        Some(gateway) => {
+            let mut client = RouterClient::new(
+                http.clone(),
+                format!("http://{}", gateway),
+                credentials.clone(),
+                header_overrides.clone(),
+            );
+            configure(&mut client);
+            // a gateway that is not the router should not be waited for
+            client.set_login_attempts(1);
+            let result = client.authenticate().await;
+            configure(&mut client);
+            match result {
+                Ok(()) => {
+                    eprintln!("Auto-discovered router at {}", client.base_url());
+                    return Ok(client);
+                }
+                Err(e) if client.base_url() != DEFAULT_BASE_URL => eprintln!(
+                    "Gateway {} did not accept login ({}), falling back to {}",
+                    gateway, e, DEFAULT_BASE_URL
+                ),
+                Err(e) => return Err(e),
+            }
+        }
+        None => eprintln!(
+            "Could not determine the default gateway, falling back to {}",
+            DEFAULT_BASE_URL
+        ),
+    }
+
+    let mut client = RouterClient::new(
+        http,
+        DEFAULT_BASE_URL.to_string(),
+        credentials,
+        header_overrides,
+    );
+    configure(&mut client);
+    client.authenticate().await?;
+    eprintln!("Using router at {}", DEFAULT_BASE_URL);
+    Ok(client)
+}
+
+// ---------- LAN probe ----------
+// `zitel discover`: which of the usual router addresses has the Leano login
+// endpoint. Nothing is logged in to, so no credentials are needed and no
+// failed login is counted against anyone.
+const COMMON_ADDRESSES: [Ipv4Addr; 3] = [
+    Ipv4Addr::new(192, 168, 0, 1),
+    Ipv4Addr::new(192, 168, 1, 1),
+    Ipv4Addr::new(10, 0, 0, 1),
+];
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub enum Probe {
+    Zitel,
+    /// A web server that is not the Leano API, with its HTTP status.
+    OtherServer(u16),
+    NoAnswer(String),
+}
+
+pub struct Candidate {
+    pub base_url: String,
+    pub gateway: bool,
+    pub probe: Probe,
+}
+
+impl Candidate {
+    fn describe(&self) -> String {
+        let found = match &self.probe {
+            Probe::Zitel => "Zitel router".to_string(),
+            Probe::OtherServer(status) => {
+                format!("web server, not a Zitel router (HTTP {})", status)
+            }
+            Probe::NoAnswer(reason) => format!("no answer ({})", reason),
+        };
+        match self.gateway {
+            true => format!("{} (default gateway)", found),
+            false => found,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let (zitel, status, error) = match &self.probe {
+            Probe::Zitel => (true, None, None),
+            Probe::OtherServer(status) => (false, Some(*status), None),
+            Probe::NoAnswer(reason) => (false, None, Some(reason.as_str())),
+        };
+        json!({
+            "base_url": self.base_url,
+            "default_gateway": self.gateway,
+            "zitel": zitel,
+            "http_status": status,
+            "error": error,
+        })
+    }
+}
+
+/// The Leano login answers anything, even an empty request, with a JSON
+/// object carrying `status`.
+pub fn looks_like_zitel(body: &str) -> bool {
+    serde_json::from_str::<Value>(body).is_ok_and(|value| value.get("status").is_some())
+}
+
+pub async fn probe(http: &Client, base_url: &str, timeout: Duration) -> Probe {
+    let request = http
+        .post(format!("{}/authenticate.leano", base_url))
+        .timeout(timeout)
+        .send();
+    let response = match request.await {
+        Ok(response) => response,
+        Err(e) if e.is_timeout() => return Probe::NoAnswer("timeout".to_string()),
+        Err(e) => {
+            // the innermost cause, e.g. "Connection refused (os error 111)"
+            let mut cause: &dyn Error = &e;
+            while let Some(source) = cause.source() {
+                cause = source;
+            }
+            return Probe::NoAnswer(cause.to_string());
+        }
+    };
+    let status = response.status().as_u16();
+    match response.text().await {
+        Ok(body) if looks_like_zitel(&body) => Probe::Zitel,
+        _ => Probe::OtherServer(status),
+    }
+}
+
+/// Probes every base URL at once, keeping their order.
+pub async fn probe_all(http: &Client, candidates: Vec<(String, bool)>) -> Vec<Candidate> {
+    let probes: Vec<_> = candidates
+        .iter()
+        .map(|(base_url, _)| {
+            let http = http.clone();
+            let base_url = base_url.clone();
+            tokio::spawn(async move { probe(&http, &base_url, PROBE_TIMEOUT).await })
+        })
+        .collect();
+    let mut found = Vec::with_capacity(probes.len());
+    for ((base_url, gateway), probe) in candidates.into_iter().zip(probes) {
+        let probe = probe
+            .await
+            .unwrap_or_else(|e| Probe::NoAnswer(e.to_string()));
+        found.push(Candidate {
+            base_url,
+            gateway,
+            probe,
+        });
+    }
+    found
+}
+
+/// The default gateway first, then the common addresses it is not.
+async fn candidates() -> Vec<(String, bool)> {
+    let gateway = default_gateway().await;
+    let mut addresses: Vec<(Ipv4Addr, bool)> = gateway.into_iter().map(|g| (g, true)).collect();
+    for address in COMMON_ADDRESSES {
+        if gateway != Some(address) {
+            addresses.push((address, false));
+        }
+    }
+    addresses
+        .into_iter()
+        .map(|(address, gateway)| (format!("http://{}", address), gateway))
+        .collect()
+}
+
+/// Offers to put the first router found into the config file, unless it is
+/// already there. `--yes` saves without asking.
+fn offer_to_save(
+    base_url: &str,
+    path: &Path,
+    configured: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    if configured == Some(base_url) {
+        println!("{} is already the configured router", base_url);
+        return Ok(());
+    }
+    let question = format!("Save {} as base_url in {}?", base_url, path.display());
+    let save = match prompt::assume_yes() {
+        true => true,
+        false if io::stdin().is_terminal() => prompt::confirm(&question)?,
+        false => {
+            println!(
+                "Use --base-url {} or set base_url in {}",
+                base_url,
+                path.display()
+            );
+            return Ok(());
+        }
+    };
+    if save {
+        config::save_base_url(path, base_url)?;
+        println!("Saved to {}", path.display());
+    }
+    Ok(())
+}
+
+pub async fn run_discover(
+    http: &Client,
+    config_path: Option<&Path>,
+    configured: Option<&str>,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let found = probe_all(http, candidates().await).await;
+    let router = found.iter().find(|c| matches!(c.probe, Probe::Zitel));
+    match format {
+        OutputFormat::Json | OutputFormat::Raw => {
+            let results: Vec<Value> = found.iter().map(Candidate::to_json).collect();
+            println!("{}", serde_json::to_string_pretty(&results)?);
+        }
+        OutputFormat::Text => {
+            for candidate in &found {
+                println!("{:24} {}", candidate.base_url, candidate.describe());
+            }
+            if let (Some(router), Some(path)) = (router, config_path) {
+                offer_to_save(&router.base_url, path, configured)?;
+            }
+        }
+    }
+    match router {
+        Some(_) => Ok(()),
+        None => Err("No Zitel router answered, is this computer on its network?".into()),
+    }
+}
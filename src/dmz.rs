@@ -0,0 +1,239 @@
+use std::error::Error;
+use std::net::Ipv4Addr;
+
+use serde_json::{json, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::{failure_reason, is_success, RouterClient};
+use crate::error::ZitelError;
+use crate::fields::field;
+use crate::port_forward::Protocol;
+use crate::prompt::confirm_change;
+
+pub enum DmzAction {
+    Set { ip: String, protocol: Protocol },
+    Disable,
+    Show,
+}
+
+// ---------- host address ----------
+const LAN_IP_SPEC: &str = "lanip|LANIP|lan_ip";
+const NETMASK_SPEC: &str = "netmask|NETMASK|lan_netmask";
+
+/// A DMZ host as typed: a dotted IPv4 address that can be a single LAN host,
+/// so not 0.0.0.0, broadcast, loopback or multicast.
+pub fn parse_host(input: &str) -> Result<Ipv4Addr, String> {
+    let ip: Ipv4Addr = input
+        .trim()
+        .parse()
+        .map_err(|_| format!("{:?} is not a valid IPv4 address", input.trim()))?;
+    if ip.is_unspecified() || ip.is_broadcast() || ip.is_loopback() || ip.is_multicast() {
+        return Err(format!("{} cannot be a DMZ host", ip));
+    }
+    Ok(ip)
+}
+
+/// The router's LAN address and netmask from `get_index_data`.
+pub fn lan_subnet(data: &Value) -> Option<(Ipv4Addr, Ipv4Addr)> {
+    let lan = field(data, LAN_IP_SPEC)?.trim().parse().ok()?;
+    let mask = field(data, NETMASK_SPEC)?.trim().parse().ok()?;
+    Some((lan, mask))
+}
+
+/// Why `host` looks wrong for the LAN `lan`/`mask`, if it does.
+pub fn lan_warning(host: Ipv4Addr, lan: Ipv4Addr, mask: Ipv4Addr) -> Option<String> {
+    let (host_bits, lan_bits, mask_bits) = (u32::from(host), u32::from(lan), u32::from(mask));
+    let network = Ipv4Addr::from(lan_bits & mask_bits);
+    let prefix = mask_bits.count_ones();
+    if host_bits & mask_bits != lan_bits & mask_bits {
+        Some(format!(
+            "{} is outside the router's LAN {}/{}",
+            host, network, prefix
+        ))
+    } else if host == lan {
+        Some(format!("{} is the router itself", host))
+    } else if host_bits | mask_bits == u32::MAX {
+        Some(format!(
+            "{} is the broadcast address of {}/{}",
+            host, network, prefix
+        ))
+    } else {
+        None
+    }
+}
+
+// ---------- status ----------
+pub struct DmzStatus {
+    pub enabled: Option<bool>,
+    pub host: Option<String>,
+    pub protocol: Option<String>,
+}
+
+pub fn parse_dmz_status(resp: &Value) -> DmzStatus {
+    let enabled = field(resp, "dmz_enable|dmz.enable|enable").map(|v| {
+        matches!(
+            v.trim().to_lowercase().as_str(),
+            "1" | "true" | "on" | "enabled"
+        )
+    });
+    DmzStatus {
+        enabled,
+        host: field(resp, "dmz_ip|dmz.ip|ip").map(String::from),
+        protocol: field(resp, "dmz_proto|dmz.protocol|protocol").map(String::from),
+    }
+}
+
+impl DmzStatus {
+    pub fn describe(&self) -> String {
+        match (self.enabled, &self.host) {
+            (Some(false), _) => "DMZ disabled".to_string(),
+            (_, Some(host)) => format!(
+                "DMZ host {} ({})",
+                host,
+                self.protocol.as_deref().unwrap_or("protocol unknown")
+            ),
+            (Some(true), None) => "DMZ enabled, host not reported".to_string(),
+            (None, None) => "Router did not report the DMZ state".to_string(),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({ "enabled": self.enabled, "host": self.host, "protocol": self.protocol })
+    }
+}
+
+/// Turns DMZ off and reads the state back, so "done" means the router
+/// really reports it disabled (or does not report it at all).
+pub async fn disable(client: &RouterClient) -> Result<(Value, String), Box<dyn Error>> {
+    let resp = client.disable_dmz().await?;
+    if !is_success(&resp) {
+        return Err(ZitelError::refused("Disabling DMZ failed", &resp).into());
+    }
+    if client.dry_run() {
+        return Ok((resp, client.outcome("DMZ disabled")));
+    }
+    let status = parse_dmz_status(&client.dmz_status().await?);
+    if status.enabled == Some(true) {
+        let message = format!("Router still reports {}", status.describe());
+        return Err(ZitelError::RouterStatus(message).into());
+    }
+    Ok((resp, "DMZ disabled".to_string()))
+}
+
+// ---------- one-shot command ----------
+pub async fn run(
+    client: &RouterClient,
+    action: DmzAction,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let (ip, protocol) = match action {
+        DmzAction::Set { ip, protocol } => (ip, protocol),
+        DmzAction::Disable => {
+            confirm_change(client, "Turn the DMZ off?")?;
+            let (resp, summary) = disable(client).await?;
+            match format {
+                OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&resp)?),
+                OutputFormat::Json => {
+                    let normalized = json!({
+                        "success": true,
+                        "enabled": false,
+                        "dry_run": client.dry_run(),
+                    });
+                    println!("{}", serde_json::to_string_pretty(&normalized)?);
+                }
+                OutputFormat::Text => println!("{}", summary),
+            }
+            return Ok(());
+        }
+        DmzAction::Show => {
+            let resp = client.dmz_status().await?;
+            let status = parse_dmz_status(&resp);
+            match format {
+                OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&resp)?),
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&status.to_json())?)
+                }
+                OutputFormat::Text => println!("{}", status.describe()),
+            }
+            return Ok(());
+        }
+    };
+
+    let host = parse_host(&ip).map_err(ZitelError::Input)?;
+    // only a warning: the LAN may be reported wrong, or about to change
+    let subnet = match client.api_request("get_index_data").await {
+        Ok(data) => lan_subnet(&data),
+        Err(_) => None,
+    };
+    if let Some(warning) = subnet.and_then(|(lan, mask)| lan_warning(host, lan, mask)) {
+        eprintln!("Warning: {}", warning);
+    }
+    let ip = host.to_string();
+    let question = format!(
+        "Expose {} to the internet as the DMZ host ({})?",
+        ip,
+        protocol.describe()
+    );
+    confirm_change(client, &question)?;
+    let resp = client.set_dmz(&ip, protocol).await?;
+    let done = is_success(&resp);
+    let summary = client.outcome(&format!("DMZ host set to {} ({})", ip, protocol.describe()));
+
+    match format {
+        OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&resp)?),
+        OutputFormat::Json => {
+            let normalized = json!({
+                "success": done,
+                "ip": ip,
+                "protocol": protocol.command_name(),
+                "dry_run": client.dry_run(),
+                "message": if done { Some(summary.as_str()) } else { failure_reason(&resp) },
+            });
+            println!("{}", serde_json::to_string_pretty(&normalized)?);
+        }
+        OutputFormat::Text if done => println!("{}", summary),
+        OutputFormat::Text => {}
+    }
+
+    if done {
+        Ok(())
+    } else {
+        Err(ZitelError::refused("Setting the DMZ host failed", &resp).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hosts_are_parsed_and_normalized() {
+        assert_eq!(
+            parse_host(" 192.168.0.92 "),
+            Ok(Ipv4Addr::new(192, 168, 0, 92))
+        );
+        assert!(parse_host("192.168.0.999").is_err());
+        assert!(parse_host("not-an-ip").is_err());
+        assert!(parse_host("192.168.0").is_err());
+        assert!(parse_host("").is_err());
+        assert!(parse_host("0.0.0.0").is_err());
+        assert!(parse_host("255.255.255.255").is_err());
+        assert!(parse_host("127.0.0.1").is_err());
+        assert!(parse_host("224.0.0.1").is_err());
+    }
+
+    #[test]
+    fn warns_about_hosts_outside_the_lan() {
+        let data = json!({ "lanip": "192.168.0.1", "netmask": "255.255.255.0" });
+        let (lan, mask) = lan_subnet(&data).unwrap();
+        let host = |s: &str| s.parse::<Ipv4Addr>().unwrap();
+        assert_eq!(lan_warning(host("192.168.0.92"), lan, mask), None);
+        assert_eq!(
+            lan_warning(host("192.168.1.92"), lan, mask).as_deref(),
+            Some("192.168.1.92 is outside the router's LAN 192.168.0.0/24")
+        );
+        assert!(lan_warning(host("192.168.0.1"), lan, mask).is_some());
+        assert!(lan_warning(host("192.168.0.255"), lan, mask).is_some());
+        assert_eq!(lan_subnet(&json!({ "lanip": "192.168.0.1" })), None);
+    }
+}
@@ -0,0 +1,290 @@
+use std::error::Error;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::RouterClient;
+use crate::color;
+use crate::error::ZitelError;
+use crate::signal::Quality;
+
+// ---------- setup checks ----------
+// `zitel doctor`: each step of talking to the router in turn, from name
+// lookup to an API answer, with what to try when one fails. A failed step
+// skips the ones that need it. The clock only warns, it does not stop
+// anything from working.
+const DNS_TIMEOUT: Duration = Duration::from_secs(5);
+/// How far the router's clock may be off before it is worth a warning.
+const MAX_CLOCK_SKEW_SECS: i64 = 300;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Pass,
+    Warn,
+    Fail,
+    Skip,
+}
+
+impl Outcome {
+    fn label(self) -> &'static str {
+        match self {
+            Outcome::Pass => "PASS",
+            Outcome::Warn => "WARN",
+            Outcome::Fail => "FAIL",
+            Outcome::Skip => "SKIP",
+        }
+    }
+}
+
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    pub detail: String,
+    /// What to try, for a warning or failure.
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: String) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Pass,
+            detail,
+            hint: None,
+        }
+    }
+
+    fn fail(name: &'static str, detail: String, hint: &str) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Fail,
+            detail,
+            hint: Some(hint.to_string()),
+        }
+    }
+
+    fn skip(name: &'static str, detail: &str) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Skip,
+            detail: detail.to_string(),
+            hint: None,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "check": self.name,
+            "result": self.outcome.label().to_lowercase(),
+            "detail": self.detail,
+            "hint": self.hint,
+        })
+    }
+}
+
+async fn check_dns(base_url: &str) -> Check {
+    const NAME: &str = "Name lookup";
+    let url = match reqwest::Url::parse(base_url) {
+        Ok(url) => url,
+        Err(e) => {
+            let hint = "Set the address as e.g. --base-url http://192.168.0.1";
+            return Check::fail(NAME, format!("{} is not a URL: {}", base_url, e), hint);
+        }
+    };
+    let host = url.host_str().unwrap_or_default().to_string();
+    let bare = host.trim_start_matches('[').trim_end_matches(']');
+    if bare.parse::<IpAddr>().is_ok() {
+        return Check::pass(NAME, format!("{} is an address, nothing to look up", host));
+    }
+    let port = url.port_or_known_default().unwrap_or(80);
+    let hint = "Use the router's IP address instead, or run `zitel discover` to find it";
+    let lookup = tokio::time::timeout(DNS_TIMEOUT, tokio::net::lookup_host((bare, port))).await;
+    match lookup {
+        Ok(Ok(mut addresses)) => match addresses.next() {
+            Some(address) => Check::pass(NAME, format!("{} is {}", host, address.ip())),
+            None => Check::fail(NAME, format!("{} has no address", host), hint),
+        },
+        Ok(Err(e)) => Check::fail(NAME, format!("cannot resolve {}: {}", host, e), hint),
+        Err(_) => Check::fail(
+            NAME,
+            format!("no answer for {} within {}s", host, DNS_TIMEOUT.as_secs()),
+            hint,
+        ),
+    }
+}
+
+fn network_hint(e: &ZitelError) -> &'static str {
+    match e {
+        ZitelError::Certificate(_) => {
+            "Pass --insecure if the router uses a self-signed certificate"
+        }
+        _ => {
+            "Check this computer is on the router's network and the address is right; \
+             `zitel discover` tries the usual ones"
+        }
+    }
+}
+
+fn login_hint(e: &ZitelError) -> &'static str {
+    match e {
+        ZitelError::Auth(_) => {
+            "Check the user name and password; `zitel login` stores a working one, \
+             --auth-mode auto helps with firmware that wants it hashed"
+        }
+        ZitelError::EndpointMissing { .. } | ZitelError::Decode(_) => {
+            "This may not be a Zitel router, check --base-url"
+        }
+        e => network_hint(e),
+    }
+}
+
+fn api_hint(e: &ZitelError) -> &'static str {
+    match e {
+        ZitelError::EndpointMissing { .. } | ZitelError::Decode(_) => {
+            "Point --api-path (or api_path in the config) at where this firmware serves its API"
+        }
+        e => login_hint(e),
+    }
+}
+
+/// How the router's clock compares to this computer's.
+pub fn check_clock(router: Option<DateTime<Utc>>, local: DateTime<Utc>) -> Check {
+    const NAME: &str = "Clock";
+    let Some(router) = router else {
+        return Check::skip(NAME, "the router sent no time to compare with");
+    };
+    let skew = (router - local).num_seconds();
+    let detail = match skew {
+        0 => "the router's clock agrees with this computer's".to_string(),
+        s if s > 0 => format!("the router's clock is {}s ahead of this computer's", s),
+        s => format!("the router's clock is {}s behind this computer's", -s),
+    };
+    if skew.abs() <= MAX_CLOCK_SKEW_SECS {
+        return Check::pass(NAME, detail);
+    }
+    Check {
+        name: NAME,
+        outcome: Outcome::Warn,
+        detail,
+        hint: Some(
+            "Cached sessions may expire early and logged times will not match the \
+             router's; check the time on this computer, the router sets its own once \
+             it is online"
+                .to_string(),
+        ),
+    }
+}
+
+/// Runs every check against the router `client` points at. The client must
+/// not have logged in yet, the login is one of the checks.
+pub async fn run_checks(client: &RouterClient) -> Vec<Check> {
+    // the checks after a failed one, all of which need it
+    fn skip_rest(checks: &mut Vec<Check>, reason: &str) {
+        for name in ["Web server", "Login", "API", "Clock"] {
+            if !checks.iter().any(|c| c.name == name) {
+                checks.push(Check::skip(name, reason));
+            }
+        }
+    }
+
+    let mut checks = vec![check_dns(client.base_url()).await];
+    if checks[0].outcome == Outcome::Fail {
+        skip_rest(&mut checks, "needs the name lookup");
+        return checks;
+    }
+
+    let router_date = match client.server_date().await {
+        Ok(date) => {
+            let detail = format!("{} answers", client.base_url());
+            checks.push(Check::pass("Web server", detail));
+            date
+        }
+        Err(e) => {
+            let hint = network_hint(&e);
+            checks.push(Check::fail("Web server", e.to_string(), hint));
+            skip_rest(&mut checks, "needs the web server");
+            return checks;
+        }
+    };
+
+    match client.authenticate().await {
+        Ok(()) => checks.push(Check::pass(
+            "Login",
+            "the router accepted the password".into(),
+        )),
+        Err(e) => {
+            let hint = login_hint(&e);
+            checks.push(Check::fail("Login", e.to_string(), hint));
+        }
+    }
+    if checks.last().is_some_and(|c| c.outcome == Outcome::Fail) {
+        checks.push(Check::skip("API", "needs a login"));
+    } else {
+        match client.api_request("get_index_data").await {
+            Ok(_) => checks.push(Check::pass("API", "get_index_data answered".into())),
+            Err(e) => {
+                let hint = api_hint(&e);
+                checks.push(Check::fail("API", e.to_string(), hint));
+            }
+        }
+    }
+    checks.push(check_clock(router_date, Utc::now()));
+    checks
+}
+
+pub async fn run(client: &RouterClient, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let checks = run_checks(client).await;
+    let failed = checks.iter().filter(|c| c.outcome == Outcome::Fail).count();
+    match format {
+        OutputFormat::Json | OutputFormat::Raw => {
+            let result = json!({
+                "base_url": client.base_url(),
+                "ok": failed == 0,
+                "checks": checks.iter().map(Check::to_json).collect::<Vec<_>>(),
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text => {
+            for check in &checks {
+                let label = match check.outcome {
+                    Outcome::Pass => color::paint(check.outcome.label(), Quality::Good),
+                    Outcome::Warn => color::paint(check.outcome.label(), Quality::Fair),
+                    Outcome::Fail => color::paint(check.outcome.label(), Quality::Poor),
+                    Outcome::Skip => check.outcome.label().to_string(),
+                };
+                println!("{}  {:<12} {}", label, check.name, check.detail);
+                if let Some(hint) = &check.hint {
+                    println!("      {:<12} {}", "", hint);
+                }
+            }
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        n => Err(format!("{} of {} checks failed", n, checks.len()).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_large_clock_skew_warns() {
+        let local = Utc::now();
+        let check = check_clock(Some(local + chrono::Duration::seconds(42)), local);
+        assert_eq!(check.outcome, Outcome::Pass);
+        assert_eq!(
+            check.detail,
+            "the router's clock is 42s ahead of this computer's"
+        );
+
+        let check = check_clock(Some(local - chrono::Duration::hours(2)), local);
+        assert_eq!(check.outcome, Outcome::Warn);
+        assert!(check.detail.contains("7200s behind") && check.hint.is_some());
+        assert_eq!(check_clock(None, local).outcome, Outcome::Skip);
+    }
+}
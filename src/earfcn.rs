@@ -0,0 +1,145 @@
+use crate::charset;
+
+// ---------- EARFCN ----------
+// Downlink EARFCN ranges per E-UTRA band (3GPP TS 36.101, table 5.7.3-1).
+// Uplink EARFCNs are valid numbers too but belong to no entry here.
+pub const MAX_EARFCN: u32 = 262_143;
+
+const BANDS: &[(u32, u32, u16)] = &[
+    (0, 599, 1),
+    (600, 1199, 2),
+    (1200, 1949, 3),
+    (1950, 2399, 4),
+    (2400, 2649, 5),
+    (2650, 2749, 6),
+    (2750, 3449, 7),
+    (3450, 3799, 8),
+    (3800, 4149, 9),
+    (4150, 4749, 10),
+    (4750, 4949, 11),
+    (5010, 5179, 12),
+    (5180, 5279, 13),
+    (5280, 5379, 14),
+    (5730, 5849, 17),
+    (5850, 5999, 18),
+    (6000, 6149, 19),
+    (6150, 6449, 20),
+    (6450, 6599, 21),
+    (6600, 7399, 22),
+    (7500, 7699, 23),
+    (7700, 8039, 24),
+    (8040, 8689, 25),
+    (8690, 9039, 26),
+    (9040, 9209, 27),
+    (9210, 9659, 28),
+    (9660, 9769, 29),
+    (9770, 9869, 30),
+    (9870, 9919, 31),
+    (9920, 10359, 32),
+    (36000, 36199, 33),
+    (36200, 36349, 34),
+    (36350, 36949, 35),
+    (36950, 37549, 36),
+    (37550, 37749, 37),
+    (37750, 38249, 38),
+    (38250, 38649, 39),
+    (38650, 39649, 40),
+    (39650, 41589, 41),
+    (41590, 43589, 42),
+    (43590, 45589, 43),
+    (45590, 46589, 44),
+    (46590, 46789, 45),
+    (46790, 54539, 46),
+    (54540, 55239, 47),
+    (55240, 56739, 48),
+    (56740, 58239, 49),
+    (58240, 59089, 50),
+    (59090, 59139, 51),
+    (59140, 60139, 52),
+    (60140, 60254, 53),
+    (65536, 66435, 65),
+    (66436, 67335, 66),
+    (67336, 67535, 67),
+    (67536, 67835, 68),
+    (67836, 68335, 69),
+    (68336, 68585, 70),
+    (68586, 68935, 71),
+    (68936, 68985, 72),
+    (68986, 69035, 73),
+    (69036, 69465, 74),
+    (69466, 70315, 75),
+    (70316, 70365, 76),
+    (70366, 70545, 85),
+    (70546, 70595, 87),
+    (70596, 70645, 88),
+];
+
+/// Parses an EARFCN typed by the user, rejecting anything outside 0-262143.
+pub fn parse_earfcn(s: &str) -> Result<u32, String> {
+    let earfcn: u32 = s
+        .trim()
+        .parse()
+        .map_err(|_| format!("EARFCN must be a whole number, got {:?}", s))?;
+    if earfcn > MAX_EARFCN {
+        return Err(format!(
+            "EARFCN {} is out of range (0-{})",
+            earfcn, MAX_EARFCN
+        ));
+    }
+    Ok(earfcn)
+}
+
+/// The LTE band a downlink EARFCN belongs to.
+pub fn band_for_earfcn(earfcn: u32) -> Option<u16> {
+    BANDS
+        .iter()
+        .find(|(first, last, _)| (*first..=*last).contains(&earfcn))
+        .map(|&(_, _, band)| band)
+}
+
+/// "EARFCN 42490 → Band 42", or without the band when it is not known.
+pub fn describe(earfcn: u32) -> String {
+    match band_for_earfcn(earfcn) {
+        Some(band) => format!("EARFCN {} {} Band {}", earfcn, charset::arrow(), band),
+        None => format!("EARFCN {} (no known downlink band)", earfcn),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_the_full_range() {
+        assert_eq!(parse_earfcn("0"), Ok(0));
+        assert_eq!(parse_earfcn(" 42490 "), Ok(42490));
+        assert_eq!(parse_earfcn("262143"), Ok(MAX_EARFCN));
+    }
+
+    #[test]
+    fn rejects_out_of_range_and_non_numbers() {
+        assert!(parse_earfcn("262144").is_err());
+        assert!(parse_earfcn("-1").is_err());
+        assert!(parse_earfcn("4249O").is_err());
+        assert!(parse_earfcn("").is_err());
+    }
+
+    #[test]
+    fn maps_earfcns_to_bands() {
+        assert_eq!(band_for_earfcn(0), Some(1));
+        assert_eq!(band_for_earfcn(1300), Some(3));
+        assert_eq!(band_for_earfcn(6300), Some(20));
+        assert_eq!(band_for_earfcn(38950), Some(40));
+        assert_eq!(band_for_earfcn(42490), Some(42));
+        assert_eq!(band_for_earfcn(43590), Some(43));
+        assert_eq!(band_for_earfcn(66786), Some(66));
+    }
+
+    #[test]
+    fn gaps_and_uplink_earfcns_have_no_band() {
+        assert_eq!(band_for_earfcn(5000), None);
+        assert_eq!(band_for_earfcn(18100), None);
+        assert_eq!(describe(18100), "EARFCN 18100 (no known downlink band)");
+        assert_eq!(describe(42690), "EARFCN 42690 → Band 42");
+    }
+}
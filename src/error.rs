@@ -0,0 +1,171 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::Value;
+
+use crate::client::{failure_reason, NotJsonError};
+
+// ---------- errors ----------
+/// What went wrong talking to the router, by kind, so callers can tell a
+/// refused login from a dead link from a router that said no.
+#[derive(Debug)]
+pub enum ZitelError {
+    /// The router refused the credentials, or a new login after the session
+    /// expired failed.
+    Auth(String),
+    /// The router rejected the session token (HTTP 401/403).
+    SessionExpired(StatusCode),
+    /// The router did not answer within the timeout.
+    Timeout(Duration),
+    /// Nothing accepts connections at the router address (`host:port`).
+    ConnectionRefused(String),
+    /// The router's host name does not resolve.
+    DnsFailure(String),
+    /// Any other failure to get an answer, e.g. a broken transfer.
+    Http(reqwest::Error),
+    /// The router's HTTPS certificate was rejected.
+    Certificate(String),
+    /// The router's web server answered, but not at the login or API path.
+    EndpointMissing {
+        url: String,
+        status: StatusCode,
+        hint: &'static str,
+    },
+    /// The router answered with something other than JSON.
+    Decode(NotJsonError),
+    /// The router answered but did not do what was asked.
+    RouterStatus(String),
+    /// Refused locally, before anything was sent.
+    Input(String),
+}
+
+impl ZitelError {
+    /// A `RouterStatus` error for a command answered with `resp`, with the
+    /// router's own reason when it gave one.
+    pub fn refused(what: &str, resp: &Value) -> Self {
+        let reason = failure_reason(resp).unwrap_or("router did not report success");
+        ZitelError::RouterStatus(format!("{}: {}", what, reason))
+    }
+}
+
+impl fmt::Display for ZitelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZitelError::Auth(message) => write!(f, "{}", message),
+            ZitelError::SessionExpired(status) => {
+                write!(f, "router rejected the session token (HTTP {})", status)
+            }
+            ZitelError::Timeout(timeout) => write!(
+                f,
+                "router not responding (timeout after {}s, see --timeout)",
+                timeout.as_secs_f64()
+            ),
+            ZitelError::ConnectionRefused(address) => write!(
+                f,
+                "connection refused by {} (is the router address correct?)",
+                address
+            ),
+            ZitelError::DnsFailure(host) => write!(
+                f,
+                "cannot resolve {} (is the router address correct?)",
+                host
+            ),
+            ZitelError::Http(e) => write!(f, "{}", e),
+            ZitelError::EndpointMissing { url, status, hint } => write!(
+                f,
+                "the router answered but {} does not exist (HTTP {}), {}",
+                url, status, hint
+            ),
+            ZitelError::Certificate(message) => write!(
+                f,
+                "{} (if the router uses a self-signed certificate, pass --insecure)",
+                message
+            ),
+            ZitelError::Decode(e) => write!(f, "{}", e),
+            ZitelError::RouterStatus(message) | ZitelError::Input(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl Error for ZitelError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ZitelError::Http(e) => Some(e),
+            ZitelError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// ---------- exit codes ----------
+// 2 is what clap exits with for bad arguments, so local validation failures
+// share it.
+pub const EXIT_FAILURE: u8 = 1;
+pub const EXIT_USAGE: u8 = 2;
+pub const EXIT_AUTH: u8 = 3;
+pub const EXIT_NETWORK: u8 = 4;
+pub const EXIT_ROUTER: u8 = 5;
+
+/// The process exit code for an error that ended the run.
+pub fn exit_code(e: &(dyn Error + 'static)) -> u8 {
+    match e.downcast_ref::<ZitelError>() {
+        Some(ZitelError::Auth(_) | ZitelError::SessionExpired(_)) => EXIT_AUTH,
+        Some(
+            ZitelError::Timeout(_)
+            | ZitelError::ConnectionRefused(_)
+            | ZitelError::DnsFailure(_)
+            | ZitelError::Http(_)
+            | ZitelError::Certificate(_),
+        ) => EXIT_NETWORK,
+        Some(
+            ZitelError::EndpointMissing { .. }
+            | ZitelError::Decode(_)
+            | ZitelError::RouterStatus(_),
+        ) => EXIT_ROUTER,
+        Some(ZitelError::Input(_)) => EXIT_USAGE,
+        None => EXIT_FAILURE,
+    }
+}
+
+impl From<reqwest::Error> for ZitelError {
+    fn from(e: reqwest::Error) -> Self {
+        ZitelError::Http(e)
+    }
+}
+
+impl From<NotJsonError> for ZitelError {
+    fn from(e: NotJsonError) -> Self {
+        ZitelError::Decode(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn exit_codes_follow_the_error_kind() {
+        let code = |e: ZitelError| exit_code(&e);
+        assert_eq!(code(ZitelError::Auth("no".to_string())), EXIT_AUTH);
+        assert_eq!(
+            code(ZitelError::Certificate("bad".to_string())),
+            EXIT_NETWORK
+        );
+        assert_eq!(
+            code(ZitelError::refused("Reboot refused", &json!({}))),
+            EXIT_ROUTER
+        );
+        assert_eq!(code(ZitelError::Input("PCI 600".to_string())), EXIT_USAGE);
+        assert_eq!(
+            code(ZitelError::Timeout(Duration::from_secs(30))),
+            EXIT_NETWORK
+        );
+        let other: Box<dyn Error> = "Reboot cancelled".into();
+        assert_eq!(exit_code(other.as_ref()), EXIT_FAILURE);
+    }
+}
@@ -0,0 +1,166 @@
+use std::borrow::Cow;
+
+use serde_json::{Map, Value};
+
+// ---------- field access ----------
+// A key spec is one or more alternatives separated by `|`, each a dotted path
+// into the response ("RSRP|lte.rsrp"). The first alternative holding a scalar
+// wins, so one spec covers firmware with flat and with nested layouts.
+
+fn lookup<'a>(data: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(data, |value, segment| match value {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+fn scalar_text(value: &Value) -> Option<Cow<'_, str>> {
+    match value {
+        Value::String(s) => Some(Cow::Borrowed(s.as_str())),
+        Value::Number(n) => Some(Cow::Owned(n.to_string())),
+        Value::Bool(b) => Some(Cow::Owned(b.to_string())),
+        _ => None,
+    }
+}
+
+/// The value at `spec` as text, with numbers and booleans stringified.
+pub fn field<'a>(data: &'a Value, spec: &str) -> Option<Cow<'a, str>> {
+    spec.split('|')
+        .filter_map(|path| lookup(data, path.trim()))
+        .find_map(scalar_text)
+}
+
+/// The value at `spec` parsed as a number, whatever JSON type it came as.
+pub fn field_parse<T: std::str::FromStr>(data: &Value, spec: &str) -> Option<T> {
+    field(data, spec)?.trim().parse().ok()
+}
+
+/// The alternative of `spec` that `field` would read.
+pub fn matched_key<'s>(data: &Value, spec: &'s str) -> Option<&'s str> {
+    spec.split('|')
+        .map(str::trim)
+        .find(|path| lookup(data, path).and_then(scalar_text).is_some())
+}
+
+/// `field_parse` for specs whose alternatives are spellings of one key
+/// (the router's "recieve" next to a corrected "received"), logging which
+/// spelling this firmware uses.
+pub fn aliased_parse<T: std::str::FromStr>(data: &Value, spec: &str) -> Option<T> {
+    let key = matched_key(data, spec);
+    if spec.contains('|') {
+        log::debug!("{:?} read from {:?}", spec, key);
+    }
+    field_parse(data, key?)
+}
+
+// ---------- lists ----------
+/// The records of a list response: an array of objects at the top level or
+/// under one of `list_keys`, or on older firmware numbered keys ("ip1",
+/// "ip2", ...) with the count in `lenghtt` (sic). Numbered records come back
+/// as objects with the number stripped, so the same specs read both layouts.
+pub fn list_records(data: &Value, list_keys: &[&str], max: usize) -> Vec<Value> {
+    let list = std::iter::once(data)
+        .chain(list_keys.iter().map(|key| &data[*key]))
+        .find_map(Value::as_array);
+    if let Some(list) = list {
+        return list.iter().take(max).cloned().collect();
+    }
+
+    let count = field_parse::<usize>(data, "lenghtt").unwrap_or(0).min(max);
+    let mut records = vec![Map::new(); count];
+    if let Value::Object(map) = data {
+        for (key, value) in map {
+            let name = key.trim_end_matches(|c: char| c.is_ascii_digit());
+            let index = key[name.len()..].parse::<usize>().ok();
+            if let Some(record) = index.and_then(|i| records.get_mut(i.checked_sub(1)?)) {
+                record.insert(name.to_string(), value.clone());
+            }
+        }
+    }
+    records.into_iter().map(Value::Object).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn misspelled_and_corrected_keys_both_count() {
+        let spec = "recieve|receive|received";
+        assert_eq!(
+            aliased_parse::<u64>(&json!({ "recieve": "2048" }), spec),
+            Some(2048)
+        );
+        assert_eq!(
+            aliased_parse::<u64>(&json!({ "received": 4096 }), spec),
+            Some(4096)
+        );
+        assert_eq!(
+            matched_key(&json!({ "received": 1 }), spec),
+            Some("received")
+        );
+        assert_eq!(aliased_parse::<u64>(&json!({ "sent": 1 }), spec), None);
+    }
+
+    #[test]
+    fn reads_top_level_strings() {
+        let data = json!({ "RSRP": "-95" });
+        assert_eq!(field(&data, "RSRP").as_deref(), Some("-95"));
+    }
+
+    #[test]
+    fn stringifies_numbers_and_bools() {
+        let data = json!({ "RSRP": -95, "SINR": 12.5, "roaming": false });
+        assert_eq!(field(&data, "RSRP").as_deref(), Some("-95"));
+        assert_eq!(field(&data, "SINR").as_deref(), Some("12.5"));
+        assert_eq!(field(&data, "roaming").as_deref(), Some("false"));
+        assert_eq!(field_parse::<i64>(&data, "RSRP"), Some(-95));
+    }
+
+    #[test]
+    fn follows_dotted_paths() {
+        let data = json!({ "lte": { "rsrp": "-101", "cells": [{ "pci": 7 }] } });
+        assert_eq!(field(&data, "lte.rsrp").as_deref(), Some("-101"));
+        assert_eq!(field(&data, "lte.cells.0.pci").as_deref(), Some("7"));
+        assert_eq!(field(&data, "lte.cells.1.pci"), None);
+        assert_eq!(field(&data, "lte.rsrp.value"), None);
+    }
+
+    #[test]
+    fn first_matching_alternative_wins() {
+        let nested = json!({ "lte": { "rsrp": -88 } });
+        let flat = json!({ "RSRP": "-90", "lte": { "rsrp": -88 } });
+        assert_eq!(field(&nested, "RSRP|lte.rsrp").as_deref(), Some("-88"));
+        assert_eq!(field(&flat, "RSRP|lte.rsrp").as_deref(), Some("-90"));
+    }
+
+    #[test]
+    fn objects_arrays_and_null_are_not_values() {
+        let data = json!({ "RSRP": null, "lte": { "rsrp": [] }, "band": {} });
+        assert_eq!(field(&data, "RSRP"), None);
+        assert_eq!(field(&data, "lte.rsrp"), None);
+        assert_eq!(field(&data, "band"), None);
+        assert_eq!(field(&data, "missing"), None);
+    }
+
+    #[test]
+    fn numbered_keys_become_records() {
+        let data = json!({ "lenghtt": "2", "ip1": "10.0.0.1", "ip2": "10.0.0.2", "name2": "b", "ip3": "x" });
+        let records = list_records(&data, &[], 8);
+        assert_eq!(
+            records,
+            vec![
+                json!({ "ip": "10.0.0.1" }),
+                json!({ "ip": "10.0.0.2", "name": "b" })
+            ]
+        );
+        let array = json!({ "list": [{ "ip": "10.0.0.9" }] });
+        assert_eq!(
+            list_records(&array, &["list"], 8),
+            vec![json!({ "ip": "10.0.0.9" })]
+        );
+    }
+}
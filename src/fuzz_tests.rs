@@ -0,0 +1,152 @@
+// Robustness tests: router firmware returns all sorts of malformed data, none
+// of which may panic the parsers or the text builders that read it.
+use std::time::Duration;
+
+use proptest::prelude::*;
+use serde_json::{Map, Value};
+use tokio::sync::mpsc;
+
+use crate::band_lock::LockStatus;
+use crate::neighbors::{parse_neighbor_cells, serving_rsrp, MAX_NEIGHBOR_CELLS};
+use crate::{
+    build_cell_text, build_connection_text, build_neighbor_text, build_system_text, select_pages,
+    App,
+};
+
+const DASHBOARD_KEYS: &[&str] = &[
+    "TYPE",
+    "BAND",
+    "CSQ",
+    "RSRP",
+    "RSRQ",
+    "SINR",
+    "RSSI",
+    "IPV4",
+    "wan_ipaddr",
+    "INTERNET",
+    "MCC",
+    "MNC",
+    "PCID",
+    "EARFCN",
+    "TAC",
+    "ENODE",
+    "CELL",
+    "model",
+    "serial",
+    "hardv",
+    "sofv",
+    "SYSUP",
+    "WANUP",
+    "ram",
+    "cpu1",
+    "cpu2",
+    "recieve",
+    "sentt",
+    "SIM",
+    "sim_status",
+];
+
+const NEIGHBOR_KEYS: &[&str] = &["type", "band", "pcid", "rsrq", "rsrp", "rsrppp"];
+
+fn arb_json() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        any::<f64>().prop_map(Value::from),
+        arb_router_string().prop_map(Value::String),
+    ];
+    leaf.prop_recursive(3, 32, 6, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+            prop::collection::btree_map(".{0,8}", inner, 0..6)
+                .prop_map(|m| Value::Object(m.into_iter().collect())),
+        ]
+    })
+}
+
+// Mostly number-ish strings, since that is what the parsers try to read.
+fn arb_router_string() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "-?[0-9]{0,25}",
+        "-?[0-9]{1,4}\\.[0-9]{0,4}",
+        " *-?[0-9]{1,3} *(dBm|dB)?",
+        Just("NaN".to_string()),
+        Just("inf".to_string()),
+        Just("18446744073709551616".to_string()),
+        ".{0,12}",
+    ]
+}
+
+// An object using the keys the dashboard reads, with arbitrary values.
+fn arb_dashboard() -> impl Strategy<Value = Value> {
+    prop::collection::vec(arb_json(), DASHBOARD_KEYS.len()).prop_map(|values| {
+        let map = DASHBOARD_KEYS
+            .iter()
+            .map(|k| k.to_string())
+            .zip(values)
+            .collect::<Map<_, _>>();
+        Value::Object(map)
+    })
+}
+
+fn arb_neighbors() -> impl Strategy<Value = Value> {
+    let cell_values = prop::collection::vec(arb_json(), NEIGHBOR_KEYS.len() * 4);
+    (arb_router_string(), cell_values).prop_map(|(count, values)| {
+        let mut map = Map::new();
+        map.insert("lenghtt".to_string(), Value::String(count));
+        let keys = (1..=4).flat_map(|i| NEIGHBOR_KEYS.iter().map(move |k| format!("{}{}", k, i)));
+        map.extend(keys.zip(values));
+        Value::Object(map)
+    })
+}
+
+fn test_app() -> App {
+    App::new(
+        mpsc::unbounded_channel().0,
+        String::new(),
+        Duration::from_secs(1),
+        select_pages(&[]).unwrap(),
+    )
+}
+
+fn render_dashboard(data: &Value) {
+    let lock = LockStatus::Locked {
+        earfcn: 1850,
+        since: String::new(),
+    };
+    let _ = build_connection_text(data, &lock);
+    let _ = build_cell_text(data);
+    let _ = build_system_text(data);
+}
+
+proptest! {
+    #[test]
+    fn dashboard_text_never_panics(data in prop_oneof![arb_json(), arb_dashboard()]) {
+        render_dashboard(&data);
+    }
+
+    #[test]
+    fn dashboard_updates_never_panic(samples in prop::collection::vec(arb_dashboard(), 1..4)) {
+        let mut app = test_app();
+        for data in samples {
+            app.apply_dashboard(data);
+        }
+        prop_assert!(app.rsrp_history.iter().all(|v| *v <= 140));
+        prop_assert!(app.download_speed.is_none_or(|s| s >= 0.0));
+        prop_assert!(app.upload_speed.is_none_or(|s| s >= 0.0));
+    }
+
+    #[test]
+    fn neighbor_parser_is_bounded(data in prop_oneof![arb_json(), arb_neighbors()]) {
+        let cells = parse_neighbor_cells(&data);
+        prop_assert!(cells.len() <= MAX_NEIGHBOR_CELLS);
+        let _ = build_neighbor_text(&cells, serving_rsrp(&data), 0);
+    }
+}
+
+#[test]
+fn huge_neighbor_count_is_capped() {
+    let data = serde_json::json!({ "lenghtt": "4294967295" });
+    assert_eq!(parse_neighbor_cells(&data).len(), MAX_NEIGHBOR_CELLS);
+}
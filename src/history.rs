@@ -0,0 +1,184 @@
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::cli::OutputFormat;
+use crate::client::{failure_reason, is_success};
+use crate::dashboard::render_columns;
+use crate::error::ZitelError;
+use crate::logging::redact_command;
+use crate::pager;
+use crate::terminal;
+use crate::timestamp;
+
+// ---------- change history ----------
+// Every change sent to a router, one JSON line each, for finding out later
+// what changed the band and when. Passwords and PINs are masked as in the
+// debug log; dry runs and replays change nothing and are not written.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config").join("zitel").join("history.jsonl"))
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub time: String,
+    pub router: String,
+    /// The API command, e.g. `set_band_lock`.
+    pub action: String,
+    pub params: String,
+    pub success: bool,
+    /// "success", or why the router refused or could not be reached.
+    pub result: String,
+}
+
+impl Entry {
+    pub fn new(router: &str, command: &str, outcome: &Result<Value, ZitelError>) -> Self {
+        let command = redact_command(command);
+        let (action, params) = command.split_once(' ').unwrap_or((&command, ""));
+        let (success, result) = match outcome {
+            Ok(resp) if is_success(resp) => (true, "success".to_string()),
+            Ok(resp) => (
+                false,
+                failure_reason(resp)
+                    .or_else(|| resp["status"].as_str())
+                    .unwrap_or("refused")
+                    .to_string(),
+            ),
+            Err(e) => (false, e.to_string()),
+        };
+        Self {
+            time: timestamp::now_iso(),
+            router: router.to_string(),
+            action: action.to_string(),
+            params: params.to_string(),
+            success,
+            result,
+        }
+    }
+}
+
+pub fn append(path: &Path, entry: &Entry) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)
+}
+
+/// The entries in the order they were written; lines that do not parse,
+/// e.g. one cut short by a full disk, are skipped.
+pub fn read(path: &Path) -> io::Result<Vec<Entry>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                log::warn!("Skipping a history line that does not parse: {}", e);
+                None
+            }
+        })
+        .collect())
+}
+
+pub fn run(path: &Path, limit: usize, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let entries = read(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let recent = &entries[entries.len().saturating_sub(limit)..];
+    match format {
+        OutputFormat::Json | OutputFormat::Raw => {
+            println!("{}", serde_json::to_string_pretty(recent)?)
+        }
+        OutputFormat::Text if recent.is_empty() => {
+            println!("No changes recorded in {}", path.display())
+        }
+        OutputFormat::Text => {
+            let rows: Vec<Vec<String>> = recent
+                .iter()
+                .map(|e| {
+                    vec![
+                        e.time.clone(),
+                        e.router.clone(),
+                        e.action.clone(),
+                        e.params.clone(),
+                        e.result.replace('\n', "; "),
+                    ]
+                })
+                .collect();
+            pager::print(&render_columns(
+                "Change history",
+                &["Time", "Router", "Action", "Parameters", "Result"],
+                &rows,
+                terminal::width(),
+            ));
+            if recent.len() < entries.len() {
+                println!(
+                    "{} older changes not shown, see --limit",
+                    entries.len() - recent.len()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn entries_mask_secrets_and_keep_the_reason() {
+        let ok = Entry::new(
+            "http://192.168.0.1",
+            "set_band_lock 1850",
+            &Ok(json!({ "status": "success" })),
+        );
+        assert_eq!(
+            (ok.action.as_str(), ok.params.as_str()),
+            ("set_band_lock", "1850")
+        );
+        assert_eq!((ok.success, ok.result.as_str()), (true, "success"));
+
+        let pin = Entry::new(
+            "http://192.168.0.1",
+            "set_sim_pin 1234",
+            &Ok(json!({ "status": "fail", "reason": "wrong PIN" })),
+        );
+        assert_eq!(pin.params, "***");
+        assert_eq!((pin.success, pin.result.as_str()), (false, "wrong PIN"));
+
+        let bare = Entry::new("http://192.168.0.1", "clear_band_lock", &Ok(json!({})));
+        assert_eq!(
+            (bare.params.as_str(), bare.result.as_str()),
+            ("", "refused")
+        );
+    }
+
+    #[test]
+    fn the_file_is_appended_to() {
+        let path = std::env::temp_dir().join(format!("zitel-history-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert!(read(&path).unwrap().is_empty());
+        let first = Entry::new("r", "reboot", &Ok(json!({ "status": "success" })));
+        let second = Entry::new("r", "set_dmz 0 tcpudp", &Ok(json!({ "status": "success" })));
+        append(&path, &first).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"time\":\n")
+            .unwrap();
+        append(&path, &second).unwrap();
+        assert_eq!(read(&path).unwrap(), [first, second]);
+        let _ = fs::remove_file(&path);
+    }
+}
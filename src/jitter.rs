@@ -0,0 +1,65 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, SystemTime};
+
+// ---------- jitter ----------
+// Several clients polling one router at the same interval end up asking in
+// step. With --jitter every wait between polls, and before a retry, is
+// drawn from ± that percentage of it. Off (0) unless asked for, so timings
+// stay exact.
+pub const MAX_PERCENT: u8 = 50;
+
+static PERCENT: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_percent(percent: u8) {
+    PERCENT.store(percent.min(MAX_PERCENT), Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    PERCENT.load(Ordering::Relaxed) > 0
+}
+
+/// `wait`, moved by up to the configured percentage either way.
+pub fn apply(wait: Duration) -> Duration {
+    match PERCENT.load(Ordering::Relaxed) {
+        0 => wait,
+        percent => spread(wait, percent, random_unit()),
+    }
+}
+
+// `unit` in [0, 1) picks where in the range the wait lands, 0.5 is `wait`
+fn spread(wait: Duration, percent: u8, unit: f64) -> Duration {
+    let offset = f64::from(percent) / 100.0 * (2.0 * unit - 1.0);
+    wait.mul_f64(1.0 + offset)
+}
+
+// Random enough to spread polls, from the seed std draws for hash maps; not
+// for anything that has to be unpredictable.
+fn random_unit() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    hasher.write_u128(now.as_nanos());
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waits_stay_within_the_range() {
+        let wait = Duration::from_secs(10);
+        assert_eq!(spread(wait, 20, 0.0), Duration::from_secs(8));
+        assert_eq!(spread(wait, 20, 0.5), wait);
+        assert!(spread(wait, 20, 0.999_999) < Duration::from_secs(12));
+        for _ in 0..100 {
+            let unit = random_unit();
+            assert!((0.0..1.0).contains(&unit), "{}", unit);
+        }
+        // the default leaves waits alone
+        assert_eq!(apply(wait), wait);
+    }
+}
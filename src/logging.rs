@@ -0,0 +1,178 @@
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use log::{LevelFilter, Log, Metadata, Record};
+use serde_json::Value;
+
+use crate::timestamp;
+
+// ---------- request logging ----------
+// -v logs every command with its URL and HTTP status, -vv the response
+// bodies as well. Only this crate's records are printed; reqwest and hyper
+// log through the same facade and would drown them out.
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target().starts_with(env!("CARGO_PKG_NAME"))
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!(
+                "[{} {}] {}",
+                timestamp::now_display(),
+                record.level(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+pub fn init(verbosity: u8) {
+    let level = match verbosity {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+// Commands ending in a secret, with the number of leading arguments that
+// are not part of it.
+const SECRET_COMMANDS: &[(&str, usize)] = &[("set_sim_pin", 0), ("set_wifi", 2), ("set_apn", 3)];
+
+/// The command as it may appear in a log: the secret at the end of a
+/// secret command is replaced.
+pub fn redact_command(command: &str) -> Cow<'_, str> {
+    let name = command.split(' ').next().unwrap_or_default();
+    match SECRET_COMMANDS.iter().find(|(secret, _)| *secret == name) {
+        Some(&(_, public)) => {
+            let kept: Vec<&str> = command.splitn(public + 2, ' ').take(public + 1).collect();
+            Cow::Owned(format!("{} ***", kept.join(" ")))
+        }
+        None => Cow::Borrowed(command),
+    }
+}
+
+/// A response body as it may appear in a log, with any session token
+/// replaced.
+pub fn redact_body(body: &str) -> Cow<'_, str> {
+    match serde_json::from_str::<Value>(body) {
+        Ok(Value::Object(mut map)) if map.contains_key("token") => {
+            map.insert("token".to_string(), "***".into());
+            Cow::Owned(Value::Object(map).to_string())
+        }
+        _ => Cow::Borrowed(body),
+    }
+}
+
+// Keys naming the SIM and the modem; together they identify the subscriber.
+const IDENTIFIER_KEYS: &[&str] = &["imsi", "iccid", "imei"];
+
+// Printed output shows identifiers in full unless --redact; files meant to
+// be shared (CSV and HTML exports, saved responses) mask them unless
+// --no-redact.
+static REDACT_OUTPUT: AtomicBool = AtomicBool::new(false);
+static REDACT_EXPORTS: AtomicBool = AtomicBool::new(true);
+
+pub fn set_redaction(redact: bool, no_redact: bool) {
+    REDACT_OUTPUT.store(redact, Ordering::Relaxed);
+    REDACT_EXPORTS.store(!no_redact, Ordering::Relaxed);
+}
+
+/// `data` with identifiers masked if the settings ask for it, for printed
+/// output or, with `export`, for a file.
+pub fn redacted(data: &Value, export: bool) -> Cow<'_, Value> {
+    let setting = if export {
+        &REDACT_EXPORTS
+    } else {
+        &REDACT_OUTPUT
+    };
+    if !setting.load(Ordering::Relaxed) {
+        return Cow::Borrowed(data);
+    }
+    let mut data = data.clone();
+    redact_identifiers(&mut data);
+    Cow::Owned(data)
+}
+
+/// All but the last four characters replaced, enough to tell two SIMs
+/// apart in a screenshot.
+pub fn mask_identifier(text: &str) -> String {
+    let count = text.chars().count();
+    text.chars()
+        .enumerate()
+        .map(|(i, c)| if i + 4 < count { '*' } else { c })
+        .collect()
+}
+
+/// Masks IMSI, ICCID and IMEI values (keys containing them, any case) and
+/// the session token anywhere in `value`.
+pub fn redact_identifiers(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, entry) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if key == "token" {
+                    *entry = "***".into();
+                } else if IDENTIFIER_KEYS.iter().any(|k| key.contains(k)) {
+                    let text = match entry {
+                        Value::String(s) => s.clone(),
+                        Value::Number(n) => n.to_string(),
+                        _ => continue,
+                    };
+                    *entry = mask_identifier(&text).into();
+                } else {
+                    redact_identifiers(entry);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_identifiers),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifiers_keep_only_their_last_four_digits() {
+        let mut data = serde_json::json!({
+            "IMEI": "356938035643809",
+            "sim": { "iccid": 8944110068256270054u64, "operator": "Vodafone" },
+            "RSRP": "-95",
+        });
+        redact_identifiers(&mut data);
+        assert_eq!(data["IMEI"], "***********3809");
+        assert_eq!(data["sim"]["iccid"], "***************0054");
+        assert_eq!(data["sim"]["operator"], "Vodafone");
+        assert_eq!(data["RSRP"], "-95");
+    }
+
+    #[test]
+    fn secrets_are_kept_out_of_logs() {
+        assert_eq!(redact_command("set_sim_pin 1234"), "set_sim_pin ***");
+        assert_eq!(
+            redact_command("set_wifi 5g Home-5G secret pass"),
+            "set_wifi 5g Home-5G ***"
+        );
+        assert_eq!(
+            redact_command("set_apn vzwinternet chap me pw"),
+            "set_apn vzwinternet chap me ***"
+        );
+        assert_eq!(redact_command("get_index_data"), "get_index_data");
+        assert_eq!(
+            redact_body(r#"{"status":"success","token":"abc123"}"#),
+            r#"{"status":"success","token":"***"}"#
+        );
+        assert_eq!(redact_body("<html>"), "<html>");
+    }
+}
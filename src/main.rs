@@ -1,55 +1,227 @@
+#[cfg(test)]
+mod api_tests;
+mod apn;
+mod band_lock;
+mod band_scan;
+mod capabilities;
+mod cell_lock;
+mod charset;
+mod cli;
+mod client;
+mod color;
+mod command_line;
+mod config;
+mod credentials;
+mod dashboard;
+mod data_cap;
+mod devices;
+mod discovery;
+mod dmz;
+mod doctor;
+mod earfcn;
+mod error;
+mod fields;
+#[cfg(test)]
+mod fuzz_tests;
+mod history;
+mod jitter;
+mod logging;
+mod metrics;
+mod monitor;
+mod mqtt;
+mod neighbors;
+mod network_mode;
+mod operator;
+mod pager;
+mod ping;
+mod port_forward;
+mod prometheus;
+mod prompt;
+mod raw;
+mod reboot;
+mod replay;
+mod report;
+mod rpc;
+mod secrets;
+mod sha256;
+mod signal;
+mod sim;
+mod sms;
+mod status;
+mod terminal;
+mod throughput;
+mod timestamp;
+mod timing;
+mod token_cache;
+mod usage;
+mod wait;
+mod wifi;
+
+use std::borrow::Cow;
 use std::collections::VecDeque;
+use std::env;
 use std::error::Error;
-use std::io;
+use std::io::{self, IsTerminal};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
+use clap::Parser;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Sparkline, Wrap},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Row, Sparkline, Table, Wrap},
     Frame, Terminal,
 };
-use reqwest::Client;
 use serde_json::Value;
 use tokio::sync::mpsc;
 
+use crate::band_lock::{LockStatus, STATUS_LABEL};
+use crate::cell_lock::CellLockAction;
+use crate::cli::{
+    ApnCommand, Cli, Command, ForwardCommand, OutputFormat, SimCommand, SmsCommand, UsageCommand,
+    WifiCommand,
+};
+use crate::client::{
+    build_http_client, failure_reason, is_read_command, is_success, lock_cleared, merge_headers,
+    RetryPolicy, RouterClient,
+};
+use crate::command_line::{CommandLine, TuiCommand};
+use crate::config::Config;
+use crate::dashboard::{
+    any_reported, cpu_average, format_bytes, format_duration, seconds, CELL_FIELDS,
+    CONNECTION_FIELDS, INTERNET_KEY, IP_FIELDS, NETWORK_FIELDS, NOT_AVAILABLE, RECEIVED_KEY,
+    SENT_KEY, SYSTEM_FIELDS, UPTIME_FIELDS, WAN_STAT_FIELDS,
+};
+use crate::dmz::{parse_dmz_status, DmzAction};
+use crate::error::ZitelError;
+use crate::fields::{aliased_parse, field};
+use crate::neighbors::{parse_neighbor_cells, NeighborCell};
+use crate::network_mode::{NetworkMode, Technology};
+use crate::port_forward::{ForwardAction, NewRule, Protocol};
+use crate::signal::{Quality, SignalMetrics};
+use crate::sim::{parse_sim_status, SimAction, SimStatus};
+
 // ---------- constants ----------
-const BASE_URL: &str = "http://192.168.0.1";
+pub(crate) const DEFAULT_BASE_URL: &str = "http://192.168.0.1";
 const DEFAULT_DMZ_IP: &str = "192.168.0.92";
 const RSRP_HISTORY_LEN: usize = 100;
 const SPEED_HISTORY_LEN: usize = 100;
-const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_REFRESH_SECS: u64 = 1;
+const DEFAULT_TOKEN_TTL_SECS: u64 = 600;
 
 // ---------- application state ----------
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq)]
 enum Page {
     Dashboard,
     NeighborCells,
     BandLock,
     Dmz,
+    Devices,
+    Sms,
+    All,
+}
+
+struct PageInfo {
+    page: Page,
+    /// What --pages, the config file and the command line call it.
+    name: &'static str,
+    title: &'static str,
+    draw: fn(&mut Frame, &mut App),
 }
 
+// Every page, in the default tab order.
+const PAGES: [PageInfo; 7] = [
+    PageInfo {
+        page: Page::Dashboard,
+        name: "dashboard",
+        title: "Dashboard",
+        draw: draw_dashboard,
+    },
+    PageInfo {
+        page: Page::NeighborCells,
+        name: "neighbors",
+        title: "Neighbors",
+        draw: draw_neighbor_cells,
+    },
+    PageInfo {
+        page: Page::BandLock,
+        name: "bandlock",
+        title: "BandLock",
+        draw: draw_band_lock,
+    },
+    PageInfo {
+        page: Page::Dmz,
+        name: "dmz",
+        title: "DMZ",
+        draw: draw_dmz,
+    },
+    PageInfo {
+        page: Page::Devices,
+        name: "devices",
+        title: "Devices",
+        draw: draw_devices,
+    },
+    PageInfo {
+        page: Page::Sms,
+        name: "sms",
+        title: "SMS",
+        draw: draw_sms,
+    },
+    PageInfo {
+        page: Page::All,
+        name: "all",
+        title: "All",
+        draw: draw_all,
+    },
+];
+
 impl Page {
-    fn index(&self) -> usize {
-        match self {
-            Page::Dashboard => 0,
-            Page::NeighborCells => 1,
-            Page::BandLock => 2,
-            Page::Dmz => 3,
+    fn info(self) -> &'static PageInfo {
+        PAGES.iter().find(|p| p.page == self).unwrap_or(&PAGES[0])
+    }
+
+    fn named(name: &str) -> Option<Page> {
+        let name = name.trim().to_lowercase();
+        let name = if name == "neighbours" {
+            "neighbors"
+        } else {
+            &name
+        };
+        PAGES.iter().find(|p| p.name == name).map(|p| p.page)
+    }
+}
+
+/// The tabs to show, in the order given; every page when `names` is empty.
+fn select_pages(names: &[String]) -> Result<Vec<Page>, String> {
+    if names.is_empty() {
+        return Ok(PAGES.iter().map(|p| p.page).collect());
+    }
+    let mut pages = Vec::new();
+    for name in names {
+        let page = Page::named(name).ok_or_else(|| {
+            let known: Vec<_> = PAGES.iter().map(|p| p.name).collect();
+            format!("Unknown page {:?}, pages: {}", name, known.join(", "))
+        })?;
+        if !pages.contains(&page) {
+            pages.push(page);
         }
     }
+    Ok(pages)
 }
 
 struct BandLockState {
     items: Vec<String>,
     state: ListState,
+    // from the change history, then locks applied during this session
+    band_lock: LockStatus,
+    // locks applied during this session (None = not known)
+    active_cell: Option<String>,
+    active_mode: Option<NetworkMode>,
 }
 
 impl BandLockState {
@@ -61,27 +233,100 @@ impl BandLockState {
                 "42890".to_string(),
             ],
             state: ListState::default().with_selected(Some(0)),
+            band_lock: LockStatus::Unknown,
+            active_cell: None,
+            active_mode: None,
+        }
+    }
+}
+
+// Changes that wait for a y on the next key press.
+enum Confirm {
+    Quit,
+    Reboot,
+    BandLock(String),
+    BandUnlock,
+    ClearCellLock,
+    NetworkMode(NetworkMode),
+    SetDmz {
+        ip: String,
+        protocol: Protocol,
+        warning: Option<String>,
+    },
+    DisableDmz,
+    Raw(String),
+}
+
+impl Confirm {
+    fn question(&self) -> String {
+        match self {
+            Confirm::Quit => "Quit zitel?".to_string(),
+            Confirm::Reboot => "Reboot the router?".to_string(),
+            Confirm::BandLock(earfcn) => match earfcn::parse_earfcn(earfcn) {
+                Ok(earfcn) => format!("Lock to {}?", earfcn::describe(earfcn)),
+                Err(_) => format!("Lock to EARFCN {}?", earfcn),
+            },
+            Confirm::BandUnlock => "Clear the band lock?".to_string(),
+            Confirm::ClearCellLock => "Clear the cell lock?".to_string(),
+            Confirm::NetworkMode(mode) => format!(
+                "{}. Switch to {}?",
+                network_mode::DROP_WARNING,
+                mode.describe()
+            ),
+            Confirm::SetDmz {
+                ip,
+                protocol,
+                warning: None,
+            } => format!("Make {} the DMZ host ({})?", ip, protocol.describe()),
+            Confirm::SetDmz {
+                ip,
+                protocol,
+                warning: Some(warning),
+            } => format!(
+                "{}! Make {} the DMZ host ({}) anyway?",
+                warning,
+                ip,
+                protocol.describe()
+            ),
+            Confirm::DisableDmz => "Turn the DMZ off?".to_string(),
+            Confirm::Raw(command) => format!("{}. Send {:?}?", raw::WARNING, command),
         }
     }
 }
 
 struct App {
-    token: String,
-    auth_header: String,
     page: Page,
+    // the tabs, in order; never empty
+    pages: Vec<Page>,
     index_data: Value,
     neighbour_data: Value,
+    // rank of the highlighted neighbour cell, minus one
+    neighbour_selected: usize,
+    devices_data: Value,
+    sms_inbox: Result<Vec<sms::Sms>, String>,
+    sms_state: ListState,
     dmz_response: Option<String>,
     band_lock_response: Option<String>,
     rsrp_history: VecDeque<u64>,
     dmz_ip_input: String,
+    dmz_protocol: Protocol,
+    default_dmz_ip: String,
     band_lock_state: BandLockState,
     status_message: String,
-    last_refresh_request: Instant,
+    notice: Option<String>,
+    confirm: Option<Confirm>,
+    command_line: CommandLine,
+    // no refreshes while the router is down, they would only queue up
+    rebooting: bool,
+    auto_refresh: bool,
+    refresh_interval: Duration,
+    last_updated: Option<String>,
     request_tx: mpsc::UnboundedSender<(Request, mpsc::UnboundedSender<Response>)>,
 
-    // lazy neighbour fetch
+    // lazy neighbour, device and SMS fetch
     neighbour_fetched: bool,
+    devices_fetched: bool,
+    sms_fetched: bool,
 
     // traffic tracking (per‑dashboard request)
     last_dashboard_time: Option<Instant>,
@@ -99,40 +344,108 @@ struct App {
 
 // --- communication with background task ---
 enum Request {
-    RefreshDashboard,
+    /// The dashboard, plus the lists of the page on screen, fetched at once.
+    Refresh {
+        neighbors: bool,
+        devices: bool,
+    },
     FetchNeighbors,
-    SetBandLock { index: usize, earfcn: String },
-    SetDmz { ip: String },
+    FetchDevices,
+    FetchSms,
+    SetBandLock {
+        earfcn: String,
+    },
+    ClearBandLock,
+    ClearCellLock,
+    SetNetworkMode {
+        mode: NetworkMode,
+    },
+    SetDmz {
+        ip: String,
+        protocol: Protocol,
+    },
+    DisableDmz,
+    ShowDmz,
+    Raw {
+        command: String,
+    },
+    Reboot,
 }
 
 enum Response {
-    DashboardData { data: Value, error: Option<String> },
-    NeighborData { data: Value, error: Option<String> },
-    BandLockResult { earfcn: String, result: String },
+    DashboardData {
+        data: Value,
+        error: Option<String>,
+    },
+    NeighborData {
+        data: Value,
+        error: Option<String>,
+    },
+    DeviceData {
+        data: Value,
+        error: Option<String>,
+    },
+    SmsData(Result<Vec<sms::Sms>, String>),
+    BandLockResult {
+        earfcn: String,
+        success: bool,
+        result: String,
+    },
+    BandLockCleared {
+        success: bool,
+        result: String,
+    },
+    CellLockCleared {
+        success: bool,
+        result: String,
+    },
+    NetworkModeResult {
+        mode: NetworkMode,
+        success: bool,
+        result: String,
+    },
     DmzResult(String),
+    RawResult(String),
+    RebootResult(String),
+    // one-line warning from the router client, e.g. a silent re-login
+    Notice(String),
 }
 
 impl App {
     fn new(
-        token: String,
-        auth_header: String,
         request_tx: mpsc::UnboundedSender<(Request, mpsc::UnboundedSender<Response>)>,
+        default_dmz_ip: String,
+        refresh_interval: Duration,
+        pages: Vec<Page>,
     ) -> Self {
         Self {
-            token,
-            auth_header,
-            page: Page::Dashboard,
+            page: pages[0],
+            pages,
             index_data: Value::Null,
             neighbour_data: Value::Null,
+            neighbour_selected: 0,
+            devices_data: Value::Null,
+            sms_inbox: Ok(vec![]),
+            sms_state: ListState::default().with_selected(Some(0)),
             dmz_response: None,
             band_lock_response: None,
             rsrp_history: VecDeque::with_capacity(RSRP_HISTORY_LEN),
             dmz_ip_input: String::new(),
+            dmz_protocol: Protocol::Both,
+            default_dmz_ip,
             band_lock_state: BandLockState::new(),
             status_message: String::new(),
-            last_refresh_request: Instant::now(),
+            notice: None,
+            confirm: None,
+            command_line: CommandLine::new(command_line::default_history_path()),
+            rebooting: false,
+            auto_refresh: true,
+            refresh_interval,
+            last_updated: None,
             request_tx,
             neighbour_fetched: false,
+            devices_fetched: false,
+            sms_fetched: false,
             last_dashboard_time: None,
             prev_receive: None,
             prev_sent: None,
@@ -143,52 +456,82 @@ impl App {
         }
     }
 
+    // position of the current page among the tabs
+    fn tab_index(&self) -> usize {
+        self.pages.iter().position(|&p| p == self.page).unwrap_or(0)
+    }
+
     fn next_page(&mut self) {
-        self.page = match self.page {
-            Page::Dashboard => Page::NeighborCells,
-            Page::NeighborCells => Page::BandLock,
-            Page::BandLock => Page::Dmz,
-            Page::Dmz => Page::Dashboard,
-        };
+        let next = (self.tab_index() + 1) % self.pages.len();
+        self.page = self.pages[next];
     }
 
     fn previous_page(&mut self) {
-        self.page = match self.page {
-            Page::Dashboard => Page::Dmz,
-            Page::NeighborCells => Page::Dashboard,
-            Page::BandLock => Page::NeighborCells,
-            Page::Dmz => Page::BandLock,
-        };
+        let count = self.pages.len();
+        self.page = self.pages[(self.tab_index() + count - 1) % count];
     }
 
+    /// Shows the tab at `idx` (0-based); there is nothing to do past the last.
     fn go_to_page(&mut self, idx: usize) {
-        self.page = match idx {
-            1 => Page::NeighborCells,
-            2 => Page::BandLock,
-            3 => Page::Dmz,
-            _ => Page::Dashboard,
-        };
+        if let Some(&page) = self.pages.get(idx) {
+            self.page = page;
+        }
+    }
+
+    /// Shows `page` unless it was left out of the tabs.
+    fn show(&mut self, page: Page) -> Result<(), String> {
+        if !self.pages.contains(&page) {
+            return Err(format!(
+                "The {} page is hidden, see --pages",
+                page.info().name
+            ));
+        }
+        self.page = page;
+        Ok(())
+    }
+
+    /// Asks before making `ip` the DMZ host, warning when it is outside the
+    /// router's LAN.
+    fn confirm_dmz(&mut self, ip: &str) {
+        match dmz::parse_host(ip) {
+            Ok(host) => {
+                let warning = dmz::lan_subnet(&self.index_data)
+                    .and_then(|(lan, mask)| dmz::lan_warning(host, lan, mask));
+                self.confirm = Some(Confirm::SetDmz {
+                    ip: host.to_string(),
+                    protocol: self.dmz_protocol,
+                    warning,
+                });
+            }
+            Err(e) => self.dmz_response = Some(format!("Error: {}", e)),
+        }
+    }
+
+    fn apply_dashboard(&mut self, data: Value) {
+        let data = logging::redacted(&data, false).into_owned();
+        if let Some(val) = SignalMetrics::from_data(&data).rsrp {
+            let abs_val = u64::from(val.unsigned_abs().min(140));
+            if self.rsrp_history.len() >= RSRP_HISTORY_LEN {
+                self.rsrp_history.pop_front();
+            }
+            self.rsrp_history.push_back(abs_val);
+        }
+        self.index_data = data;
+        self.update_traffic();
     }
 
     fn update_traffic(&mut self) {
-        let current_rx = self.index_data["recieve"]
-            .as_str()
-            .and_then(|s| s.parse::<u64>().ok())
-            .unwrap_or(0);
-        let current_tx = self.index_data["sentt"]
-            .as_str()
-            .and_then(|s| s.parse::<u64>().ok())
-            .unwrap_or(0);
+        let current_rx = aliased_parse::<u64>(&self.index_data, RECEIVED_KEY).unwrap_or(0);
+        let current_tx = aliased_parse::<u64>(&self.index_data, SENT_KEY).unwrap_or(0);
 
         if let (Some(prev_rx), Some(prev_tx), Some(prev_time)) =
             (self.prev_receive, self.prev_sent, self.last_dashboard_time)
         {
-            let elapsed = prev_time.elapsed().as_secs_f64();
-            if elapsed > 0.0 {
-                let dl_bytes = current_rx.saturating_sub(prev_rx) as f64;
-                let ul_bytes = current_tx.saturating_sub(prev_tx) as f64;
-                self.download_speed = Some((dl_bytes * 8.0) / (elapsed * 1_000_000.0));
-                self.upload_speed = Some((ul_bytes * 8.0) / (elapsed * 1_000_000.0));
+            let elapsed = prev_time.elapsed();
+            if !elapsed.is_zero() {
+                // a reset counter gives no speed rather than a bogus one
+                self.download_speed = throughput::rate(prev_rx, current_rx, elapsed).mbps();
+                self.upload_speed = throughput::rate(prev_tx, current_tx, elapsed).mbps();
 
                 // scale to u64 for sparkline (Mbps * 10)
                 if let Some(dl) = self.download_speed {
@@ -214,109 +557,208 @@ impl App {
     }
 }
 
-// ---------- API helpers ----------
-async fn authenticate() -> Result<(String, String), Box<dyn Error>> {
-    let client = Client::builder().timeout(Duration::from_secs(10)).build()?;
-
-    let url = format!("{}/authenticate.leano", BASE_URL);
-    let xml_data = "authenticate admin admin";
-
-    let response = client
-        .post(&url)
-        .header(
-            "Content-Type",
-            "application/x-www-form-urlencoded; charset=UTF-8",
-        )
-        .body(xml_data)
-        .send()
-        .await?;
-
-    let json: Value = response.json().await?;
-
-    if json["status"] == "success" {
-        let token = json["token"].as_str().unwrap_or("").to_string();
-        let auth_header = token.clone();
-        Ok((token, auth_header))
-    } else {
-        Err("Authentication failed".into())
+/// Sets `stop` on SIGINT, or SIGTERM on Unix.
+async fn wait_for_stop_signal(stop: Arc<AtomicBool>) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let Ok(mut term) = signal(SignalKind::terminate()) else {
+            let _ = tokio::signal::ctrl_c().await;
+            stop.store(true, Ordering::Relaxed);
+            return;
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = term.recv() => {}
+        }
     }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+    stop.store(true, Ordering::Relaxed);
 }
 
-async fn api_request(auth_header: &str, command: &str) -> Result<Value, Box<dyn Error>> {
-    let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
-
-    let url = format!("{}/api.leano", BASE_URL);
-
-    let response = client
-        .post(&url)
-        .header(
-            "Content-Type",
-            "application/x-www-form-urlencoded; charset=UTF-8",
-        )
-        .header("Leano_Auth", auth_header)
-        .header("Accept", "*/*")
-        .header("X-Requested-With", "XMLHttpRequest")
-        .body(command.to_string())
-        .send()
-        .await?;
-
-    let json: Value = response.json().await?;
-    Ok(json)
+// --- background task runner ---
+fn data_or_error(result: Result<Value, ZitelError>) -> (Value, Option<String>) {
+    match result {
+        Ok(data) => (data, None),
+        Err(e) => (Value::Null, Some(e.to_string())),
+    }
 }
 
-// --- background task runner ---
 async fn run_handlers(
-    auth_header: String,
+    client: RouterClient,
     mut rx: mpsc::UnboundedReceiver<(Request, mpsc::UnboundedSender<Response>)>,
 ) {
     while let Some((request, resp_tx)) = rx.recv().await {
         match request {
-            Request::RefreshDashboard => {
-                let result = api_request(&auth_header, "get_index_data").await;
-                let (data, error) = match result {
-                    Ok(d) => (d, None),
-                    Err(e) => (Value::Null, Some(e.to_string())),
+            Request::Refresh { neighbors, devices } => {
+                // each answer is shown as it is, one failing does not hold
+                // back the others
+                let neighbor_cells = async {
+                    match neighbors {
+                        true => Some(client.api_request("get_neighbour_cell").await),
+                        false => None,
+                    }
+                };
+                let device_list = async {
+                    match devices {
+                        true => Some(client.connected_devices().await),
+                        false => None,
+                    }
                 };
+                let (index, neighbor_cells, device_list) = tokio::join!(
+                    client.api_request("get_index_data"),
+                    neighbor_cells,
+                    device_list
+                );
+                let (data, error) = data_or_error(index);
                 let _ = resp_tx.send(Response::DashboardData { data, error });
+                if let Some(result) = neighbor_cells {
+                    let (data, error) = data_or_error(result);
+                    let _ = resp_tx.send(Response::NeighborData { data, error });
+                }
+                if let Some(result) = device_list {
+                    let (data, error) = data_or_error(result);
+                    let _ = resp_tx.send(Response::DeviceData { data, error });
+                }
             }
             Request::FetchNeighbors => {
-                let result = api_request(&auth_header, "get_neighbour_cell").await;
-                let (data, error) = match result {
-                    Ok(d) => (d, None),
-                    Err(e) => (Value::Null, Some(e.to_string())),
-                };
+                let result = client.api_request("get_neighbour_cell").await;
+                let (data, error) = data_or_error(result);
                 let _ = resp_tx.send(Response::NeighborData { data, error });
             }
-            Request::SetBandLock { index: _, earfcn } => {
-                let command = format!("set_band_lock {}", earfcn);
-                let result = api_request(&auth_header, &command).await;
-                let msg = match result {
-                    Ok(resp) => format!(
-                        "Set to {}: {}",
-                        earfcn,
-                        serde_json::to_string_pretty(&resp).unwrap_or_default()
+            Request::FetchDevices => {
+                let result = client.connected_devices().await;
+                let (data, error) = data_or_error(result);
+                let _ = resp_tx.send(Response::DeviceData { data, error });
+            }
+            Request::FetchSms => {
+                let result = sms::fetch_inbox(&client).await.map_err(|e| e.to_string());
+                let _ = resp_tx.send(Response::SmsData(result));
+            }
+            Request::SetBandLock { earfcn } => {
+                let result = client.set_band_lock(&earfcn).await;
+                let (success, msg) = match result {
+                    Ok(resp) if is_success(&resp) => (
+                        true,
+                        client.outcome(&band_lock::lock_message(&earfcn, &resp)),
                     ),
-                    Err(e) => format!("Error: {}", e),
+                    Ok(resp) => (false, band_lock::lock_message(&earfcn, &resp)),
+                    Err(e) => (false, format!("Error: {}", e)),
                 };
                 let _ = resp_tx.send(Response::BandLockResult {
                     earfcn,
+                    success,
+                    result: msg,
+                });
+            }
+            Request::ClearBandLock => {
+                let result = band_lock::unlock(&client).await;
+                let (success, msg) = match result {
+                    Ok((before, after)) => (
+                        true,
+                        client.outcome(&band_lock::unlock_message(&before, &after)),
+                    ),
+                    Err(e) => (false, format!("Error: {}", e)),
+                };
+                let _ = resp_tx.send(Response::BandLockCleared {
+                    success,
+                    result: msg,
+                });
+            }
+            Request::ClearCellLock => {
+                let result = client.clear_cell_lock().await;
+                let (success, msg) = match result {
+                    Ok(resp) if lock_cleared(&resp) => (true, client.outcome("Cell lock cleared")),
+                    Ok(resp) => (
+                        false,
+                        format!(
+                            "Cell lock clear failed: {}",
+                            failure_reason(&resp).unwrap_or("router did not report success")
+                        ),
+                    ),
+                    Err(e) => (false, format!("Error: {}", e)),
+                };
+                let _ = resp_tx.send(Response::CellLockCleared {
+                    success,
+                    result: msg,
+                });
+            }
+            Request::SetNetworkMode { mode } => {
+                let result = network_mode::switch(&client, mode)
+                    .await
+                    .map_err(|e| e.to_string());
+                let (success, msg) = match result {
+                    Ok(msg) => (true, msg),
+                    Err(e) => (false, format!("Error: {}", e)),
+                };
+                let _ = resp_tx.send(Response::NetworkModeResult {
+                    mode,
+                    success,
                     result: msg,
                 });
             }
-            Request::SetDmz { ip } => {
-                let command = format!("set_dmz 1 tcpudp {}", ip);
-                let result = api_request(&auth_header, &command).await;
+            Request::SetDmz { ip, protocol } => {
+                let result = client.set_dmz(&ip, protocol).await;
                 let msg = match result {
                     Ok(resp) => serde_json::to_string_pretty(&resp).unwrap_or_default(),
                     Err(e) => format!("Error: {}", e),
                 };
                 let _ = resp_tx.send(Response::DmzResult(msg));
             }
+            Request::DisableDmz => {
+                let msg = match dmz::disable(&client).await {
+                    Ok((_, summary)) => summary,
+                    Err(e) => format!("Error: {}", e),
+                };
+                let _ = resp_tx.send(Response::DmzResult(msg));
+            }
+            Request::ShowDmz => {
+                let msg = match client.dmz_status().await {
+                    Ok(resp) => parse_dmz_status(&resp).describe(),
+                    Err(e) => format!("Error: {}", e),
+                };
+                let _ = resp_tx.send(Response::DmzResult(msg));
+            }
+            Request::Raw { command } => {
+                // one line for the footer, `zitel raw` prints it in full
+                let msg = match client.raw_command(&command).await {
+                    Ok(resp) => format!("{}: {}", command, resp),
+                    Err(e) => format!("{}: error: {}", command, e),
+                };
+                let _ = resp_tx.send(Response::RawResult(msg));
+            }
+            Request::Reboot => {
+                let started = Instant::now();
+                // errors become strings before the next await, the boxed
+                // error is not Send
+                let sent = reboot::send_reboot(&client)
+                    .await
+                    .map_err(|e| e.to_string());
+                let msg = match sent {
+                    Ok(()) if client.dry_run() => client.outcome("Reboot sent"),
+                    Ok(()) => match reboot::wait_until_back(&client, started).await {
+                        Ok(elapsed) => {
+                            format!("Router back online after {:.1} s", elapsed.as_secs_f64())
+                        }
+                        Err(e) => e.to_string(),
+                    },
+                    Err(e) => e,
+                };
+                let _ = resp_tx.send(Response::RebootResult(msg));
+            }
         }
     }
 }
 
 // ---------- TUI drawing ----------
+// A bordered block, in ASCII with --ascii
+fn panel<'a>() -> Block<'a> {
+    Block::default()
+        .borders(Borders::ALL)
+        .border_set(charset::border_set())
+}
+
 fn draw_dashboard(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -324,13 +766,16 @@ fn draw_dashboard(f: &mut Frame, app: &mut App) {
         .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
         .split(f.size());
 
-    let title = Paragraph::new("Zitel Router Manager | Dashboard")
-        .style(
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        )
-        .block(Block::default().borders(Borders::ALL));
+    let title = Paragraph::new(format!(
+        "Zitel Router Manager | Dashboard | Last updated {}",
+        app.last_updated.as_deref().unwrap_or("-")
+    ))
+    .style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    )
+    .block(panel());
     f.render_widget(title, chunks[0]);
 
     let main_chunks = Layout::default()
@@ -351,15 +796,15 @@ fn draw_dashboard(f: &mut Frame, app: &mut App) {
         )
         .split(main_chunks[0]);
 
-    let conn_block = Block::default().title("Connection").borders(Borders::ALL);
-    let conn_text = build_connection_text(&app.index_data);
+    let conn_block = panel().title("Connection");
+    let conn_text = build_connection_text(&app.index_data, &app.band_lock_state.band_lock);
     f.render_widget(Paragraph::new(conn_text).block(conn_block), left_chunks[0]);
 
-    let cell_block = Block::default().title("Cell Info").borders(Borders::ALL);
+    let cell_block = panel().title("Cell Info");
     let cell_text = build_cell_text(&app.index_data);
     f.render_widget(Paragraph::new(cell_text).block(cell_block), left_chunks[1]);
 
-    let data_block = Block::default().title("Data Usage").borders(Borders::ALL);
+    let data_block = panel().title("Data Usage");
     let data_text = build_data_usage_text(app);
     f.render_widget(Paragraph::new(data_text).block(data_block), left_chunks[2]);
 
@@ -379,55 +824,75 @@ fn draw_dashboard(f: &mut Frame, app: &mut App) {
 
     let binding = app.rsrp_history.make_contiguous();
     let rsrp_sparkline = Sparkline::default()
-        .block(Block::default().title("RSRP (dBm)").borders(Borders::ALL))
-        .data(&binding)
+        .bar_set(charset::sparkline_set())
+        .block(panel().title("RSRP (dBm)"))
+        .data(binding)
         .style(Style::default().fg(Color::Yellow));
     f.render_widget(rsrp_sparkline, right_chunks[0]);
 
     let dl_spark = Sparkline::default()
-        .block(
-            Block::default()
-                .title("Download (Mbps)")
-                .borders(Borders::ALL),
-        )
+        .bar_set(charset::sparkline_set())
+        .block(panel().title("Download (Mbps)"))
         .data(app.dl_spark_data.make_contiguous())
         .style(Style::default().fg(Color::Green))
         .max(100); // 10.0 Mbps * 10 = 100
     f.render_widget(dl_spark, right_chunks[1]);
 
     let ul_spark = Sparkline::default()
-        .block(
-            Block::default()
-                .title("Upload (Mbps)")
-                .borders(Borders::ALL),
-        )
+        .bar_set(charset::sparkline_set())
+        .block(panel().title("Upload (Mbps)"))
         .data(app.ul_spark_data.make_contiguous())
         .style(Style::default().fg(Color::Red))
         .max(50); // 5.0 Mbps * 10 = 50
     f.render_widget(ul_spark, right_chunks[2]);
 
-    let sys_block = Block::default().title("System").borders(Borders::ALL);
+    let sys_block = panel().title("System");
     let sys_text = build_system_text(&app.index_data);
     f.render_widget(Paragraph::new(sys_text).block(sys_block), right_chunks[3]);
 }
 
-fn build_connection_text(data: &Value) -> Text {
+fn build_connection_text<'a>(data: &'a Value, band_lock: &LockStatus) -> Text<'a> {
+    let reported = [CONNECTION_FIELDS, NETWORK_FIELDS, IP_FIELDS]
+        .iter()
+        .any(|fields| any_reported(data, fields));
+    if !reported && field(data, INTERNET_KEY).is_none() {
+        return not_available();
+    }
     let mut lines = vec![];
-    add_line(&mut lines, "Type", data, "TYPE");
-    add_line(&mut lines, "Band", data, "BAND");
-    add_line(&mut lines, "CSQ", data, "CSQ");
-    add_line(&mut lines, "RSRP", data, "RSRP");
-    add_line(&mut lines, "RSRQ", data, "RSRQ");
-    add_line(&mut lines, "SINR", data, "SINR");
-    add_line(&mut lines, "RSSI", data, "RSSI");
-
-    let public_ip = data["IPV4"]
-        .as_str()
-        .or_else(|| data["wan_ipaddr"].as_str())
-        .unwrap_or("-");
-    let internet_status = data["INTERNET"].as_str().unwrap_or("-");
-    lines.push(Line::from(format!("Public IP:    {}", public_ip)));
+    for f in CONNECTION_FIELDS {
+        match (f.name, field(data, f.spec)) {
+            ("type", Some(reported)) => lines.push(type_line(f.label, &reported)),
+            _ => add_line(&mut lines, f.label, data, f.spec),
+        }
+        if f.name == "type" {
+            lines.push(band_lock_line(band_lock, data));
+        }
+    }
+    add_value_line(&mut lines, "Operator", operator::describe(data));
+    let signals = SignalMetrics::from_data(data);
+    for f in NETWORK_FIELDS {
+        let value = field(data, f.spec);
+        let text = value.as_deref().unwrap_or("-");
+        let style = signals
+            .quality(f.name)
+            .map_or(Style::default(), quality_style);
+        let mut spans = vec![
+            Span::styled(format!("{:12}", f.label), Style::default().fg(Color::Gray)),
+            Span::styled(format!("{:8}", text), style),
+        ];
+        if let Some((min, max)) = signal::bar_range(f.name) {
+            spans.push(Span::styled(
+                format!(" {}", signal::bar_of(signals.get(f.name), min, max)),
+                style,
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+    for f in IP_FIELDS {
+        add_line(&mut lines, f.label, data, f.spec);
+    }
 
+    let internet_status = field(data, INTERNET_KEY).unwrap_or(Cow::Borrowed("-"));
     let status_color = if internet_status.to_lowercase() == "online" {
         Color::Green
     } else {
@@ -438,31 +903,36 @@ fn build_connection_text(data: &Value) -> Text {
         Span::styled(internet_status, Style::default().fg(status_color)),
     ]));
 
+    if let Some(sim) = parse_sim_status(data) {
+        let sim_color = if sim == SimStatus::Ready {
+            Color::Green
+        } else {
+            Color::Red
+        };
+        lines.push(Line::from(vec![
+            Span::raw("SIM:          "),
+            Span::styled(sim.description(), Style::default().fg(sim_color)),
+        ]));
+    }
+
     Text::from(lines)
 }
 
-fn build_cell_text(data: &Value) -> Text {
+fn build_cell_text(data: &Value) -> Text<'_> {
+    if !any_reported(data, CELL_FIELDS) {
+        return not_available();
+    }
     let mut lines = vec![];
-    add_line(&mut lines, "Modem Call Control", data, "MCC");
-    add_line(&mut lines, "MNC", data, "MNC");
-    add_line(&mut lines, "PCI", data, "PCID");
-    add_line(&mut lines, "EARFCN", data, "EARFCN");
-    add_line(&mut lines, "Technical Assistance Center (TAC)", data, "TAC");
-    add_line(&mut lines, "eNodeB", data, "ENODE");
-    add_line(&mut lines, "Cell ID", data, "CELL");
+    for f in CELL_FIELDS {
+        add_line(&mut lines, f.label, data, f.spec);
+    }
     Text::from(lines)
 }
 
-fn build_data_usage_text(app: &App) -> Text {
+fn build_data_usage_text(app: &App) -> Text<'_> {
     let mut lines = vec![];
-    let current_rx = app.index_data["recieve"]
-        .as_str()
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(0);
-    let current_tx = app.index_data["sentt"]
-        .as_str()
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(0);
+    let current_rx = aliased_parse::<u64>(&app.index_data, RECEIVED_KEY).unwrap_or(0);
+    let current_tx = aliased_parse::<u64>(&app.index_data, SENT_KEY).unwrap_or(0);
 
     lines.push(Line::from(format!(
         "Received: {}",
@@ -472,6 +942,14 @@ fn build_data_usage_text(app: &App) -> Text {
         "Sent:     {}",
         format_bytes(current_tx)
     )));
+    if let Some(total) = usage::Usage::read(&app.index_data).total() {
+        lines.push(Line::from(format!("Total:    {}", format_bytes(total))));
+    }
+    for f in WAN_STAT_FIELDS {
+        if let Some(value) = field(&app.index_data, f.spec) {
+            add_value_line(&mut lines, f.label, Some(value));
+        }
+    }
 
     if let Some(dl) = app.download_speed {
         lines.push(Line::from(vec![
@@ -489,53 +967,118 @@ fn build_data_usage_text(app: &App) -> Text {
     Text::from(lines)
 }
 
-fn build_system_text(data: &Value) -> Text {
+fn build_system_text(data: &Value) -> Text<'_> {
+    if !any_reported(data, SYSTEM_FIELDS) && !any_reported(data, UPTIME_FIELDS) {
+        return not_available();
+    }
     let mut lines = vec![];
-    add_line(&mut lines, "Model", data, "model");
-    add_line(&mut lines, "Serial", data, "serial");
-    add_line(&mut lines, "Hardware", data, "hardv");
-    add_line(&mut lines, "Software", data, "sofv");
-    add_line(&mut lines, "Uptime (s)", data, "SYSUP");
-    add_line(&mut lines, "RAM (MB)", data, "ram");
-    add_line(&mut lines, "CPU1 %", data, "cpu1");
-    add_line(&mut lines, "CPU2 %", data, "cpu2");
-
-    if let (Some(c1), Some(c2)) = (
-        data["cpu1"].as_str().and_then(|v| v.parse::<f64>().ok()),
-        data["cpu2"].as_str().and_then(|v| v.parse::<f64>().ok()),
-    ) {
-        let avg = (c1 + c2) / 2.0;
+    for f in SYSTEM_FIELDS {
+        add_line(&mut lines, f.label, data, f.spec);
+    }
+    for f in UPTIME_FIELDS {
+        add_value_line(
+            &mut lines,
+            f.label,
+            seconds(data, f.spec).map(format_duration),
+        );
+    }
+
+    if let Some(avg) = cpu_average(data) {
         lines.push(Line::from(format!("CPU Avg %   {:.1}", avg)));
     }
 
     Text::from(lines)
 }
 
+// a panel whose keys are all missing, instead of a column of dashes
+fn not_available<'a>() -> Text<'a> {
+    Text::styled(NOT_AVAILABLE, Style::default().fg(Color::DarkGray))
+}
+
+fn quality_style(quality: Quality) -> Style {
+    let color = match quality {
+        Quality::Good => Color::Green,
+        Quality::Fair => Color::Yellow,
+        Quality::Poor => Color::Red,
+    };
+    Style::default().fg(color)
+}
+
+// The band lock zitel last set, yellow so it is not missed. The router does
+// not report its lock, so the line says where it comes from.
+fn band_lock_line<'a>(band_lock: &LockStatus, data: &Value) -> Line<'a> {
+    let band_lock = band_lock.checked(data);
+    let style = match band_lock {
+        LockStatus::Locked { .. } => Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+        LockStatus::Cleared { .. } => Style::default(),
+        LockStatus::Unknown => Style::default().fg(Color::DarkGray),
+    };
+    Line::from(vec![
+        Span::styled(
+            format!("{:12}", "Band lock"),
+            Style::default().fg(Color::Gray),
+        ),
+        Span::styled(band_lock.describe(), style),
+        Span::styled(" (last set by zitel)", Style::default().fg(Color::DarkGray)),
+    ])
+}
+
+// The connection type as a badge, with what the router sent after it when
+// that reads differently. The rest of the TUI is colour-coded either way, the
+// badge follows --no-color as printed output does.
+fn type_line<'a>(label: &str, reported: &str) -> Line<'a> {
+    let mut spans = vec![Span::styled(
+        format!("{:12}", label),
+        Style::default().fg(Color::Gray),
+    )];
+    let Some(technology) = Technology::parse(reported) else {
+        spans.push(Span::raw(reported.to_string()));
+        return Line::from(spans);
+    };
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    spans.push(match color::enabled() {
+        true => {
+            let background = match technology {
+                Technology::NrSa | Technology::NrNsa => Color::Green,
+                Technology::Lte | Technology::LteAdvanced => Color::Cyan,
+                Technology::Umts => Color::Yellow,
+                Technology::Gsm => Color::Red,
+            };
+            Span::styled(
+                format!(" {} ", technology.label()),
+                bold.fg(Color::Black).bg(background),
+            )
+        }
+        false => Span::styled(format!("[{}]", technology.label()), bold),
+    });
+    if let Some(spelling) = technology.spelling(reported) {
+        spans.push(Span::styled(
+            format!(" {}", spelling),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans)
+}
+
 fn add_line<'a>(lines: &mut Vec<Line<'a>>, label: &str, data: &'a Value, key: &str) {
-    let val = data[key].as_str().unwrap_or("-");
+    add_value_line(lines, label, field(data, key));
+}
+
+fn add_value_line<'a>(
+    lines: &mut Vec<Line<'a>>,
+    label: &str,
+    value: Option<impl Into<Cow<'a, str>>>,
+) {
+    let val = value.map(Into::into).unwrap_or(Cow::Borrowed("-"));
     lines.push(Line::from(vec![
         Span::styled(format!("{:12}", label), Style::default().fg(Color::Gray)),
         Span::raw(val),
     ]));
 }
 
-fn format_bytes(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
-
-    if bytes >= GB {
-        format!("{:.2} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.2} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.2} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} bytes", bytes)
-    }
-}
-
-fn draw_neighbor_cells(f: &mut Frame, app: &App) {
+fn draw_neighbor_cells(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
@@ -548,37 +1091,42 @@ fn draw_neighbor_cells(f: &mut Frame, app: &App) {
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
         )
-        .block(Block::default().borders(Borders::ALL));
+        .block(panel());
     f.render_widget(title, chunks[0]);
 
-    let data = &app.neighbour_data;
-    let count = data["lenghtt"]
-        .as_str()
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(0);
+    let cells = neighbors::ranked_cells(&app.neighbour_data);
+    let serving = neighbors::serving_rsrp(&app.index_data);
+    let text = build_neighbor_text(&cells, serving, app.neighbour_selected);
+    let paragraph = Paragraph::new(text)
+        .block(panel().title("Up/Down: pick a cell | Enter: lock to its EARFCN"))
+        .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, chunks[1]);
+}
 
+// cells come sorted by signal, so the first one is the best candidate
+fn build_neighbor_text(cells: &[NeighborCell], serving: Option<f64>, selected: usize) -> Text<'_> {
     let mut lines = vec![];
-    lines.push(Line::from(format!("Found {} neighbor cell(s)", count)));
-    for i in 1..=count {
+    lines.push(Line::from(format!(
+        "Found {} neighbor cell(s), strongest first",
+        cells.len()
+    )));
+    for (i, cell) in cells.iter().enumerate() {
         lines.push(Line::from(""));
-        lines.push(Line::from(format!(" Cell {} ", i)));
-        add_line(&mut lines, "MCC", data, &format!("type{}", i));
-        add_line(&mut lines, "MNC", data, &format!("band{}", i));
-        add_line(&mut lines, "Band", data, &format!("pcid{}", i));
-        add_line(&mut lines, "ARFCN", data, &format!("rsrq{}", i));
-        add_line(&mut lines, "PCI", data, &format!("rsrp{}", i));
-        add_line(&mut lines, "Signal(dBm)", data, &format!("rsrppp{}", i));
+        if i == selected {
+            lines.push(Line::styled(
+                format!("> #{} ", i + 1),
+                Style::default().add_modifier(Modifier::REVERSED),
+            ));
+        } else {
+            lines.push(Line::from(format!(" #{} ", i + 1)));
+        }
+        for (label, value) in cell.labeled() {
+            add_value_line(&mut lines, label, value);
+        }
+        let delta = neighbors::delta_text(cell.delta_db(serving));
+        add_value_line(&mut lines, "vs serving", Some(delta));
     }
-
-    let text = Text::from(lines);
-    let paragraph = Paragraph::new(text)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Neighbour Cells"),
-        )
-        .wrap(Wrap { trim: true });
-    f.render_widget(paragraph, chunks[1]);
+    Text::from(lines)
 }
 
 fn draw_band_lock(f: &mut Frame, app: &mut App) {
@@ -590,35 +1138,71 @@ fn draw_band_lock(f: &mut Frame, app: &mut App) {
                 Constraint::Length(3),
                 Constraint::Min(0),
                 Constraint::Length(3),
+                Constraint::Length(4),
             ]
             .as_ref(),
         )
         .split(f.size());
 
-    let title = Paragraph::new("Band Lock – Select EARFCN")
+    let title = Paragraph::new("Band Lock - Select EARFCN")
         .style(
             Style::default()
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
         )
-        .block(Block::default().borders(Borders::ALL));
+        .block(panel());
     f.render_widget(title, chunks[0]);
 
     let list = List::new(
         app.band_lock_state
             .items
             .iter()
-            .map(|i| ListItem::new(i.as_str()))
+            .map(|i| match earfcn::parse_earfcn(i) {
+                Ok(earfcn) => ListItem::new(earfcn::describe(earfcn)),
+                Err(_) => ListItem::new(i.as_str()),
+            })
             .collect::<Vec<_>>(),
     )
-    .block(Block::default().borders(Borders::ALL).title("EARFCN"))
+    .block(panel().title("EARFCN"))
     .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
     .highlight_symbol("> ");
     f.render_stateful_widget(list, chunks[1], &mut app.band_lock_state.state);
 
+    let locks = Line::from(vec![
+        Span::styled(
+            format!("{}: ", STATUS_LABEL),
+            Style::default().fg(Color::Gray),
+        ),
+        Span::raw(
+            app.band_lock_state
+                .band_lock
+                .checked(&app.index_data)
+                .describe(),
+        ),
+        Span::styled("   Cell lock: ", Style::default().fg(Color::Gray)),
+        Span::raw(
+            app.band_lock_state
+                .active_cell
+                .as_deref()
+                .unwrap_or("unknown"),
+        ),
+        Span::styled("   Network mode: ", Style::default().fg(Color::Gray)),
+        Span::raw(
+            app.band_lock_state
+                .active_mode
+                .map_or("unknown", NetworkMode::name),
+        ),
+    ]);
+    let locks_para = Paragraph::new(locks).block(
+        panel().title("Enter: lock band | u: unlock band | x: clear cell lock | m: network mode"),
+    );
+    f.render_widget(locks_para, chunks[2]);
+
     let status = app.band_lock_response.clone().unwrap_or_default();
-    let status_para = Paragraph::new(status).block(Block::default().borders(Borders::ALL));
-    f.render_widget(status_para, chunks[2]);
+    let status_para = Paragraph::new(status)
+        .block(panel())
+        .wrap(Wrap { trim: true });
+    f.render_widget(status_para, chunks[3]);
 }
 
 fn draw_dmz(f: &mut Frame, app: &mut App) {
@@ -641,28 +1225,205 @@ fn draw_dmz(f: &mut Frame, app: &mut App) {
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
         )
-        .block(Block::default().borders(Borders::ALL));
+        .block(panel());
     f.render_widget(title, chunks[0]);
 
     let input = Paragraph::new(format!(
         "DMZ IP (default {}): {}",
-        DEFAULT_DMZ_IP, app.dmz_ip_input
+        app.default_dmz_ip, app.dmz_ip_input
     ))
-    .block(Block::default().borders(Borders::ALL));
+    .block(panel().title(format!(
+        "Enter: set | p: protocol ({}) | o: disable | s: show current",
+        app.dmz_protocol.describe()
+    )));
     f.render_widget(input, chunks[1]);
 
     let status = app.dmz_response.clone().unwrap_or_default();
-    let status_para = Paragraph::new(status).block(Block::default().borders(Borders::ALL));
+    let status_para = Paragraph::new(status).block(panel());
     f.render_widget(status_para, chunks[2]);
 }
 
-fn ui(f: &mut Frame, app: &mut App) {
-    match app.page {
-        Page::Dashboard => draw_dashboard(f, app),
-        Page::NeighborCells => draw_neighbor_cells(f, app),
-        Page::BandLock => draw_band_lock(f, app),
-        Page::Dmz => draw_dmz(f, app),
+// Every dashboard section stacked as `zitel dashboard` prints them, in two
+// columns, all from the one cached get_index_data answer.
+fn draw_all(f: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .split(f.size());
+
+    let title = Paragraph::new(format!(
+        "Zitel Router Manager | All sections | Last updated {}",
+        app.last_updated.as_deref().unwrap_or("-")
+    ))
+    .style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    )
+    .block(panel());
+    f.render_widget(title, chunks[0]);
+
+    let mut sections = dashboard::sections(&app.index_data);
+    dashboard::add_band_lock(
+        &mut sections,
+        &app.index_data,
+        &app.band_lock_state.band_lock,
+    );
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(chunks[1]);
+    for (column, group) in columns
+        .iter()
+        .zip(sections.chunks(sections.len().div_ceil(2)))
+    {
+        let heights: Vec<Constraint> = group
+            .iter()
+            .map(|s| Constraint::Length(section_height(s)))
+            .chain([Constraint::Min(0)])
+            .collect();
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(heights)
+            .split(*column);
+        for (section, area) in group.iter().zip(areas.iter()) {
+            let block = panel().title(section.title);
+            f.render_widget(Paragraph::new(section_text(section)).block(block), *area);
+        }
+    }
+}
+
+fn section_height(section: &dashboard::Section) -> u16 {
+    let lines = match section.has_data() {
+        true => section.rows.len(),
+        false => 1,
+    };
+    u16::try_from(lines + 2).unwrap_or(u16::MAX)
+}
+
+fn section_text<'a>(section: &dashboard::Section) -> Text<'a> {
+    if !section.has_data() {
+        return not_available();
     }
+    let width = section
+        .rows
+        .iter()
+        .map(|r| r.label.len())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let lines: Vec<Line> = section
+        .rows
+        .iter()
+        .map(|row| {
+            let text = row.text.as_deref().unwrap_or("-");
+            if row.name == "type" && row.text.is_some() {
+                return type_line(&format!("{:width$}", row.label), text);
+            }
+            let style = dashboard::row_quality(row, text).map_or(Style::default(), quality_style);
+            Line::from(vec![
+                Span::styled(
+                    format!("{:width$}", row.label),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(text.to_string(), style),
+            ])
+        })
+        .collect();
+    Text::from(lines)
+}
+
+fn draw_devices(f: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .split(f.size());
+
+    let title = Paragraph::new("Connected Devices")
+        .style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .block(panel());
+    f.render_widget(title, chunks[0]);
+
+    let devices = devices::parse_devices(&app.devices_data);
+    let block = panel().title("DHCP Leases");
+    if devices.is_empty() {
+        f.render_widget(
+            Paragraph::new("No devices connected").block(block),
+            chunks[1],
+        );
+        return;
+    }
+    let header = Row::new(devices::HEADERS).style(Style::default().fg(Color::Gray));
+    let rows = devices::device_rows(&devices).into_iter().map(Row::new);
+    let table = Table::new(rows).header(header).block(block).widths(&[
+        Constraint::Percentage(30),
+        Constraint::Percentage(20),
+        Constraint::Percentage(25),
+        Constraint::Percentage(25),
+    ]);
+    f.render_widget(table, chunks[1]);
+}
+
+fn draw_sms(f: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .split(f.size());
+
+    let title = Paragraph::new("SMS Inbox")
+        .style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .block(panel());
+    f.render_widget(title, chunks[0]);
+
+    let block = panel().title("Messages (Up/Down to scroll)");
+    let messages = match &app.sms_inbox {
+        Ok(messages) if !messages.is_empty() => messages,
+        Ok(_) => {
+            f.render_widget(Paragraph::new("No messages").block(block), chunks[1]);
+            return;
+        }
+        Err(e) => {
+            let error = Paragraph::new(e.as_str()).style(Style::default().fg(Color::Yellow));
+            f.render_widget(error.block(block), chunks[1]);
+            return;
+        }
+    };
+    let width = chunks[1].width.saturating_sub(6).max(10) as usize;
+    let items: Vec<ListItem> = messages
+        .iter()
+        .map(|m| {
+            let mut lines = vec![Line::from(vec![
+                Span::styled(
+                    m.sender.clone().unwrap_or_else(|| "-".to_string()),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("  {}", m.time.as_deref().unwrap_or("")),
+                    Style::default().fg(Color::Gray),
+                ),
+            ])];
+            lines.extend(sms::wrap(&m.body, width).into_iter().map(Line::from));
+            lines.push(Line::from(""));
+            ListItem::new(lines)
+        })
+        .collect();
+    let list = List::new(items).block(block).highlight_symbol("> ");
+    f.render_stateful_widget(list, chunks[1], &mut app.sms_state);
+}
+
+fn ui(f: &mut Frame, app: &mut App) {
+    (app.page.info().draw)(f, app);
 
     let footer_rect = Rect::new(
         f.size().x,
@@ -671,35 +1432,586 @@ fn ui(f: &mut Frame, app: &mut App) {
         1,
     );
 
-    let tabs = [" Dashboard ", " Neighbors ", " BandLock ", " DMZ "];
     let mut footer_spans = vec![Span::raw(" Tabs: ")];
-    let active_index = app.page.index();
-    for (i, name) in tabs.iter().enumerate() {
-        let style = if i == active_index {
+    for (i, page) in app.pages.iter().enumerate() {
+        let style = if *page == app.page {
             Style::default().fg(Color::Black).bg(Color::Cyan)
         } else {
             Style::default()
         };
-        footer_spans.push(Span::styled(format!("{}({}) ", name, i + 1), style));
+        let tab = format!(" {} ({}) ", page.info().title, i + 1);
+        footer_spans.push(Span::styled(tab, style));
+    }
+    if app.command_line.active {
+        let hint = match command_line::candidates(&app.command_line.input).as_slice() {
+            [] | [_] => "Enter: run | Esc: cancel".to_string(),
+            several => several.join(" "),
+        };
+        let prompt = vec![
+            Span::styled(
+                format!(":{}_", app.command_line.input),
+                Style::default().fg(Color::White),
+            ),
+            Span::styled(format!("  {}", hint), Style::default().fg(Color::Gray)),
+        ];
+        let footer = Paragraph::new(Line::from(prompt)).style(Style::default().bg(Color::DarkGray));
+        f.render_widget(footer, footer_rect);
+        return;
+    }
+    if let Some(confirm) = &app.confirm {
+        footer_spans.push(Span::styled(
+            format!("| {} y = yes, any other key = no", confirm.question()),
+            Style::default().fg(Color::White).bg(Color::Red),
+        ));
+        let footer =
+            Paragraph::new(Line::from(footer_spans)).style(Style::default().bg(Color::DarkGray));
+        f.render_widget(footer, footer_rect);
+        return;
+    }
+    footer_spans.push(Span::raw("| q: quit | a: all | b: reboot | :: command | "));
+    if app.auto_refresh {
+        footer_spans.push(Span::raw(format!(
+            "r: auto-refresh {}s | ",
+            app.refresh_interval.as_secs()
+        )));
+    } else {
+        footer_spans.push(Span::styled(
+            "r: auto-refresh off",
+            Style::default().fg(Color::Yellow),
+        ));
+        footer_spans.push(Span::raw(" | "));
     }
-    footer_spans.push(Span::raw("| q: quit | "));
     footer_spans.push(Span::raw(&app.status_message));
+    if let Some(notice) = &app.notice {
+        footer_spans.push(Span::styled(
+            format!(" | {}", notice),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
 
     let footer =
         Paragraph::new(Line::from(footer_spans)).style(Style::default().bg(Color::DarkGray));
     f.render_widget(footer, footer_rect);
 }
 
-// ---------- main TUI loop ----------
+// ---------- one-shot commands ----------
+async fn run_command(
+    command: Command,
+    client: &RouterClient,
+    default_dmz_ip: &str,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Dashboard {
+            csv,
+            html,
+            page,
+            all,
+        } => match all {
+            true => dashboard::run_all(client, format).await,
+            false => dashboard::run(client, csv.as_deref(), html.as_deref(), page, format).await,
+        },
+        Command::Dmz {
+            ip,
+            protocol,
+            off,
+            show,
+        } => {
+            let action = if off {
+                DmzAction::Disable
+            } else if show {
+                DmzAction::Show
+            } else {
+                let ip = ip.map_or_else(|| default_dmz_ip.to_string(), |ip| ip.to_string());
+                DmzAction::Set { ip, protocol }
+            };
+            dmz::run(client, action, format).await
+        }
+        Command::BandLock { earfcn, neighbor } => match (earfcn, neighbor) {
+            (Some(earfcn), _) => band_lock::run(client, earfcn, format).await,
+            (None, Some(rank)) => band_lock::run_from_neighbor(client, rank as usize, format).await,
+            (None, None) => unreachable!("clap requires an EARFCN or --neighbor"),
+        },
+        Command::BandUnlock => band_lock::run_unlock(client, format).await,
+        Command::BandScan {
+            earfcns,
+            settle,
+            restore,
+        } => {
+            let options = band_scan::ScanOptions {
+                earfcns,
+                settle: Duration::from_secs(settle.unwrap_or(band_scan::DEFAULT_SETTLE_SECS)),
+                restore,
+            };
+            band_scan::run(client, options, format).await
+        }
+        Command::NetworkMode { mode } => network_mode::run(client, mode, format).await,
+        Command::Monitor {
+            interval,
+            log,
+            graph,
+            no_graph,
+            history,
+            data_cap,
+            cap_alerts,
+            cap_hook,
+        } => {
+            let alerts = match cap_alerts.is_empty() {
+                true => &data_cap::DEFAULT_ALERTS[..],
+                false => &cap_alerts[..],
+            };
+            let data_cap = data_cap.map(|gb| {
+                data_cap::DataCap::new(gb, alerts, client.base_url(), data_cap::default_path())
+            });
+            let options = monitor::MonitorOptions {
+                interval: Duration::from_secs(interval),
+                log,
+                graph: (!no_graph).then_some(graph),
+                history: history.into(),
+                data_cap,
+                cap_hook,
+            };
+            monitor::run(client, options, format).await
+        }
+        Command::Wait {
+            metric,
+            above,
+            below,
+            max_wait,
+            interval,
+            bell,
+        } => {
+            let target = match (above, below) {
+                (Some(limit), _) => wait::Target::Above(limit),
+                (None, Some(limit)) => wait::Target::Below(limit),
+                (None, None) => unreachable!("clap requires --above or --below"),
+            };
+            let options = wait::WaitOptions {
+                metric,
+                target,
+                interval: Duration::from_secs(interval),
+                max_wait: Duration::from_secs(max_wait),
+                bell,
+            };
+            wait::run(client, options, format).await
+        }
+        Command::Mqtt {
+            host,
+            port,
+            broker_user,
+            broker_password,
+            client_id,
+            topic_prefix,
+            discovery_prefix,
+            no_discovery,
+            interval,
+        } => {
+            let options = mqtt::MqttOptions {
+                host,
+                port,
+                username: broker_user,
+                password: broker_password,
+                client_id,
+                topic_prefix,
+                discovery_prefix: (!no_discovery).then_some(discovery_prefix),
+                interval: Duration::from_secs(interval),
+            };
+            mqtt::run(client, options).await
+        }
+        Command::ServeMetrics {
+            listen,
+            interval,
+            max_age,
+        } => {
+            // one missed poll, and jitter, should not fail a probe
+            let max_age = Duration::from_secs(max_age.unwrap_or(interval * 3));
+            prometheus::run(client, listen, Duration::from_secs(interval), max_age).await
+        }
+        Command::Serve { socket, listen } => {
+            let endpoint = match (listen, socket) {
+                (Some(listen), _) => rpc::Endpoint::Tcp(listen),
+                #[cfg(unix)]
+                (None, socket) => rpc::Endpoint::Socket(
+                    socket
+                        .or_else(rpc::default_socket)
+                        .ok_or("No directory for the socket, pass --socket")?,
+                ),
+                #[cfg(not(unix))]
+                (None, Some(_)) => {
+                    return Err("--socket needs Unix sockets, pass --listen instead".into())
+                }
+                #[cfg(not(unix))]
+                (None, None) => rpc::Endpoint::Tcp(rpc::DEFAULT_LISTEN.parse()?),
+            };
+            rpc::run(client, endpoint).await
+        }
+        Command::Throughput { window } => {
+            throughput::run(client, Duration::from_secs(window), format).await
+        }
+        // they need the credentials, run() handles them
+        Command::Login | Command::Logout | Command::Discover | Command::History { .. } => {
+            unreachable!("handled before connecting")
+        }
+        Command::Status { template } => {
+            let template = template.as_deref().unwrap_or(status::DEFAULT_TEMPLATE);
+            status::run(client, template, format).await
+        }
+        Command::Doctor => doctor::run(client, format).await,
+        Command::Reboot => reboot::run(client, format).await,
+        Command::FactoryReset { force } => reboot::run_factory_reset(client, force, format).await,
+        Command::Capabilities => capabilities::run(client, format).await,
+        Command::Raw { command } => raw::run(client, &command.join(" ")).await,
+        Command::Devices => devices::run(client, format).await,
+        Command::Ping { host, count } => ping::run(client, host, count, format).await,
+        Command::Neighbors {
+            watch,
+            interval,
+            log_jsonl,
+            gps_cmd,
+        } => {
+            let options = neighbors::ScanOptions {
+                watch,
+                interval: Duration::from_secs(interval),
+                log_jsonl,
+                gps_cmd,
+            };
+            neighbors::run(client, options, format).await
+        }
+        Command::Forward { action } => {
+            let action = match action {
+                ForwardCommand::List => ForwardAction::List,
+                ForwardCommand::Add {
+                    external_port,
+                    ip,
+                    internal_port,
+                    protocol,
+                } => ForwardAction::Add(NewRule {
+                    external_port,
+                    ip,
+                    internal_port: internal_port.unwrap_or(external_port),
+                    protocol,
+                }),
+                ForwardCommand::Delete { id } => ForwardAction::Delete { id },
+            };
+            port_forward::run(client, action, format).await
+        }
+        Command::CellLock { earfcn, pci, clear } => {
+            let action = match (earfcn, pci) {
+                (Some(earfcn), Some(pci)) if !clear => CellLockAction::Set { earfcn, pci },
+                _ => CellLockAction::Clear,
+            };
+            cell_lock::run(client, action, format).await
+        }
+        Command::Apn { action } => match action {
+            ApnCommand::Show => apn::run_show(client, format).await,
+            ApnCommand::Set {
+                apn,
+                auth,
+                apn_username,
+                apn_password,
+            } => {
+                let change = apn::ApnChange {
+                    apn,
+                    auth,
+                    username: apn_username,
+                    password: apn_password,
+                };
+                apn::run_set(client, change, format).await
+            }
+        },
+        Command::Usage { action } => match action {
+            UsageCommand::Show => usage::run_show(client, format).await,
+            UsageCommand::Reset => usage::run_reset(client, format).await,
+        },
+        Command::Sim { action } => {
+            let action = match action {
+                SimCommand::Status => SimAction::Status,
+                SimCommand::Unlock { pin } => SimAction::Unlock { pin },
+            };
+            sim::run(client, action, format).await
+        }
+        Command::Wifi {
+            action: WifiCommand::Status,
+        } => wifi::run_status(client, format).await,
+        Command::Wifi {
+            action:
+                WifiCommand::Set {
+                    band,
+                    ssid,
+                    passphrase,
+                },
+        } => wifi::run_set(client, band.radios(), ssid, passphrase, format).await,
+        Command::Sms {
+            action: SmsCommand::Inbox { page, per_page },
+        } => sms::run_inbox(client, page as usize, per_page as usize, format).await,
+        Command::Sms {
+            action: SmsCommand::Send { to, message },
+        } => sms::run_send(client, to, message, format).await,
+    }
+}
+
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let (token, auth_header) = authenticate().await?;
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(error::exit_code(e.as_ref()))
+        }
+    }
+}
+
+// ---------- main TUI loop ----------
+async fn run() -> Result<(), Box<dyn Error>> {
+    let mut cli = Cli::parse();
+    let format = cli.output_format();
+    // the interactive view has no JSON form, print one dashboard snapshot
+    let command = match cli.command.take() {
+        None if cli.once || format == OutputFormat::Json => Some(Command::Dashboard {
+            csv: None,
+            html: None,
+            page: cli.page,
+            all: false,
+        }),
+        command => command,
+    };
+    if command.is_some() {
+        logging::init(cli.verbose);
+    }
+    let config = Config::load(cli.config.as_deref())?;
+    // checked before logging in, a typo should not cost a login
+    let pages = match cli.pages.is_empty() {
+        true => select_pages(&config.pages)?,
+        false => select_pages(&cli.pages)?,
+    };
+    timestamp::set_utc(cli.utc || config.utc);
+    // Windows consoles name no locale, --ascii is the way there
+    charset::set_ascii(
+        cli.ascii
+            || config.ascii
+            || (!cfg!(windows) && !charset::locale_is_utf8(|name| env::var(name).ok())),
+    );
+    jitter::set_percent(cli.jitter.or(config.jitter).unwrap_or(0));
+    prompt::set_assume_yes(cli.yes);
+    logging::set_redaction(cli.redact, cli.no_redact);
+    color::set_enabled(
+        !cli.no_color && env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal(),
+    );
+    pager::set_enabled(!cli.no_pager && format == OutputFormat::Text && io::stdout().is_terminal());
+    let configured_url = cli.base_url.clone().or(config.base_url.clone());
+    let secrets_path = secrets::default_path();
+    if let Some(Command::Logout) = command {
+        let path = secrets_path.ok_or("No home directory to keep passwords in")?;
+        let base_url = configured_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+        return secrets::run_logout(&path, base_url, format);
+    }
+    let history_path = cli
+        .history_file
+        .clone()
+        .or(config.history_file.clone())
+        .or_else(history::default_path);
+    if let Some(Command::History { limit }) = command {
+        let path = history_path.ok_or("No home directory to keep the history in")?;
+        return history::run(&path, limit as usize, format);
+    }
+    if let Some(Command::Discover) = command {
+        let http = build_http_client(cli.insecure || config.insecure)?;
+        let path = cli.config.clone().or_else(config::default_path);
+        let configured = configured_url.as_deref();
+        return discovery::run_discover(&http, path.as_deref(), configured, format).await;
+    }
+    if matches!(command, Some(Command::Doctor)) && cli.replay.is_some() {
+        return Err("doctor checks a router, it cannot run on --replay".into());
+    }
+    let logging_in = matches!(command, Some(Command::Login));
+    // a replay never logs in, so there is nothing to ask for
+    let credentials = match cli.replay {
+        Some(_) => credentials::Credentials {
+            username: String::new(),
+            password: String::new(),
+        },
+        None => {
+            let username = cli.username.clone().or(config.username.clone());
+            // a login asks for the password again rather than re-save the old one
+            let stored = match (&secrets_path, &username) {
+                (Some(path), Some(username)) if !logging_in && cli.password.is_none() => {
+                    let base_url = match cli.auto_discover {
+                        true => None,
+                        false => Some(configured_url.as_deref().unwrap_or(DEFAULT_BASE_URL)),
+                    };
+                    secrets::lookup(&secrets::OsKeyring, path, base_url, username)
+                }
+                _ => None,
+            };
+            credentials::resolve(
+                username,
+                cli.password.clone().or(stored).or(config.password.clone()),
+            )?
+        }
+    };
+    let login_credentials = logging_in.then(|| credentials.clone());
+    let cli_headers = cli.headers.into_iter().collect();
+    let header_overrides = merge_headers(config.header_map()?, &cli_headers);
+    let http = build_http_client(cli.insecure || config.insecure)?;
+    let token_ttl = cli
+        .token_ttl
+        .or(config.token_ttl)
+        .unwrap_or(DEFAULT_TOKEN_TTL_SECS);
+    // a cached token would skip the login that checks the password
+    let token_cache = token_cache::default_path()
+        .filter(|_| !cli.fresh_login && !logging_in)
+        .map(|path| (path, Duration::from_secs(token_ttl)));
+    let timeout = cli.timeout.map(Duration::from_secs);
+    let api_path = cli.api_path.clone().or(config.api_path.clone());
+    // the settings every client gets, whichever router address it tries
+    let configure = |client: &mut RouterClient| {
+        client.set_auth_mode(cli.auth_mode);
+        client.set_login_attempts(cli.login_attempts);
+        if let Some(timeout) = timeout {
+            client.set_timeout(timeout);
+        }
+        if let Some(api_path) = &api_path {
+            client.set_api_path(api_path.clone());
+        }
+    };
+    let mut client = if let Some(path) = &cli.replay {
+        let base_url = format!("replay of {}", path.display());
+        let mut client = RouterClient::new(http, base_url, credentials, header_overrides);
+        client.set_replay(replay::load(path)?);
+        client
+    } else if cli.auto_discover {
+        // discovery confirms the gateway with a real login, so the cache is
+        // only used for logins later in the run
+        let mut client =
+            discovery::discover_router(http, credentials, header_overrides, configure).await?;
+        if let Some((path, ttl)) = token_cache {
+            client.set_token_cache(path, ttl);
+        }
+        client
+    } else {
+        let base_url = configured_url
+            .clone()
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        let mut client = RouterClient::new(http, base_url, credentials, header_overrides);
+        configure(&mut client);
+        if let Some((path, ttl)) = token_cache {
+            client.set_token_cache(path, ttl);
+        }
+        // the doctor logs in as one of its checks
+        if !matches!(command, Some(Command::Doctor)) {
+            client.start_session().await?;
+        }
+        client
+    };
+
+    client.set_dry_run(cli.dry_run);
+    // a replay sends nothing, so there is no change to record
+    if let Some(path) = history_path.filter(|_| cli.replay.is_none()) {
+        client.set_history(path);
+    }
+    if let Some(dir) = cli.save_responses.clone() {
+        client
+            .save_responses(dir.clone())
+            .map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    }
+    client.set_retry(RetryPolicy {
+        max_retries: cli.max_retries,
+        delay: Duration::from_millis(cli.retry_delay),
+    });
+    let default_dmz_ip = config
+        .default_dmz_ip
+        .unwrap_or_else(|| DEFAULT_DMZ_IP.to_string());
+    let timings = client.timings();
+    if let Some(credentials) = login_credentials {
+        let path = secrets_path.ok_or("No home directory to keep passwords in")?;
+        let in_config = config.password.is_some();
+        return secrets::run_login(&path, client.base_url(), &credentials, in_config, format);
+    }
+    // checked like --data-cap and --cap-alerts, a cap of 0 would make every
+    // percentage of it meaningless
+    let config_cap = config
+        .data_cap_gb
+        .map(|gb| data_cap::parse_cap(&gb.to_string()))
+        .transpose()
+        .map_err(|e| format!("data_cap_gb in the config: {}", e))?;
+    if config.data_cap_alerts.contains(&0) {
+        return Err("data_cap_alerts in the config: 0 is not a percentage to warn at".into());
+    }
+    if let Some(Command::Monitor {
+        data_cap: None,
+        cap_alerts,
+        cap_hook,
+        ..
+    }) = &command
+    {
+        if config_cap.is_none() && (!cap_alerts.is_empty() || cap_hook.is_some()) {
+            return Err("--cap-alerts and --cap-hook need a --data-cap".into());
+        }
+    }
+    // the config supplies what the command line leaves out
+    let command = command.map(|command| match command {
+        Command::Status { template: None } => Command::Status {
+            template: config.status_template,
+        },
+        Command::BandScan {
+            earfcns,
+            settle,
+            restore,
+        } => Command::BandScan {
+            earfcns: match earfcns.is_empty() {
+                true => config.band_scan_earfcns,
+                false => earfcns,
+            },
+            settle: settle.or(config.band_scan_settle),
+            restore,
+        },
+        Command::Monitor {
+            interval,
+            log,
+            graph,
+            no_graph,
+            history,
+            data_cap,
+            cap_alerts,
+            cap_hook,
+        } => {
+            let data_cap = data_cap.or(config_cap);
+            // without a cap there is nothing for the configured alerts to warn about
+            let capped = data_cap.is_some();
+            Command::Monitor {
+                interval,
+                log,
+                graph,
+                no_graph,
+                history,
+                data_cap,
+                cap_alerts: match cap_alerts.is_empty() && capped {
+                    true => config.data_cap_alerts,
+                    false => cap_alerts,
+                },
+                cap_hook: cap_hook.or(config.data_cap_hook.filter(|_| capped)),
+            }
+        }
+        command => command,
+    });
+    if let Some(command) = command {
+        let result = run_command(command, &client, &default_dmz_ip, format).await;
+        if cli.profile_timing {
+            timings.print_summary();
+        }
+        return result;
+    }
 
     let (worker_tx, request_rx) =
         mpsc::unbounded_channel::<(Request, mpsc::UnboundedSender<Response>)>();
     let (response_tx, mut response_rx) = mpsc::unbounded_channel::<Response>();
 
-    tokio::spawn(run_handlers(auth_header.clone(), request_rx));
+    let notice_tx = response_tx.clone();
+    client.on_notice(move |notice| {
+        let _ = notice_tx.send(Response::Notice(notice.to_string()));
+    });
+    let band_lock = LockStatus::read(&client);
+    tokio::spawn(run_handlers(client, request_rx));
 
     fn send_request(
         worker_tx: &mpsc::UnboundedSender<(Request, mpsc::UnboundedSender<Response>)>,
@@ -709,38 +2021,51 @@ async fn main() -> Result<(), Box<dyn Error>> {
         let _ = worker_tx.send((request, response_tx.clone()));
     }
 
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    let bracketed_paste = !cli.no_bracketed_paste && terminal::bracketed_paste_supported();
+    let guard = terminal::TuiGuard::enter(io::stdout(), bracketed_paste)?;
+    // Ctrl-C is a key press in raw mode; this catches SIGINT/SIGTERM sent
+    // from elsewhere, which would otherwise kill the process mid-screen
+    let stop = Arc::new(AtomicBool::new(false));
+    tokio::spawn(wait_for_stop_signal(stop.clone()));
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new(token, auth_header.clone(), worker_tx.clone());
+    let refresh_secs = cli
+        .refresh_interval
+        .or(config.refresh_interval)
+        .unwrap_or(DEFAULT_REFRESH_SECS)
+        .max(1);
+    let mut app = App::new(
+        worker_tx.clone(),
+        default_dmz_ip,
+        Duration::from_secs(refresh_secs),
+        pages,
+    );
+    app.band_lock_state.band_lock = band_lock;
 
-    send_request(&app.request_tx, &response_tx, Request::RefreshDashboard);
+    let refresh = Request::Refresh {
+        neighbors: false,
+        devices: false,
+    };
+    send_request(&app.request_tx, &response_tx, refresh);
 
     let tick_rate = Duration::from_millis(50);
     let mut last_tick = Instant::now();
     let mut last_refresh = Instant::now();
+    let mut refresh_wait = jitter::apply(app.refresh_interval);
 
     loop {
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
         while let Ok(response) = response_rx.try_recv() {
             match response {
                 Response::DashboardData { data, error } => {
                     if let Some(e) = error {
                         app.status_message = format!("Dashboard error: {}", e);
                     } else {
-                        if let Some(rsrp_str) = data["RSRP"].as_str() {
-                            if let Ok(val) = rsrp_str.parse::<i64>() {
-                                let abs_val = val.unsigned_abs().min(140);
-                                if app.rsrp_history.len() >= RSRP_HISTORY_LEN {
-                                    app.rsrp_history.pop_front();
-                                }
-                                app.rsrp_history.push_back(abs_val);
-                            }
-                        }
-                        app.index_data = data;
-                        app.update_traffic();
+                        app.apply_dashboard(data);
+                        app.last_updated = Some(timestamp::now_display());
                         app.status_message = "Dashboard updated".into();
                     }
                 }
@@ -752,62 +2077,304 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         app.status_message = "Neighbour cells fetched".into();
                     }
                 }
-                Response::BandLockResult { result, .. } => {
+                Response::DeviceData { data, error } => {
+                    if let Some(e) = error {
+                        app.status_message = format!("Devices error: {}", e);
+                    } else {
+                        app.devices_data = data;
+                        app.status_message = "Connected devices fetched".into();
+                    }
+                }
+                Response::SmsData(inbox) => {
+                    app.status_message = match &inbox {
+                        Ok(messages) => format!("{} SMS fetched", messages.len()),
+                        Err(e) => format!("SMS error: {}", e),
+                    };
+                    app.sms_inbox = inbox;
+                }
+                Response::BandLockResult {
+                    earfcn,
+                    success,
+                    result,
+                } => {
                     app.band_lock_response = Some(result);
+                    app.status_message = format!("Band lock {} answered", earfcn);
+                    if let (true, Ok(earfcn)) = (success, earfcn::parse_earfcn(&earfcn)) {
+                        app.band_lock_state.band_lock = LockStatus::Locked {
+                            earfcn,
+                            since: timestamp::now_iso(),
+                        };
+                    }
+                }
+                Response::BandLockCleared { success, result } => {
+                    app.band_lock_response = Some(result);
+                    if success {
+                        app.band_lock_state.band_lock = LockStatus::Cleared {
+                            since: timestamp::now_iso(),
+                            how: "cleared",
+                        };
+                    }
+                }
+                Response::CellLockCleared { success, result } => {
+                    app.band_lock_response = Some(result);
+                    if success {
+                        app.band_lock_state.active_cell = Some("off".to_string());
+                    }
+                }
+                Response::NetworkModeResult {
+                    mode,
+                    success,
+                    result,
+                } => {
+                    app.band_lock_response = Some(result);
+                    if success {
+                        app.band_lock_state.active_mode = Some(mode);
+                    }
                 }
                 Response::DmzResult(result) => {
                     app.dmz_response = Some(result);
                     app.status_message = "DMZ updated".into();
                 }
+                Response::RawResult(result) => app.status_message = result,
+                Response::RebootResult(result) => {
+                    app.rebooting = false;
+                    app.status_message = result;
+                }
+                Response::Notice(notice) => {
+                    app.notice = Some(format!("{} ({})", notice, timestamp::now_display()));
+                }
             }
         }
 
+        let render_started = Instant::now();
         terminal.draw(|f| ui(f, &mut app))?;
+        timings.record("render", render_started.elapsed());
 
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
 
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
+            let ev = event::read()?;
+            if let Event::Paste(text) = &ev {
+                let text = terminal::strip_paste_markers(text);
+                if app.command_line.active {
+                    app.command_line
+                        .input
+                        .push_str(text.lines().next().unwrap_or(""));
+                } else if matches!(app.page, Page::Dmz) {
+                    let ip_chars = text.chars().filter(|c| c.is_ascii_digit() || *c == '.');
+                    app.dmz_ip_input.extend(ip_chars);
+                } else if let Some(idx) = terminal::menu_choice(&text, app.pages.len()) {
+                    app.go_to_page(idx);
+                }
+            }
+            if let Event::Key(key) = ev {
                 if key.kind == KeyEventKind::Press {
-                    // --- Global quit ---
-                    if key.code == KeyCode::Char('q') || key.code == KeyCode::Char('Q') {
+                    if key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL)
+                    {
                         break;
                     }
 
+                    // --- A pending confirmation swallows the next key ---
+                    if let Some(confirm) = app.confirm.take() {
+                        let yes = matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y'));
+                        match confirm {
+                            Confirm::Quit if yes => break,
+                            Confirm::Quit => app.status_message.clear(),
+                            Confirm::Reboot if yes => {
+                                send_request(&app.request_tx, &response_tx, Request::Reboot);
+                                app.rebooting = true;
+                                app.status_message = "Rebooting, waiting for the router...".into();
+                            }
+                            Confirm::Reboot => app.status_message = "Reboot cancelled".into(),
+                            Confirm::BandUnlock if yes => {
+                                send_request(&app.request_tx, &response_tx, Request::ClearBandLock);
+                                app.band_lock_response = Some("Sending...".to_string());
+                            }
+                            Confirm::BandUnlock => {
+                                app.status_message = "Band unlock cancelled".into()
+                            }
+                            Confirm::BandLock(earfcn) if yes => {
+                                send_request(
+                                    &app.request_tx,
+                                    &response_tx,
+                                    Request::SetBandLock { earfcn },
+                                );
+                                app.band_lock_response = Some("Sending...".to_string());
+                                // the answer is shown with the other locks
+                                let _ = app.show(Page::BandLock);
+                            }
+                            Confirm::ClearCellLock if yes => {
+                                send_request(&app.request_tx, &response_tx, Request::ClearCellLock);
+                                app.band_lock_response = Some("Sending...".to_string());
+                            }
+                            Confirm::NetworkMode(mode) if yes => {
+                                send_request(
+                                    &app.request_tx,
+                                    &response_tx,
+                                    Request::SetNetworkMode { mode },
+                                );
+                                app.band_lock_response = Some("Sending...".to_string());
+                                let _ = app.show(Page::BandLock);
+                            }
+                            Confirm::SetDmz { ip, protocol, .. } if yes => {
+                                let request = Request::SetDmz { ip, protocol };
+                                send_request(&app.request_tx, &response_tx, request);
+                                app.dmz_response = Some("Sending...".to_string());
+                            }
+                            Confirm::DisableDmz if yes => {
+                                send_request(&app.request_tx, &response_tx, Request::DisableDmz);
+                                app.dmz_response = Some("Sending...".to_string());
+                            }
+                            Confirm::Raw(command) if yes => {
+                                send_request(
+                                    &app.request_tx,
+                                    &response_tx,
+                                    Request::Raw { command },
+                                );
+                                app.status_message = "Sending...".into();
+                            }
+                            Confirm::BandLock(_)
+                            | Confirm::Raw(_)
+                            | Confirm::ClearCellLock
+                            | Confirm::NetworkMode(_)
+                            | Confirm::SetDmz { .. }
+                            | Confirm::DisableDmz => {
+                                app.status_message = "Cancelled, nothing was changed".into()
+                            }
+                        }
+                        continue;
+                    }
+
+                    // --- Command line, opened with ':' ---
+                    if app.command_line.active {
+                        match key.code {
+                            KeyCode::Esc => app.command_line.close(),
+                            KeyCode::Enter => {
+                                let line = app.command_line.submit();
+                                match command_line::parse(&line) {
+                                    Ok(TuiCommand::Quit) if prompt::assume_yes() => break,
+                                    Ok(TuiCommand::Quit) => app.confirm = Some(Confirm::Quit),
+                                    Ok(TuiCommand::Page(idx)) if idx < app.pages.len() => {
+                                        app.go_to_page(idx)
+                                    }
+                                    Ok(TuiCommand::Page(idx)) => {
+                                        app.status_message = format!("There is no page {}", idx + 1)
+                                    }
+                                    Ok(TuiCommand::Show(name)) => {
+                                        let shown = Page::named(name)
+                                            .ok_or_else(|| format!("There is no {} page", name))
+                                            .and_then(|page| app.show(page));
+                                        if let Err(e) = shown {
+                                            app.status_message = e;
+                                        }
+                                    }
+                                    Ok(TuiCommand::Dmz(ip)) => {
+                                        let _ = app.show(Page::Dmz);
+                                        app.confirm_dmz(&ip);
+                                    }
+                                    Ok(TuiCommand::BandLock(earfcn)) => {
+                                        match earfcn::parse_earfcn(&earfcn) {
+                                            Ok(earfcn) => {
+                                                app.confirm =
+                                                    Some(Confirm::BandLock(earfcn.to_string()))
+                                            }
+                                            Err(e) => app.status_message = e,
+                                        }
+                                    }
+                                    Ok(TuiCommand::NetworkMode(mode)) => {
+                                        app.confirm = Some(Confirm::NetworkMode(mode))
+                                    }
+                                    Ok(TuiCommand::Raw(command)) if is_read_command(&command) => {
+                                        send_request(
+                                            &app.request_tx,
+                                            &response_tx,
+                                            Request::Raw { command },
+                                        );
+                                        app.status_message = "Sending...".into();
+                                    }
+                                    Ok(TuiCommand::Raw(command)) => {
+                                        app.confirm = Some(Confirm::Raw(command))
+                                    }
+                                    Ok(TuiCommand::Reboot) if app.rebooting => {
+                                        app.status_message = "Already rebooting".into()
+                                    }
+                                    Ok(TuiCommand::Reboot) => app.confirm = Some(Confirm::Reboot),
+                                    Err(e) => app.status_message = e,
+                                }
+                            }
+                            KeyCode::Tab => app.command_line.complete(),
+                            KeyCode::Up => app.command_line.previous(),
+                            KeyCode::Down => app.command_line.next(),
+                            KeyCode::Backspace => {
+                                app.command_line.input.pop();
+                            }
+                            KeyCode::Char(c) => app.command_line.input.push(c),
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    if key.code == KeyCode::Char(':') {
+                        app.command_line.open();
+                        continue;
+                    }
+
+                    // --- Global quit, Ctrl-C skips the question ---
+                    if key.code == KeyCode::Char('q') || key.code == KeyCode::Char('Q') {
+                        if prompt::assume_yes() {
+                            break;
+                        }
+                        app.confirm = Some(Confirm::Quit);
+                        continue;
+                    }
+
+                    // --- Auto-refresh toggle (not a valid DMZ input character) ---
+                    if key.code == KeyCode::Char('r') || key.code == KeyCode::Char('R') {
+                        app.auto_refresh = !app.auto_refresh;
+                        if app.auto_refresh {
+                            // refresh right away instead of after a full interval
+                            last_refresh = Instant::now()
+                                .checked_sub(refresh_wait)
+                                .unwrap_or(last_refresh);
+                        }
+                        continue;
+                    }
+
                     // --- DMZ input handling (always takes precedence over tab switching) ---
                     if matches!(app.page, Page::Dmz) {
                         match key.code {
                             KeyCode::Enter => {
                                 let ip = if app.dmz_ip_input.is_empty() {
-                                    DEFAULT_DMZ_IP.to_string()
+                                    app.default_dmz_ip.clone()
                                 } else {
                                     app.dmz_ip_input.clone()
                                 };
-                                send_request(&app.request_tx, &response_tx, Request::SetDmz { ip });
-                                app.dmz_response = Some("Sending...".to_string());
+                                app.confirm_dmz(&ip);
                                 app.dmz_ip_input.clear();
                             }
                             KeyCode::Backspace | KeyCode::Delete => {
                                 app.dmz_ip_input.pop();
                             }
-                            KeyCode::Tab => {
-                                app.next_page();
-                                if matches!(app.page, Page::NeighborCells) && !app.neighbour_fetched {
-                                    app.neighbour_fetched = true;
-                                    send_request(
-                                        &app.request_tx,
-                                        &response_tx,
-                                        Request::FetchNeighbors,
-                                    );  
-                                }
+                            KeyCode::Tab => app.next_page(),
+                            KeyCode::Char('o') | KeyCode::Char('O') => {
+                                app.confirm = Some(Confirm::DisableDmz);
                             }
-                            KeyCode::Char(c) => {
-                                // Allow digits and dots only (simple IP input)
-                                if c.is_ascii_digit() || c == '.' {
-                                    app.dmz_ip_input.push(c);
-                                }
+                            KeyCode::Char('p') | KeyCode::Char('P') => {
+                                app.dmz_protocol = match app.dmz_protocol {
+                                    Protocol::Both => Protocol::Tcp,
+                                    Protocol::Tcp => Protocol::Udp,
+                                    Protocol::Udp => Protocol::Both,
+                                };
+                            }
+                            KeyCode::Char('s') | KeyCode::Char('S') => {
+                                send_request(&app.request_tx, &response_tx, Request::ShowDmz);
+                                app.dmz_response = Some("Querying...".to_string());
+                            }
+                            // Allow digits and dots only (simple IP input)
+                            KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => {
+                                app.dmz_ip_input.push(c);
                             }
                             _ => {}
                         }
@@ -816,43 +2383,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
                     // --- Tab switching (only when NOT in DMZ) ---
                     match key.code {
-                        KeyCode::Tab => {
-                            app.next_page();
-                            if matches!(app.page, Page::NeighborCells) && !app.neighbour_fetched {
-                                app.neighbour_fetched = true;
-                                send_request(
-                                    &app.request_tx,
-                                    &response_tx,
-                                    Request::FetchNeighbors,
-                                );
+                        KeyCode::Tab => app.next_page(),
+                        KeyCode::BackTab => app.previous_page(),
+                        KeyCode::Char(c @ '1'..='9') => app.go_to_page(usize::from(c as u8 - b'1')),
+                        KeyCode::Char('a') | KeyCode::Char('A') => {
+                            if let Err(e) = app.show(Page::All) {
+                                app.status_message = e;
                             }
                         }
-                        KeyCode::BackTab => {
-                            app.previous_page();
-                            if matches!(app.page, Page::NeighborCells) && !app.neighbour_fetched {
-                                app.neighbour_fetched = true;
-                                send_request(
-                                    &app.request_tx,
-                                    &response_tx,
-                                    Request::FetchNeighbors,
-                                );
-                            }
-                        }
-                        KeyCode::Char('1') => {
-                            app.go_to_page(0);
-                            if matches!(app.page, Page::NeighborCells) && !app.neighbour_fetched {
-                                app.neighbour_fetched = true;
-                                send_request(
-                                    &app.request_tx,
-                                    &response_tx,
-                                    Request::FetchNeighbors,
-                                );
-                            }
-                        }
-                        KeyCode::Char('2') => app.go_to_page(1),
-                        KeyCode::Char('3') => app.go_to_page(2),
-                        KeyCode::Char('4') => app.go_to_page(3),
-                        KeyCode::Char('5') => app.go_to_page(4),
                         KeyCode::Up | KeyCode::Down => {
                             if let Page::BandLock = app.page {
                                 let i = match key.code {
@@ -873,20 +2411,60 @@ async fn main() -> Result<(), Box<dyn Error>> {
                                 };
                                 app.band_lock_state.state.select(Some(i));
                             }
+                            if let Page::NeighborCells = app.page {
+                                let last = parse_neighbor_cells(&app.neighbour_data)
+                                    .len()
+                                    .saturating_sub(1);
+                                app.neighbour_selected = match key.code {
+                                    KeyCode::Up => app.neighbour_selected.saturating_sub(1),
+                                    _ => (app.neighbour_selected + 1).min(last),
+                                };
+                            }
+                            if let (Page::Sms, Ok(messages)) = (app.page, &app.sms_inbox) {
+                                let last = messages.len().saturating_sub(1);
+                                let selected = app.sms_state.selected().unwrap_or(0);
+                                let i = match key.code {
+                                    KeyCode::Up => selected.saturating_sub(1),
+                                    _ => (selected + 1).min(last),
+                                };
+                                app.sms_state.select(Some(i));
+                            }
                         }
                         KeyCode::Enter => {
                             if let Page::BandLock = app.page {
                                 let selected = app.band_lock_state.state.selected().unwrap_or(0);
                                 let earfcn = app.band_lock_state.items[selected].clone();
-                                send_request(
-                                    &app.request_tx,
-                                    &response_tx,
-                                    Request::SetBandLock {
-                                        index: selected,
-                                        earfcn,
-                                    },
-                                );
-                                app.band_lock_response = Some("Sending...".to_string());
+                                app.confirm = Some(Confirm::BandLock(earfcn));
+                            }
+                            if let Page::NeighborCells = app.page {
+                                let cells = neighbors::ranked_cells(&app.neighbour_data);
+                                match neighbors::pick_earfcn(&cells, app.neighbour_selected + 1) {
+                                    Ok(earfcn) => {
+                                        app.confirm = Some(Confirm::BandLock(earfcn.to_string()))
+                                    }
+                                    Err(e) => app.status_message = e,
+                                }
+                            }
+                        }
+                        KeyCode::Char('b') | KeyCode::Char('B') if !app.rebooting => {
+                            app.confirm = Some(Confirm::Reboot);
+                        }
+                        KeyCode::Char('u') | KeyCode::Char('U') => {
+                            if let Page::BandLock = app.page {
+                                app.confirm = Some(Confirm::BandUnlock);
+                            }
+                        }
+                        KeyCode::Char('x') | KeyCode::Char('X') => {
+                            if let Page::BandLock = app.page {
+                                app.confirm = Some(Confirm::ClearCellLock);
+                            }
+                        }
+                        KeyCode::Char('m') | KeyCode::Char('M') => {
+                            if let Page::BandLock = app.page {
+                                app.command_line.open();
+                                app.command_line.input.push_str("mode ");
+                                app.status_message =
+                                    "Network modes: auto, 4g, 5g-sa, 5g-nsa".into();
                             }
                         }
                         _ => {}
@@ -899,19 +2477,39 @@ async fn main() -> Result<(), Box<dyn Error>> {
             last_tick = Instant::now();
         }
 
-        if last_refresh.elapsed() >= REFRESH_INTERVAL {
-            send_request(&app.request_tx, &response_tx, Request::RefreshDashboard);
+        // pages other than the dashboard fetch their data when first shown
+        if matches!(app.page, Page::NeighborCells) && !app.neighbour_fetched {
+            app.neighbour_fetched = true;
+            send_request(&app.request_tx, &response_tx, Request::FetchNeighbors);
+        }
+        if matches!(app.page, Page::Sms) && !app.sms_fetched {
+            app.sms_fetched = true;
+            send_request(&app.request_tx, &response_tx, Request::FetchSms);
+        }
+        if matches!(app.page, Page::Devices) && !app.devices_fetched {
+            app.devices_fetched = true;
+            send_request(&app.request_tx, &response_tx, Request::FetchDevices);
+        }
+
+        if app.auto_refresh && !app.rebooting && last_refresh.elapsed() >= refresh_wait {
+            let refresh = Request::Refresh {
+                neighbors: matches!(app.page, Page::NeighborCells),
+                devices: matches!(app.page, Page::Devices),
+            };
+            send_request(&app.request_tx, &response_tx, refresh);
             last_refresh = Instant::now();
+            refresh_wait = jitter::apply(app.refresh_interval);
         }
     }
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    // leaving the alternate screen puts the shell's own lines back, so the
+    // goodbye lands on a tidy terminal
+    drop(guard);
+    println!("Goodbye!");
+
+    if cli.profile_timing {
+        timings.print_summary();
+    }
 
     Ok(())
-}
\ No newline at end of file
+}
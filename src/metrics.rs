@@ -0,0 +1,139 @@
+use serde_json::Value;
+
+use crate::dashboard::{seconds, RECEIVED_KEY, SENT_KEY};
+use crate::fields::aliased_parse;
+use crate::signal::SignalMetrics;
+
+// ---------- telemetry metrics ----------
+// The numeric dashboard values exporters publish, looked up by dashboard
+// field name so they read the same keys as the dashboard itself.
+pub struct Metric {
+    /// Dashboard field name, also the metric's base name.
+    pub name: &'static str,
+    pub label: &'static str,
+    /// Which part of the router it describes: signal, traffic or system.
+    pub group: &'static str,
+    pub help: &'static str,
+    pub unit: &'static str,
+    /// Only ever grows (until the router resets it).
+    pub counter: bool,
+    read: fn(&Value, &str) -> Option<f64>,
+    spec: &'static str,
+}
+
+fn signal_value(data: &Value, name: &str) -> Option<f64> {
+    SignalMetrics::from_data(data).get(name)
+}
+
+fn byte_count(data: &Value, spec: &str) -> Option<f64> {
+    aliased_parse::<u64>(data, spec).map(|b| b as f64)
+}
+
+fn seconds_value(data: &Value, spec: &str) -> Option<f64> {
+    seconds(data, spec).map(|s| s as f64)
+}
+
+const fn signal(
+    name: &'static str,
+    label: &'static str,
+    help: &'static str,
+    unit: &'static str,
+) -> Metric {
+    Metric {
+        name,
+        label,
+        group: "signal",
+        help,
+        unit,
+        counter: false,
+        read: signal_value,
+        spec: name,
+    }
+}
+
+pub const METRICS: &[Metric] = &[
+    signal("rsrp", "RSRP", "Reference signal received power", "dBm"),
+    signal("rsrq", "RSRQ", "Reference signal received quality", "dB"),
+    signal(
+        "sinr",
+        "SINR",
+        "Signal to interference plus noise ratio",
+        "dB",
+    ),
+    signal("rssi", "RSSI", "Received signal strength indicator", "dBm"),
+    Metric {
+        name: "received",
+        label: "Received",
+        group: "traffic",
+        help: "Bytes received since the router's counter last reset",
+        unit: "bytes",
+        counter: true,
+        read: byte_count,
+        spec: RECEIVED_KEY,
+    },
+    Metric {
+        name: "sent",
+        label: "Sent",
+        group: "traffic",
+        help: "Bytes sent since the router's counter last reset",
+        unit: "bytes",
+        counter: true,
+        read: byte_count,
+        spec: SENT_KEY,
+    },
+    Metric {
+        name: "uptime",
+        label: "Uptime",
+        group: "system",
+        help: "Router uptime",
+        unit: "seconds",
+        counter: false,
+        read: seconds_value,
+        spec: "SYSUP",
+    },
+    Metric {
+        name: "wan_uptime",
+        label: "WAN uptime",
+        group: "system",
+        help: "Time since the WAN connection came up",
+        unit: "seconds",
+        counter: false,
+        read: seconds_value,
+        spec: "WANUP",
+    },
+];
+
+impl Metric {
+    pub fn value(&self, data: &Value) -> Option<f64> {
+        (self.read)(data, self.spec)
+    }
+}
+
+pub fn find(name: &str) -> Option<&'static Metric> {
+    METRICS.iter().find(|m| m.name == name)
+}
+
+/// Every metric with its value in `data`, `None` when the router left it out.
+pub fn sample(data: &Value) -> Vec<(&'static Metric, Option<f64>)> {
+    METRICS.iter().map(|m| (m, m.value(data))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_signal_counters_and_uptime() {
+        let data = json!({ "RSRP": "-95 dBm", "SINR": 12.5, "recieve": "2048", "SYSUP": "60" });
+        let values: Vec<(&str, Option<f64>)> = sample(&data)
+            .into_iter()
+            .map(|(m, v)| (m.name, v))
+            .collect();
+        assert!(values.contains(&("rsrp", Some(-95.0))));
+        assert!(values.contains(&("sinr", Some(12.5))));
+        assert!(values.contains(&("received", Some(2048.0))));
+        assert!(values.contains(&("uptime", Some(60.0))));
+        assert!(values.contains(&("rssi", None)));
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::{json, Map, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::RouterClient;
+use crate::color;
+use crate::dashboard::{csv_line, find_field};
+use crate::data_cap::{self, DataCap};
+use crate::fields::field;
+use crate::jitter;
+use crate::signal::{leading_number, quality_of, sparkline};
+use crate::timestamp;
+use crate::usage::Usage;
+
+// Dashboard field names recorded per sample, in log column order. The first
+// four get min/max/average statistics.
+const SAMPLE_FIELDS: &[&str] = &["rsrp", "rsrq", "sinr", "rssi", "band", "cell_id"];
+const STAT_FIELDS: usize = 4;
+/// The metrics `--graph` can plot.
+pub const GRAPH_FIELDS: [&str; STAT_FIELDS] = ["rsrp", "rsrq", "sinr", "rssi"];
+
+pub struct MonitorOptions {
+    pub interval: Duration,
+    pub log: Option<PathBuf>,
+    /// Metric to plot after each text sample, `None` for no graph.
+    pub graph: Option<String>,
+    pub history: usize,
+    pub data_cap: Option<DataCap>,
+    /// Run at every data cap warning.
+    pub cap_hook: Option<String>,
+}
+
+// The last `capacity` values of the plotted metric, oldest first.
+struct History {
+    values: VecDeque<f64>,
+    capacity: usize,
+}
+
+impl History {
+    fn new(capacity: usize) -> Self {
+        History {
+            values: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    fn push(&mut self, value: f64) {
+        if self.values.len() >= self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    // coloured by the latest value, like the dashboard's signal values
+    fn render(&self, name: &str) -> String {
+        let values: Vec<f64> = self.values.iter().copied().collect();
+        let line = sparkline(&values);
+        match self.values.back().and_then(|&v| quality_of(name, v)) {
+            Some(q) => color::paint(&line, q),
+            None => line,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    count: u32,
+    min: f64,
+    max: f64,
+    sum: f64,
+}
+
+impl Stats {
+    fn add(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        }
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+    }
+
+    fn average(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+fn sample_values(data: &Value) -> Vec<Option<String>> {
+    SAMPLE_FIELDS
+        .iter()
+        .map(|name| {
+            let spec = find_field(name).map_or(*name, |f| f.spec);
+            field(data, spec).map(String::from)
+        })
+        .collect()
+}
+
+// Appends to an existing log; a new file starts with the header line.
+fn open_log(path: &Path) -> Result<BufWriter<File>, Box<dyn Error>> {
+    let is_new = path.metadata().map_or(true, |m| m.len() == 0);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    let mut log = BufWriter::new(file);
+    if is_new {
+        let header = std::iter::once("time").chain(SAMPLE_FIELDS.iter().copied());
+        log.write_all(csv_line(header.map(String::from)).as_bytes())?;
+    }
+    Ok(log)
+}
+
+/// One sample as printed: a text line (and its sparkline) or a JSON line.
+fn render_sample(
+    time: &str,
+    values: &[Option<String>],
+    graph: Option<String>,
+    format: OutputFormat,
+) -> String {
+    match format {
+        OutputFormat::Text => {
+            let fields: Vec<String> = SAMPLE_FIELDS
+                .iter()
+                .zip(values)
+                .map(|(name, value)| format!("{} {}", name, value.as_deref().unwrap_or("-")))
+                .collect();
+            let mut out = format!("{}  {}\n", time, fields.join("  "));
+            if let Some(graph) = graph {
+                out.push_str(&format!("{:width$}  {}\n", "", graph, width = time.len()));
+            }
+            out
+        }
+        OutputFormat::Json | OutputFormat::Raw => {
+            let mut sample = Map::new();
+            sample.insert("time".to_string(), time.into());
+            for (name, value) in SAMPLE_FIELDS.iter().zip(values) {
+                sample.insert(name.to_string(), value.clone().into());
+            }
+            format!("{}\n", Value::Object(sample))
+        }
+    }
+}
+
+fn render_summary(samples: u64, stats: &[Stats], format: OutputFormat) -> String {
+    let names = &SAMPLE_FIELDS[..STAT_FIELDS];
+    match format {
+        OutputFormat::Text => {
+            let mut out = format!("{} sample(s)\n", samples);
+            out.push_str(&format!(
+                "  {:6} {:>8} {:>8} {:>8}\n",
+                "", "min", "max", "avg"
+            ));
+            for (name, stats) in names.iter().zip(stats) {
+                out.push_str(&match stats.average() {
+                    Some(avg) => format!(
+                        "  {:6} {:>8.1} {:>8.1} {:>8.1}\n",
+                        name, stats.min, stats.max, avg
+                    ),
+                    None => format!("  {:6} {:>8} {:>8} {:>8}\n", name, "-", "-", "-"),
+                });
+            }
+            out
+        }
+        OutputFormat::Json | OutputFormat::Raw => {
+            let mut summary = Map::new();
+            summary.insert("samples".to_string(), samples.into());
+            for (name, stats) in names.iter().zip(stats) {
+                let entry = match stats.average() {
+                    Some(avg) => json!({ "min": stats.min, "max": stats.max, "avg": avg }),
+                    None => Value::Null,
+                };
+                summary.insert(name.to_string(), entry);
+            }
+            format!("{}\n", json!({ "summary": summary }))
+        }
+    }
+}
+
+pub async fn run(
+    client: &RouterClient,
+    mut options: MonitorOptions,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let mut log = options.log.as_deref().map(open_log).transpose()?;
+    let mut stats: Vec<Stats> = (0..STAT_FIELDS).map(|_| Stats::default()).collect();
+    let mut samples = 0u64;
+    let graph = options
+        .graph
+        .as_deref()
+        .and_then(|name| SAMPLE_FIELDS.iter().position(|f| *f == name));
+    let mut history = History::new(options.history);
+
+    loop {
+        match client.api_request("get_index_data").await {
+            Ok(data) => {
+                let time = timestamp::now_iso();
+                let values = sample_values(&data);
+                for (stats, value) in stats.iter_mut().zip(&values) {
+                    if let Some(number) = value.as_deref().and_then(leading_number) {
+                        stats.add(number);
+                    }
+                }
+                if let Some(log) = log.as_mut() {
+                    let line = std::iter::once(time.clone())
+                        .chain(values.iter().map(|v| v.clone().unwrap_or_default()));
+                    log.write_all(csv_line(line).as_bytes())?;
+                    // a sample on disk survives a crash or a pulled cable
+                    log.flush()?;
+                }
+                let plotted = graph.map(|index| {
+                    if let Some(value) = values[index].as_deref().and_then(leading_number) {
+                        history.push(value);
+                    }
+                    format!(
+                        "{} {}",
+                        SAMPLE_FIELDS[index],
+                        history.render(SAMPLE_FIELDS[index])
+                    )
+                });
+                print!("{}", render_sample(&time, &values, plotted, format));
+                samples += 1;
+
+                let total = Usage::read(&data).total();
+                let alert = match (options.data_cap.as_mut(), total) {
+                    (Some(cap), Some(total)) => cap.update(total),
+                    _ => None,
+                };
+                if let Some(alert) = alert {
+                    match format {
+                        OutputFormat::Text => eprintln!("Warning: {}", alert.message()),
+                        OutputFormat::Json | OutputFormat::Raw => println!("{}", alert.to_json()),
+                    }
+                    if let Some(hook) = &options.cap_hook {
+                        data_cap::run_hook(hook, &alert).await;
+                    }
+                }
+            }
+            Err(e) => eprintln!("Sample failed: {}", e),
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(jitter::apply(options.interval)) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    if let Some(mut log) = log {
+        log.flush()?;
+    }
+    print!("{}", render_summary(samples, &stats, format));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_and_summaries_render_as_text() {
+        let values = vec![Some("-95".to_string()), None, None, None, None, None];
+        assert_eq!(
+            render_sample("12:00:00", &values, Some("\u{2581}\u{2588}".to_string()), OutputFormat::Text),
+            "12:00:00  rsrp -95  rsrq -  sinr -  rssi -  band -  cell_id -\n          \u{2581}\u{2588}\n"
+        );
+
+        let mut rsrp = Stats::default();
+        rsrp.add(-95.0);
+        rsrp.add(-100.0);
+        let stats = [rsrp, Stats::default(), Stats::default(), Stats::default()];
+        let summary = render_summary(2, &stats, OutputFormat::Text);
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines[0], "2 sample(s)");
+        assert_eq!(lines[2], "  rsrp     -100.0    -95.0    -97.5");
+        assert_eq!(lines[3], "  rsrq          -        -        -");
+    }
+}
@@ -0,0 +1,411 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+
+use crate::client::RouterClient;
+use crate::jitter;
+use crate::metrics::{self, Metric};
+
+// ---------- MQTT 3.1.1 packets ----------
+// Only what publishing needs: CONNECT with a will, QoS 0 PUBLISH, PINGREQ
+// and DISCONNECT. Nothing is subscribed, so the broker only ever sends
+// CONNACK and PINGRESP.
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const PINGREQ: u8 = 0xC0;
+const DISCONNECT: u8 = 0xE0;
+const RETAIN: u8 = 0x01;
+
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+fn put_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![header];
+    // remaining length: 7 bits per byte, high bit set while more follow
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    out.extend_from_slice(body);
+    out
+}
+
+fn connect_packet(options: &MqttOptions, keep_alive: u16) -> Vec<u8> {
+    let mut flags = 0x02 | 0x04 | 0x20; // clean session, will, retained will
+    if options.username.is_some() {
+        flags |= 0x80;
+    }
+    if options.password.is_some() {
+        flags |= 0x40;
+    }
+    let mut body = Vec::new();
+    put_str(&mut body, "MQTT");
+    body.push(4);
+    body.push(flags);
+    body.extend_from_slice(&keep_alive.to_be_bytes());
+    put_str(&mut body, &options.client_id);
+    put_str(&mut body, &status_topic(&options.topic_prefix));
+    put_str(&mut body, "offline");
+    if let Some(username) = &options.username {
+        put_str(&mut body, username);
+    }
+    if let Some(password) = &options.password {
+        put_str(&mut body, password);
+    }
+    packet(CONNECT, &body)
+}
+
+fn publish_packet(message: &Message) -> Vec<u8> {
+    let mut body = Vec::new();
+    put_str(&mut body, &message.topic);
+    body.extend_from_slice(message.payload.as_bytes());
+    let retain = if message.retain { RETAIN } else { 0 };
+    packet(PUBLISH | retain, &body)
+}
+
+// ---------- messages ----------
+pub struct MqttOptions {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub client_id: String,
+    pub topic_prefix: String,
+    /// `None` turns Home Assistant discovery off.
+    pub discovery_prefix: Option<String>,
+    pub interval: Duration,
+}
+
+#[derive(Debug, PartialEq)]
+struct Message {
+    topic: String,
+    payload: String,
+    retain: bool,
+}
+
+fn status_topic(prefix: &str) -> String {
+    format!("{}/status", prefix)
+}
+
+fn state_topic(prefix: &str, metric: &Metric) -> String {
+    format!("{}/{}/{}", prefix, metric.group, metric.name)
+}
+
+/// One message per metric the router reported, on `<prefix>/<group>/<name>`.
+fn state_messages(data: &Value, prefix: &str) -> Vec<Message> {
+    metrics::sample(data)
+        .into_iter()
+        .filter_map(|(metric, value)| {
+            Some(Message {
+                topic: state_topic(prefix, metric),
+                payload: value?.to_string(),
+                retain: false,
+            })
+        })
+        .collect()
+}
+
+// Home Assistant's unit and device class for each metric unit.
+fn home_assistant_unit(unit: &str) -> (&'static str, &'static str) {
+    match unit {
+        "dBm" => ("dBm", "signal_strength"),
+        "dB" => ("dB", "signal_strength"),
+        "bytes" => ("B", "data_size"),
+        _ => ("s", "duration"),
+    }
+}
+
+/// Retained sensor configs so Home Assistant creates one entity per metric.
+fn discovery_messages(options: &MqttOptions, discovery_prefix: &str) -> Vec<Message> {
+    let node = &options.client_id;
+    metrics::METRICS
+        .iter()
+        .map(|metric| {
+            let (unit, device_class) = home_assistant_unit(metric.unit);
+            let config = json!({
+                "name": metric.label,
+                "unique_id": format!("{}_{}", node, metric.name),
+                "state_topic": state_topic(&options.topic_prefix, metric),
+                "availability_topic": status_topic(&options.topic_prefix),
+                "unit_of_measurement": unit,
+                "device_class": device_class,
+                "state_class": if metric.counter { "total_increasing" } else { "measurement" },
+                "device": { "identifiers": [node], "name": "Zitel router" },
+            });
+            Message {
+                topic: format!(
+                    "{}/sensor/{}/{}/config",
+                    discovery_prefix, node, metric.name
+                ),
+                payload: config.to_string(),
+                retain: true,
+            }
+        })
+        .collect()
+}
+
+// ---------- broker connection ----------
+#[derive(Debug)]
+enum BrokerError {
+    Io(io::Error),
+    /// CONNACK with a non-zero return code; retrying will not help.
+    Refused(u8),
+}
+
+impl fmt::Display for BrokerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BrokerError::Io(e) => write!(f, "{}", e),
+            BrokerError::Refused(code) => {
+                let reason = match code {
+                    1 => "unsupported protocol version",
+                    2 => "client id rejected",
+                    3 => "server unavailable",
+                    4 => "bad username or password",
+                    5 => "not authorized",
+                    _ => "unknown reason",
+                };
+                write!(f, "broker refused the connection: {}", reason)
+            }
+        }
+    }
+}
+
+impl Error for BrokerError {}
+
+impl From<io::Error> for BrokerError {
+    fn from(e: io::Error) -> Self {
+        BrokerError::Io(e)
+    }
+}
+
+struct Broker {
+    reader: OwnedReadHalf,
+    writer: OwnedWriteHalf,
+}
+
+impl Broker {
+    async fn connect(options: &MqttOptions) -> Result<Self, BrokerError> {
+        let stream = TcpStream::connect((options.host.as_str(), options.port)).await?;
+        let (mut reader, mut writer) = stream.into_split();
+        // every poll sends something, so twice the interval never lapses
+        let keep_alive = (options.interval.as_secs() * 2).clamp(10, u16::MAX as u64) as u16;
+        writer
+            .write_all(&connect_packet(options, keep_alive))
+            .await?;
+
+        let mut connack = [0u8; 4];
+        tokio::time::timeout(Duration::from_secs(10), reader.read_exact(&mut connack))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "no CONNACK from broker"))??;
+        if connack[0] != CONNACK {
+            let e = io::Error::new(io::ErrorKind::InvalidData, "broker did not answer CONNECT");
+            return Err(e.into());
+        }
+        if connack[3] != 0 {
+            return Err(BrokerError::Refused(connack[3]));
+        }
+
+        let mut broker = Broker { reader, writer };
+        if let Some(prefix) = &options.discovery_prefix {
+            broker.publish(&discovery_messages(options, prefix)).await?;
+        }
+        let online = Message {
+            topic: status_topic(&options.topic_prefix),
+            payload: "online".to_string(),
+            retain: true,
+        };
+        broker.publish(&[online]).await?;
+        Ok(broker)
+    }
+
+    async fn publish(&mut self, messages: &[Message]) -> io::Result<()> {
+        for message in messages {
+            self.writer.write_all(&publish_packet(message)).await?;
+        }
+        Ok(())
+    }
+
+    async fn ping(&mut self) -> io::Result<()> {
+        self.writer.write_all(&packet(PINGREQ, &[])).await
+    }
+
+    // A clean DISCONNECT drops the will, so "offline" is published by hand.
+    async fn close(mut self, options: &MqttOptions) -> io::Result<()> {
+        let offline = Message {
+            topic: status_topic(&options.topic_prefix),
+            payload: "offline".to_string(),
+            retain: true,
+        };
+        self.publish(&[offline]).await?;
+        self.writer.write_all(&packet(DISCONNECT, &[])).await
+    }
+}
+
+/// Retries with a doubling delay until the broker is back. `None` when
+/// interrupted with Ctrl-C.
+async fn reconnect(options: &MqttOptions) -> Result<Option<Broker>, Box<dyn Error>> {
+    let mut delay = Duration::from_secs(1);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(None),
+        }
+        match Broker::connect(options).await {
+            Ok(broker) => {
+                eprintln!("Reconnected to {}:{}", options.host, options.port);
+                return Ok(Some(broker));
+            }
+            Err(e @ BrokerError::Refused(_)) => return Err(e.into()),
+            Err(e) => eprintln!("Reconnect failed: {}", e),
+        }
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+enum Event {
+    Poll,
+    /// Bytes from the broker, 0 when it closed the connection.
+    Incoming(io::Result<usize>),
+    Stop,
+}
+
+pub async fn run(client: &RouterClient, options: MqttOptions) -> Result<(), Box<dyn Error>> {
+    let mut broker = Broker::connect(&options)
+        .await
+        .map_err(|e| format!("Cannot connect to {}:{}: {}", options.host, options.port, e))?;
+    eprintln!(
+        "Publishing to {}:{} under {}/",
+        options.host, options.port, options.topic_prefix
+    );
+
+    let mut poll = tokio::time::interval(options.interval);
+    let mut incoming = [0u8; 64];
+    loop {
+        let event = tokio::select! {
+            _ = poll.tick() => Event::Poll,
+            read = broker.reader.read(&mut incoming) => Event::Incoming(read),
+            _ = tokio::signal::ctrl_c() => Event::Stop,
+        };
+        let sent = match event {
+            Event::Poll => {
+                if jitter::enabled() {
+                    poll.reset_after(jitter::apply(options.interval));
+                }
+                match client.api_request("get_index_data").await {
+                    Ok(data) => {
+                        broker
+                            .publish(&state_messages(&data, &options.topic_prefix))
+                            .await
+                    }
+                    Err(e) => {
+                        eprintln!("Poll failed: {}", e);
+                        broker.ping().await
+                    }
+                }
+            }
+            // PINGRESP, nothing to do
+            Event::Incoming(Ok(n)) if n > 0 => Ok(()),
+            Event::Incoming(Ok(_)) => Err(io::ErrorKind::ConnectionAborted.into()),
+            Event::Incoming(Err(e)) => Err(e),
+            Event::Stop => {
+                // best effort, the broker's will covers a failed goodbye
+                let _ = broker.close(&options).await;
+                return Ok(());
+            }
+        };
+        if let Err(e) = sent {
+            eprintln!("Lost the broker connection: {}", e);
+            match reconnect(&options).await? {
+                Some(reconnected) => broker = reconnected,
+                None => return Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> MqttOptions {
+        MqttOptions {
+            host: "localhost".to_string(),
+            port: 1883,
+            username: Some("ha".to_string()),
+            password: Some("pw".to_string()),
+            client_id: "zitel".to_string(),
+            topic_prefix: "zitel".to_string(),
+            discovery_prefix: Some("homeassistant".to_string()),
+            interval: Duration::from_secs(30),
+        }
+    }
+
+    #[test]
+    fn remaining_length_spans_bytes() {
+        assert_eq!(packet(PINGREQ, &[]), vec![0xC0, 0x00]);
+        let long = packet(PUBLISH, &[0u8; 200]);
+        assert_eq!(&long[..3], &[0x30, 0xC8, 0x01]);
+        assert_eq!(long.len(), 203);
+    }
+
+    #[test]
+    fn connect_carries_will_and_credentials() {
+        let connect = connect_packet(&options(), 60);
+        assert_eq!(connect[0], CONNECT);
+        // protocol name, level 4, flags
+        assert_eq!(&connect[2..10], &[0, 4, b'M', b'Q', b'T', b'T', 4, 0xE6]);
+        assert_eq!(&connect[10..12], &[0, 60]);
+        let text = String::from_utf8_lossy(&connect);
+        assert!(text.contains("zitel/status"));
+        assert!(text.contains("offline"));
+    }
+
+    #[test]
+    fn states_and_discovery_share_topics() {
+        let data = json!({ "RSRP": "-95", "recieve": "2048" });
+        let states = state_messages(&data, "zitel");
+        assert_eq!(
+            states,
+            vec![
+                Message {
+                    topic: "zitel/signal/rsrp".to_string(),
+                    payload: "-95".to_string(),
+                    retain: false,
+                },
+                Message {
+                    topic: "zitel/traffic/received".to_string(),
+                    payload: "2048".to_string(),
+                    retain: false,
+                },
+            ]
+        );
+
+        let discovery = discovery_messages(&options(), "homeassistant");
+        let rsrp = &discovery[0];
+        assert_eq!(rsrp.topic, "homeassistant/sensor/zitel/rsrp/config");
+        assert!(rsrp.retain);
+        let config: Value = serde_json::from_str(&rsrp.payload).unwrap();
+        assert_eq!(config["state_topic"], "zitel/signal/rsrp");
+        assert_eq!(config["unit_of_measurement"], "dBm");
+    }
+}
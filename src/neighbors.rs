@@ -0,0 +1,527 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::process::Command;
+
+use crate::cli::OutputFormat;
+use crate::client::RouterClient;
+use crate::color;
+use crate::dashboard::render_columns;
+use crate::earfcn::parse_earfcn;
+use crate::fields::{field, field_parse};
+use crate::jitter;
+use crate::pager;
+use crate::signal::{leading_number, Quality, SignalMetrics};
+use crate::terminal::{self, Screen, TuiGuard};
+use crate::timestamp;
+
+// ---------- parsing ----------
+// The router reports the cell count in `lenghtt` (sic) and each cell as a set
+// of numbered keys. The count is capped so a corrupt value cannot make the
+// renderer loop for ever.
+pub const MAX_NEIGHBOR_CELLS: usize = 64;
+
+pub struct NeighborCell {
+    pub kind: Option<String>,
+    pub band: Option<String>,
+    pub earfcn: Option<String>,
+    pub pcid: Option<String>,
+    pub rsrq: Option<String>,
+    pub rsrp: Option<String>,
+    pub rsrppp: Option<String>,
+}
+
+impl NeighborCell {
+    /// Each value under the heading of the key it was read from: `pcidN` is
+    /// the PCI, `rsrqN` the RSRQ and so on. `rsrpppN` is the received signal
+    /// strength of the whole carrier.
+    pub fn labeled(&self) -> [(&'static str, Option<&str>); 7] {
+        [
+            ("Type", self.kind.as_deref()),
+            ("Band", self.band.as_deref()),
+            ("EARFCN", self.earfcn.as_deref()),
+            ("PCI", self.pcid.as_deref()),
+            ("RSRQ(dB)", self.rsrq.as_deref()),
+            ("RSRP(dBm)", self.rsrp.as_deref()),
+            ("Signal(dBm)", self.rsrppp.as_deref()),
+        ]
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "type": self.kind,
+            "band": self.band,
+            "earfcn": self.earfcn,
+            "pci": self.pcid,
+            "rsrq": self.rsrq,
+            "rsrp": self.rsrp,
+            "signal": self.rsrppp,
+        })
+    }
+
+    fn rsrp_dbm(&self) -> Option<f64> {
+        self.rsrp.as_deref().and_then(leading_number)
+    }
+
+    /// One line naming the cell, for picking it by rank.
+    pub fn summary(&self, serving: Option<f64>) -> String {
+        format!(
+            "band {}, EARFCN {}, PCI {}, RSRP {} dBm ({} vs serving)",
+            self.band.as_deref().unwrap_or("-"),
+            self.earfcn.as_deref().unwrap_or("-"),
+            self.pcid.as_deref().unwrap_or("-"),
+            self.rsrp.as_deref().unwrap_or("-"),
+            delta_text(self.delta_db(serving))
+        )
+    }
+
+    /// How much stronger (positive) or weaker this cell's RSRP is than the
+    /// serving cell's.
+    pub fn delta_db(&self, serving: Option<f64>) -> Option<f64> {
+        Some(self.rsrp_dbm()? - serving?)
+    }
+
+    // band and PCI tell cells apart between scans; the order changes
+    fn key(&self) -> String {
+        format!(
+            "{}/{}",
+            self.band.as_deref().unwrap_or("-"),
+            self.pcid.as_deref().unwrap_or("-")
+        )
+    }
+}
+
+/// The serving cell's RSRP, from get_index_data.
+pub fn serving_rsrp(data: &Value) -> Option<f64> {
+    SignalMetrics::from_data(data).get("rsrp")
+}
+
+pub fn delta_text(delta: Option<f64>) -> String {
+    match delta {
+        Some(delta) if delta > 0.0 => format!("+{} dB", delta),
+        Some(delta) => format!("{} dB", delta),
+        None => "-".to_string(),
+    }
+}
+
+/// The cells of a get_neighbour_cell answer, strongest first.
+pub fn ranked_cells(data: &Value) -> Vec<NeighborCell> {
+    let mut cells = parse_neighbor_cells(data);
+    sort_by_signal(&mut cells);
+    cells
+}
+
+/// The EARFCN of the cell ranked `rank` (1 is the strongest).
+pub fn pick_earfcn(cells: &[NeighborCell], rank: usize) -> Result<u32, String> {
+    let cell = rank
+        .checked_sub(1)
+        .and_then(|i| cells.get(i))
+        .ok_or_else(|| {
+            format!(
+                "There is no neighbour cell #{}, the router reported {}",
+                rank,
+                cells.len()
+            )
+        })?;
+    let earfcn = cell
+        .earfcn
+        .as_deref()
+        .ok_or_else(|| format!("Neighbour cell #{} has no EARFCN", rank))?;
+    parse_earfcn(earfcn).map_err(|e| format!("Neighbour cell #{}: {}", rank, e))
+}
+
+/// Strongest RSRP first, cells without one last; a cell's rank is its
+/// position plus one.
+pub fn sort_by_signal(cells: &mut [NeighborCell]) {
+    cells.sort_by(|a, b| match (a.rsrp_dbm(), b.rsrp_dbm()) {
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+}
+
+pub fn parse_neighbor_cells(data: &Value) -> Vec<NeighborCell> {
+    let count = field_parse::<usize>(data, "lenghtt")
+        .unwrap_or(0)
+        .min(MAX_NEIGHBOR_CELLS);
+
+    let field = |key: &str, i: usize| field(data, &format!("{}{}", key, i)).map(String::from);
+    (1..=count)
+        .map(|i| NeighborCell {
+            kind: field("type", i),
+            band: field("band", i),
+            earfcn: field("earfcn", i),
+            pcid: field("pcid", i),
+            rsrq: field("rsrq", i),
+            rsrp: field("rsrp", i),
+            rsrppp: field("rsrppp", i),
+        })
+        .collect()
+}
+
+// ---------- live table ----------
+// render_columns puts the border, title, rule, header and rule above the rows
+const TABLE_HEAD_LINES: usize = 5;
+
+type Signals = HashMap<String, f64>;
+
+fn signals(cells: &[NeighborCell]) -> Signals {
+    cells
+        .iter()
+        .filter_map(|cell| Some((cell.key(), cell.rsrp_dbm()?)))
+        .collect()
+}
+
+/// The RSRP change since the previous scan, `None` for a cell not seen then.
+fn signal_change(previous: &Signals, cell: &NeighborCell) -> Option<f64> {
+    Some(cell.rsrp_dbm()? - previous.get(&cell.key())?)
+}
+
+fn change_text(change: Option<f64>, first_scan: bool) -> String {
+    match change {
+        Some(change) if change > 0.0 => format!("+{} \u{25b2}", change),
+        Some(change) if change < 0.0 => format!("{} \u{25bc}", change),
+        Some(_) => "0".to_string(),
+        None if first_scan => "-".to_string(),
+        None => "new".to_string(),
+    }
+}
+
+/// Headers and rows for the cells, already sorted: rank, the router's
+/// values and the difference to the serving cell.
+fn cell_rows(
+    cells: &[NeighborCell],
+    serving: Option<f64>,
+) -> (Vec<&'static str>, Vec<Vec<String>>) {
+    let mut headers = vec!["#"];
+    if let Some(cell) = cells.first() {
+        headers.extend(cell.labeled().iter().map(|(label, _)| *label));
+    }
+    headers.push("vs serving");
+    let rows = cells
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| {
+            let mut row = vec![(i + 1).to_string()];
+            row.extend(
+                cell.labeled()
+                    .iter()
+                    .map(|(_, value)| value.unwrap_or("-").to_string()),
+            );
+            row.push(delta_text(cell.delta_db(serving)));
+            row
+        })
+        .collect();
+    (headers, rows)
+}
+
+/// The table for one scan, cells already sorted; rows whose signal improved
+/// since `previous` are marked and, with colour on, green.
+fn live_table(
+    cells: &[NeighborCell],
+    serving: Option<f64>,
+    previous: &Signals,
+    first_scan: bool,
+) -> String {
+    let (mut headers, mut rows) = cell_rows(cells, serving);
+    headers.push("Change");
+    let changes: Vec<Option<f64>> = cells.iter().map(|c| signal_change(previous, c)).collect();
+    for (row, &change) in rows.iter_mut().zip(&changes) {
+        row.push(change_text(change, first_scan));
+    }
+
+    let table = render_columns("Neighbour cells", &headers, &rows, terminal::width());
+    table
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let improved = i
+                .checked_sub(TABLE_HEAD_LINES)
+                .and_then(|row| changes.get(row))
+                .is_some_and(|change| change.is_some_and(|c| c > 0.0));
+            if improved {
+                color::paint(line, Quality::Good)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Redraws the neighbour table every `interval` until a key is pressed.
+async fn run_live(client: &RouterClient, interval: Duration) -> Result<(), Box<dyn Error>> {
+    let guard = TuiGuard::enter(io::stdout(), false)?;
+    let mut previous = Signals::new();
+    let mut screen = Screen::default();
+    let mut scans = 0u64;
+
+    loop {
+        let body = match client.api_request("get_neighbour_cell").await {
+            Ok(data) => {
+                let cells = ranked_cells(&data);
+                // without the serving cell the table just has no deltas
+                let serving = match client.api_request("get_index_data").await {
+                    Ok(index) => serving_rsrp(&index),
+                    Err(_) => None,
+                };
+                let body = if cells.is_empty() {
+                    "Router reported no neighbour cells".to_string()
+                } else {
+                    live_table(&cells, serving, &previous, scans == 0)
+                };
+                previous = signals(&cells);
+                body
+            }
+            Err(e) => format!("Neighbour scan failed: {}", e),
+        };
+        scans += 1;
+        let footer = format!(
+            "Scan {} at {}, every {}s. Press any key to stop.",
+            scans,
+            timestamp::now_display(),
+            interval.as_secs()
+        );
+        screen.draw(&mut io::stdout(), &format!("{}\n\n{}", body, footer))?;
+
+        if terminal::key_pressed_within(interval)? {
+            break;
+        }
+    }
+
+    drop(guard);
+    eprintln!("Stopped after {} scan(s)", scans);
+    Ok(())
+}
+
+/// The cells of one scan, strongest first, as a printed table.
+pub fn table(cells: &[NeighborCell], serving: Option<f64>) -> String {
+    if cells.is_empty() {
+        return "Router reported no neighbour cells\n".to_string();
+    }
+    let (headers, rows) = cell_rows(cells, serving);
+    render_columns("Neighbour cells", &headers, &rows, terminal::width())
+}
+
+/// One scan as a table, paged when it does not fit.
+async fn print_table(client: &RouterClient) -> Result<(), Box<dyn Error>> {
+    let cells = ranked_cells(&client.api_request("get_neighbour_cell").await?);
+    let serving = match cells.is_empty() {
+        true => None,
+        false => match client.api_request("get_index_data").await {
+            Ok(index) => serving_rsrp(&index),
+            Err(_) => None,
+        },
+    };
+    pager::print(&table(&cells, serving));
+    Ok(())
+}
+
+// ---------- scanning ----------
+pub struct ScanOptions {
+    pub watch: bool,
+    pub interval: Duration,
+    pub log_jsonl: Option<PathBuf>,
+    pub gps_cmd: Option<String>,
+}
+
+// ---------- GPS ----------
+// Runs the user supplied command through the shell and expects "lat lon" or
+// "lat,lon" on its stdout.
+async fn read_gps(gps_cmd: &str) -> Result<(f64, f64), Box<dyn Error>> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", gps_cmd]).output().await?
+    } else {
+        Command::new("sh").args(["-c", gps_cmd]).output().await?
+    };
+    if !output.status.success() {
+        return Err(format!("GPS command exited with {}", output.status).into());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut coords = stdout
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<f64>());
+    match (coords.next(), coords.next()) {
+        (Some(Ok(lat)), Some(Ok(lon))) => Ok((lat, lon)),
+        _ => Err(format!("GPS command printed no coordinates: {:?}", stdout.trim()).into()),
+    }
+}
+
+async fn scan_record(client: &RouterClient, gps_cmd: Option<&str>) -> Value {
+    let mut record = json!({ "time": timestamp::now_iso() });
+
+    if let Some(cmd) = gps_cmd {
+        match read_gps(cmd).await {
+            Ok((lat, lon)) => record["gps"] = json!({ "lat": lat, "lon": lon }),
+            Err(e) => record["gps_error"] = Value::String(e.to_string()),
+        }
+    }
+
+    match client.api_request("get_neighbour_cell").await {
+        Ok(cells) => record["cells"] = cells,
+        Err(e) => record["error"] = Value::String(e.to_string()),
+    }
+
+    record
+}
+
+fn write_record(log_jsonl: Option<&PathBuf>, record: &Value, raw: bool) -> io::Result<()> {
+    match log_jsonl {
+        Some(path) => {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", record)
+        }
+        // raw mode shows the router's answer alone, errors go to stderr
+        None if raw => match record.get("cells") {
+            Some(cells) => writeln!(io::stdout(), "{:#}", cells),
+            None => Ok(()),
+        },
+        None => writeln!(io::stdout(), "{}", record),
+    }
+}
+
+pub async fn run(
+    client: &RouterClient,
+    options: ScanOptions,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    // on a terminal a scan is a table, live with --watch; logs and pipes
+    // get JSON lines
+    let table = format == OutputFormat::Text
+        && options.log_jsonl.is_none()
+        && options.gps_cmd.is_none()
+        && io::stdout().is_terminal();
+    match (table, options.watch) {
+        (true, true) => return run_live(client, options.interval).await,
+        (true, false) => return print_table(client).await,
+        (false, _) => {}
+    }
+
+    let raw = format == OutputFormat::Raw;
+    let mut scans = 0u64;
+
+    loop {
+        let record = scan_record(client, options.gps_cmd.as_deref()).await;
+        write_record(options.log_jsonl.as_ref(), &record, raw)?;
+        scans += 1;
+
+        if !options.watch {
+            return match record["error"].as_str() {
+                Some(e) => Err(format!("Neighbour scan failed: {}", e).into()),
+                None => Ok(()),
+            };
+        }
+
+        if options.log_jsonl.is_some() || raw {
+            match record["error"].as_str() {
+                Some(e) => eprintln!("Scan {}: error: {}", scans, e),
+                None => {
+                    let count = field(&record["cells"], "lenghtt").unwrap_or_default();
+                    eprintln!("Scan {}: {} neighbour cell(s)", scans, count);
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(jitter::apply(options.interval)) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    eprintln!("Stopped after {} scan(s)", scans);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_follow_the_router_keys() {
+        let sample = json!({
+            "lenghtt": "2",
+            "type1": "LTE", "band1": "3", "pcid1": "245",
+            "rsrq1": "-11", "rsrp1": "-97", "rsrppp1": "-68",
+            "type2": "LTE", "band2": "20", "pcid2": "17",
+            "rsrq2": "-14", "rsrp2": "-108", "rsrppp2": "-79",
+        });
+        let cells = parse_neighbor_cells(&sample);
+        assert_eq!(cells.len(), 2);
+        assert_eq!(
+            cells[1].labeled(),
+            [
+                ("Type", Some("LTE")),
+                ("Band", Some("20")),
+                ("EARFCN", None),
+                ("PCI", Some("17")),
+                ("RSRQ(dB)", Some("-14")),
+                ("RSRP(dBm)", Some("-108")),
+                ("Signal(dBm)", Some("-79")),
+            ]
+        );
+    }
+
+    #[test]
+    fn watch_sorts_by_signal_and_marks_improvements() {
+        let scan = |rsrp: [&str; 3]| {
+            json!({
+                "lenghtt": "3",
+                "band1": "3", "pcid1": "245", "rsrp1": rsrp[0],
+                "band2": "20", "pcid2": "17", "rsrp2": rsrp[1],
+                "band3": "7", "pcid3": "301", "rsrp3": rsrp[2],
+            })
+        };
+        let mut first = parse_neighbor_cells(&scan(["-97", "-108", ""]));
+        sort_by_signal(&mut first);
+        let order: Vec<_> = first.iter().map(|c| c.pcid.as_deref()).collect();
+        assert_eq!(order, [Some("245"), Some("17"), Some("301")]);
+
+        let previous = signals(&first);
+        let mut second = parse_neighbor_cells(&scan(["-99", "-95", "-120"]));
+        sort_by_signal(&mut second);
+        assert_eq!(second[0].pcid.as_deref(), Some("17"));
+        let changes: Vec<_> = second
+            .iter()
+            .map(|c| change_text(signal_change(&previous, c), false))
+            .collect();
+        assert_eq!(changes, ["+13 \u{25b2}", "-2 \u{25bc}", "new"]);
+    }
+
+    #[test]
+    fn cells_are_picked_by_rank() {
+        let cells = ranked_cells(&json!({
+            "lenghtt": "3",
+            "earfcn1": "6300", "rsrp1": "-108",
+            "earfcn2": "1850", "rsrp2": "-92",
+            "rsrp3": "-95",
+        }));
+        assert_eq!(pick_earfcn(&cells, 1), Ok(1850));
+        assert_eq!(pick_earfcn(&cells, 3), Ok(6300));
+        assert!(pick_earfcn(&cells, 2).unwrap_err().contains("no EARFCN"));
+        assert!(pick_earfcn(&cells, 0).is_err());
+        assert!(pick_earfcn(&cells, 4).is_err());
+    }
+
+    #[test]
+    fn deltas_are_against_the_serving_cell() {
+        let serving = serving_rsrp(&json!({ "RSRP": "-100 dBm" }));
+        let cells = parse_neighbor_cells(&json!({
+            "lenghtt": "2", "rsrp1": "-97", "rsrp2": "-104",
+        }));
+        let deltas: Vec<_> = cells
+            .iter()
+            .map(|c| delta_text(c.delta_db(serving)))
+            .collect();
+        assert_eq!(deltas, ["+3 dB", "-4 dB"]);
+        assert_eq!(delta_text(cells[0].delta_db(None)), "-");
+    }
+}
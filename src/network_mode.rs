@@ -0,0 +1,306 @@
+use std::borrow::Cow;
+use std::error::Error;
+
+use serde_json::{json, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::{is_success, RouterClient};
+use crate::dashboard::CONNECTION_FIELDS;
+use crate::error::ZitelError;
+use crate::fields::field;
+use crate::prompt::confirm_change;
+
+// ---------- network mode ----------
+// Which radio access technology the modem may use: the coarse companion to
+// the band lock, e.g. to keep a router off a flaky 5G cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NetworkMode {
+    Auto,
+    Lte,
+    NrSa,
+    NrNsa,
+}
+
+pub const MODES: [NetworkMode; 4] = [
+    NetworkMode::Auto,
+    NetworkMode::Lte,
+    NetworkMode::NrSa,
+    NetworkMode::NrNsa,
+];
+
+/// Said before every switch, the modem re-registers with the network.
+pub const DROP_WARNING: &str =
+    "The connection drops for a few seconds while the modem re-registers";
+
+impl NetworkMode {
+    /// The name users type, also shown in messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            NetworkMode::Auto => "auto",
+            NetworkMode::Lte => "4g",
+            NetworkMode::NrSa => "5g-sa",
+            NetworkMode::NrNsa => "5g-nsa",
+        }
+    }
+
+    fn command_arg(self) -> &'static str {
+        match self {
+            NetworkMode::Auto => "auto",
+            NetworkMode::Lte => "lte",
+            NetworkMode::NrSa => "nr5g_sa",
+            NetworkMode::NrNsa => "nr5g_nsa",
+        }
+    }
+
+    pub fn command(self) -> String {
+        format!("set_network_mode {}", self.command_arg())
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            NetworkMode::Auto => "automatic (4G or 5G)",
+            NetworkMode::Lte => "4G only",
+            NetworkMode::NrSa => "5G standalone",
+            NetworkMode::NrNsa => "5G non-standalone (with a 4G anchor)",
+        }
+    }
+
+    /// Whether the connection type the router reports (`TYPE`, e.g. `LTE`,
+    /// `NR5G-SA`, `ENDC`) is one this mode allows.
+    pub fn allows(self, connection_type: &str) -> bool {
+        let technology = Technology::parse(connection_type);
+        match self {
+            NetworkMode::Auto => true,
+            NetworkMode::Lte => {
+                matches!(technology, Some(Technology::Lte | Technology::LteAdvanced))
+            }
+            NetworkMode::NrSa => technology == Some(Technology::NrSa),
+            NetworkMode::NrNsa => technology == Some(Technology::NrNsa),
+        }
+    }
+}
+
+// ---------- connection type ----------
+// What `TYPE` means, whichever way the firmware spells it: `LTE`, `FDD LTE`,
+// `LTE-A`, `NR5G-NSA`, `ENDC`, `NR5G-SA`, `WCDMA`, ...
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Technology {
+    Gsm,
+    Umts,
+    Lte,
+    LteAdvanced,
+    NrNsa,
+    NrSa,
+}
+
+impl Technology {
+    pub fn parse(connection_type: &str) -> Option<Self> {
+        let t: String = connection_type
+            .to_uppercase()
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .collect();
+        let any = |names: &[&str]| names.iter().any(|name| t.contains(name));
+        // "NR" alone would match inside words such as UNREGISTERED
+        let nr = t.starts_with("NR") || any(&["5G", "+NR", "LTENR"]);
+        let lte = any(&["LTE", "4G"]);
+        Some(if any(&["NSA", "ENDC"]) || (lte && nr) {
+            Technology::NrNsa
+        } else if nr {
+            Technology::NrSa
+        } else if any(&["LTEA", "LTE+", "LTECA", "4G+"]) {
+            Technology::LteAdvanced
+        } else if lte {
+            Technology::Lte
+        } else if any(&["WCDMA", "UMTS", "HSPA", "HSDPA", "HSUPA", "3G"]) {
+            Technology::Umts
+        } else if any(&["GSM", "GPRS", "EDGE", "2G"]) {
+            Technology::Gsm
+        } else {
+            return None;
+        })
+    }
+
+    /// The badge text, the same for every spelling.
+    pub fn label(self) -> &'static str {
+        match self {
+            Technology::Gsm => "2G",
+            Technology::Umts => "3G",
+            Technology::Lte => "4G LTE",
+            Technology::LteAdvanced => "4G LTE-A",
+            Technology::NrNsa => "5G NSA",
+            Technology::NrSa => "5G SA",
+        }
+    }
+
+    /// The type as the router sent it, when the label does not already
+    /// say as much: `NR5G-NSA` for "5G NSA", but not `LTE` for "4G LTE".
+    pub fn spelling(self, reported: &str) -> Option<&str> {
+        let reported = reported.trim();
+        (!self.label().contains(&reported.to_uppercase())).then_some(reported)
+    }
+
+    /// The short form for the status line: "4G", "5G" or "5G NSA".
+    pub fn generation(self) -> &'static str {
+        match self {
+            Technology::Gsm => "2G",
+            Technology::Umts => "3G",
+            Technology::Lte | Technology::LteAdvanced => "4G",
+            Technology::NrNsa => "5G NSA",
+            Technology::NrSa => "5G",
+        }
+    }
+}
+
+/// The badge text for a reported type, the type as sent when it is none
+/// `Technology` knows.
+pub fn connection_label(connection_type: &str) -> Cow<'_, str> {
+    match Technology::parse(connection_type) {
+        Some(technology) => Cow::Borrowed(technology.label()),
+        None => Cow::Borrowed(connection_type.trim()),
+    }
+}
+
+/// Accepts the names `MODES` lists, plus `lte` and `5g` for `4g`/`5g-sa`.
+pub fn parse_mode(text: &str) -> Result<NetworkMode, String> {
+    let text = text.trim().to_lowercase().replace('_', "-");
+    let alias = match text.as_str() {
+        "lte" | "4g-only" => Some(NetworkMode::Lte),
+        "5g" | "sa" => Some(NetworkMode::NrSa),
+        "nsa" => Some(NetworkMode::NrNsa),
+        _ => None,
+    };
+    alias
+        .or_else(|| MODES.into_iter().find(|m| m.name() == text))
+        .ok_or_else(|| {
+            let names: Vec<_> = MODES.iter().map(|m| m.name()).collect();
+            format!(
+                "Unknown network mode {:?}, supported: {}",
+                text,
+                names.join(", ")
+            )
+        })
+}
+
+fn connection_type(data: &Value) -> Option<String> {
+    CONNECTION_FIELDS
+        .iter()
+        .find(|f| f.name == "type")
+        .and_then(|f| field(data, f.spec))
+        .map(String::from)
+}
+
+/// What to say once the router took the mode: the connection type it reads
+/// back, and whether that already fits.
+pub fn switch_message(mode: NetworkMode, connection_type: Option<&str>) -> String {
+    let set = format!("Network mode set to {}", mode.describe());
+    match connection_type {
+        Some(t) if mode.allows(t) => format!("{}, connected via {}", set, t),
+        Some(t) => format!(
+            "{}, still connected via {} (the modem may not have re-registered yet)",
+            set, t
+        ),
+        None => format!("{}, connection type not reported", set),
+    }
+}
+
+/// Sends the mode and reads `TYPE` back; the message says what it is now.
+pub async fn switch(client: &RouterClient, mode: NetworkMode) -> Result<String, Box<dyn Error>> {
+    let resp = client.set_network_mode(mode).await?;
+    if !is_success(&resp) {
+        return Err(ZitelError::refused("Network mode change failed", &resp).into());
+    }
+    let data = client.api_request("get_index_data").await?;
+    let message = switch_message(mode, connection_type(&data).as_deref());
+    Ok(client.outcome(&message))
+}
+
+pub async fn run(
+    client: &RouterClient,
+    mode: Option<NetworkMode>,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let Some(mode) = mode else {
+        let data = client.api_request("get_index_data").await?;
+        let current = connection_type(&data);
+        match format {
+            OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&data)?),
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&json!({ "type": current }))?
+            ),
+            OutputFormat::Text => {
+                let label = current.as_deref().map(connection_label);
+                println!("Connected via {}", label.as_deref().unwrap_or("unknown"))
+            }
+        }
+        return Ok(());
+    };
+
+    let question = format!("Switch the network mode to {}?", mode.describe());
+    eprintln!("Warning: {}", DROP_WARNING);
+    confirm_change(client, &question)?;
+    let message = switch(client, mode).await?;
+    match format {
+        OutputFormat::Json | OutputFormat::Raw => {
+            let result = json!({
+                "success": true,
+                "mode": mode.name(),
+                "dry_run": client.dry_run(),
+                "message": message,
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text => println!("{}", message),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_parse_from_names_and_aliases() {
+        assert_eq!(parse_mode("4G"), Ok(NetworkMode::Lte));
+        assert_eq!(parse_mode("lte"), Ok(NetworkMode::Lte));
+        assert_eq!(parse_mode("5g_nsa"), Ok(NetworkMode::NrNsa));
+        assert_eq!(parse_mode(" auto "), Ok(NetworkMode::Auto));
+        let err = parse_mode("3g").unwrap_err();
+        assert!(err.contains("auto, 4g, 5g-sa, 5g-nsa"), "{}", err);
+    }
+
+    #[test]
+    fn the_read_back_type_is_checked_against_the_mode() {
+        assert!(NetworkMode::Lte.allows("LTE"));
+        assert!(!NetworkMode::Lte.allows("NR5G-NSA"));
+        assert!(NetworkMode::NrNsa.allows("ENDC"));
+        assert!(NetworkMode::NrSa.allows("NR5G-SA"));
+        assert!(!NetworkMode::NrSa.allows("LTE"));
+        assert!(!NetworkMode::NrSa.allows("UNREGISTERED"));
+        assert_eq!(
+            switch_message(NetworkMode::NrSa, Some("LTE")),
+            "Network mode set to 5G standalone, still connected via LTE \
+             (the modem may not have re-registered yet)"
+        );
+    }
+
+    #[test]
+    fn connection_types_get_one_label_each() {
+        let label = |t| connection_label(t).into_owned();
+        assert_eq!(label("LTE"), "4G LTE");
+        assert_eq!(label("FDD LTE"), "4G LTE");
+        assert_eq!(label("lte-a"), "4G LTE-A");
+        assert_eq!(label("NR5G-NSA"), "5G NSA");
+        assert_eq!(label("EN-DC"), "5G NSA");
+        assert_eq!(label("LTE+NR"), "5G NSA");
+        assert_eq!(label("NR5G-SA"), "5G SA");
+        assert_eq!(label("5G"), "5G SA");
+        assert_eq!(label("WCDMA"), "3G");
+        assert_eq!(label("EDGE"), "2G");
+        assert_eq!(label(" NO SERVICE "), "NO SERVICE");
+        assert_eq!(Technology::LteAdvanced.generation(), "4G");
+        assert_eq!(Technology::Lte.spelling("lte"), None);
+        assert_eq!(Technology::NrNsa.spelling("ENDC "), Some("ENDC"));
+    }
+}
@@ -0,0 +1,95 @@
+use serde_json::Value;
+
+use crate::fields::field;
+
+// ---------- operator names ----------
+// The carrier behind the MCC/MNC the router reports, for the networks Zitel
+// routers are commonly used on. Sorted by MCC, then MNC.
+const OPERATORS: &[(u16, u16, &str)] = &[
+    (234, 10, "O2"),
+    (234, 15, "Vodafone"),
+    (234, 20, "Three"),
+    (234, 30, "EE"),
+    (234, 33, "EE"),
+    (262, 1, "Telekom"),
+    (262, 2, "Vodafone"),
+    (262, 3, "O2"),
+    (262, 7, "O2"),
+    (302, 220, "Telus"),
+    (302, 370, "Fido"),
+    (302, 610, "Bell"),
+    (302, 720, "Rogers"),
+    (310, 4, "Verizon"),
+    (310, 12, "Verizon"),
+    (310, 120, "Sprint"),
+    (310, 150, "AT&T"),
+    (310, 160, "T-Mobile"),
+    (310, 170, "AT&T"),
+    (310, 200, "T-Mobile"),
+    (310, 260, "T-Mobile"),
+    (310, 280, "AT&T"),
+    (310, 410, "AT&T"),
+    (310, 490, "T-Mobile"),
+    (311, 180, "AT&T"),
+    (311, 480, "Verizon"),
+    (311, 580, "UScellular"),
+    (312, 530, "Sprint"),
+    (313, 100, "FirstNet"),
+];
+
+const MCC_KEY: &str = "MCC";
+const MNC_KEY: &str = "MNC";
+
+/// The carrier for a code pair. MNCs compare as numbers, firmware differs
+/// on whether "02" or "2" is sent.
+pub fn operator_name(mcc: &str, mnc: &str) -> Option<&'static str> {
+    let mcc: u16 = mcc.trim().parse().ok()?;
+    let mnc: u16 = mnc.trim().parse().ok()?;
+    OPERATORS
+        .iter()
+        .find(|(c, n, _)| *c == mcc && *n == mnc)
+        .map(|(_, _, name)| *name)
+}
+
+/// The operator row of the dashboard: the carrier name, else the raw codes,
+/// `None` when the router reports neither code.
+pub fn describe(data: &Value) -> Option<String> {
+    let mcc = field(data, MCC_KEY);
+    let mnc = field(data, MNC_KEY);
+    match (mcc.as_deref(), mnc.as_deref()) {
+        (Some(mcc), Some(mnc)) => Some(match operator_name(mcc, mnc) {
+            Some(name) => name.to_string(),
+            None => format!("unknown ({}/{})", mcc, mnc),
+        }),
+        (Some(code), None) | (None, Some(code)) => Some(format!("unknown ({})", code)),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn codes_map_to_carriers_or_stay_raw() {
+        assert_eq!(operator_name("310", "260"), Some("T-Mobile"));
+        assert_eq!(operator_name("262", "02"), operator_name("262", "2"));
+        assert_eq!(
+            describe(&json!({ "MCC": "262", "MNC": "02" })).as_deref(),
+            Some("Vodafone")
+        );
+        assert_eq!(
+            describe(&json!({ "MCC": "999", "MNC": "01" })).as_deref(),
+            Some("unknown (999/01)")
+        );
+        assert_eq!(describe(&json!({})), None);
+    }
+
+    #[test]
+    fn the_table_is_sorted_without_duplicates() {
+        assert!(OPERATORS
+            .windows(2)
+            .all(|w| (w[0].0, w[0].1) < (w[1].0, w[1].1)));
+    }
+}
@@ -0,0 +1,88 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::terminal;
+
+// ---------- pager ----------
+// Printed text taller than the terminal goes through $PAGER (else less),
+// the way git does it. Decided once at startup: never for JSON, pipes or
+// with --no-pager.
+static USE_PAGER: AtomicBool = AtomicBool::new(false);
+
+const DEFAULT_PAGER: &str = if cfg!(windows) { "more" } else { "less" };
+
+pub fn set_enabled(enabled: bool) {
+    USE_PAGER.store(enabled, Ordering::Relaxed);
+}
+
+/// The pager to run for `$PAGER`; set but empty means none, as for git.
+fn pager_command(env: Option<&str>) -> Option<&str> {
+    match env.map(str::trim) {
+        None => Some(DEFAULT_PAGER),
+        Some("") | Some("cat") => None,
+        Some(pager) => Some(pager),
+    }
+}
+
+fn spawn(pager: &str) -> io::Result<std::process::Child> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", pager]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-c", pager]);
+        command
+    };
+    // quit at once when it fits after all, keep colours, leave the text on
+    // the screen; only if the user has no LESS of their own
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    command.stdin(Stdio::piped()).spawn()
+}
+
+/// Prints `text`, paged when it does not fit on the screen.
+pub fn print(text: &str) {
+    let pager = std::env::var("PAGER").ok();
+    let tall = terminal::height().is_some_and(|rows| text.lines().count() >= rows);
+    let pager = match pager_command(pager.as_deref()) {
+        Some(pager) if tall && USE_PAGER.load(Ordering::Relaxed) => pager,
+        _ => {
+            print!("{}", text);
+            return;
+        }
+    };
+    let mut child = match spawn(pager) {
+        Ok(child) => child,
+        Err(e) => {
+            log::debug!("Cannot start the pager {:?}: {}", pager, e);
+            print!("{}", text);
+            return;
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // a pager quit before the end closes the pipe, that is not an error
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    // 127: the shell did not find the pager
+    if let Ok(status) = child.wait() {
+        if status.code() == Some(127) {
+            print!("{}", text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_empty_pager_turns_paging_off() {
+        assert_eq!(pager_command(None), Some(DEFAULT_PAGER));
+        assert_eq!(pager_command(Some("less -S")), Some("less -S"));
+        assert_eq!(pager_command(Some(" ")), None);
+        assert_eq!(pager_command(Some("cat")), None);
+    }
+}
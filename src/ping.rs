@@ -0,0 +1,202 @@
+use std::error::Error;
+
+use serde_json::{json, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::{failure_reason, RouterClient};
+use crate::fields::{field, field_parse};
+use crate::prompt::ask;
+
+pub const MAX_PING_COUNT: u32 = 20;
+
+// Keys firmware uses for the ping program's own output, and for the figures
+// when the router has already parsed them.
+const OUTPUT_SPEC: &str = "output|result|ping|data";
+const MIN_SPEC: &str = "min|rtt_min";
+const AVG_SPEC: &str = "avg|rtt_avg";
+const MAX_SPEC: &str = "max|rtt_max";
+const LOSS_SPEC: &str = "loss|packet_loss";
+
+/// Accepts host names, IPv4 and IPv6 addresses. Anything else is refused
+/// since the host ends up in the router's command line.
+pub fn validate_host(input: &str) -> Result<String, String> {
+    let host = input.trim();
+    let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':');
+    if !host.is_empty() && host.len() <= 253 && host.chars().all(allowed) {
+        Ok(host.to_string())
+    } else {
+        Err(format!("{:?} is not a host name or IP address", input))
+    }
+}
+
+#[derive(Default, PartialEq, Debug)]
+pub struct PingStats {
+    pub min_ms: Option<f64>,
+    pub avg_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+    pub loss_pct: Option<f64>,
+}
+
+// "rtt min/avg/max/mdev = 9.8/12.1/15.0/1.9 ms" (iputils) or
+// "round-trip min/avg/max = 9.8/12.1/15.0 ms" (busybox).
+fn parse_rtt(line: &str) -> Option<(f64, f64, f64)> {
+    let (names, values) = line.split_once('=')?;
+    if !names.contains("min/avg/max") {
+        return None;
+    }
+    let mut values = values
+        .trim()
+        .trim_end_matches("ms")
+        .split('/')
+        .map(|v| v.trim().parse::<f64>());
+    Some((
+        values.next()?.ok()?,
+        values.next()?.ok()?,
+        values.next()?.ok()?,
+    ))
+}
+
+// "4 packets transmitted, 4 received, 0% packet loss" in either dialect.
+fn parse_loss(line: &str) -> Option<f64> {
+    let before = &line[..line.find("% packet loss")?];
+    let start = before.rfind(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    before[start + 1..].parse().ok()
+}
+
+/// Summary figures out of ping's text output.
+pub fn parse_output(output: &str) -> PingStats {
+    let mut stats = PingStats::default();
+    for line in output.lines() {
+        if let Some((min, avg, max)) = parse_rtt(line) {
+            stats.min_ms = Some(min);
+            stats.avg_ms = Some(avg);
+            stats.max_ms = Some(max);
+        }
+        if let Some(loss) = parse_loss(line) {
+            stats.loss_pct = Some(loss);
+        }
+    }
+    stats
+}
+
+fn stats_from_response(resp: &Value) -> PingStats {
+    let parsed = PingStats {
+        min_ms: field_parse(resp, MIN_SPEC),
+        avg_ms: field_parse(resp, AVG_SPEC),
+        max_ms: field_parse(resp, MAX_SPEC),
+        loss_pct: field(resp, LOSS_SPEC).and_then(|v| v.trim_end_matches('%').trim().parse().ok()),
+    };
+    match field(resp, OUTPUT_SPEC) {
+        Some(output) if parsed == PingStats::default() => parse_output(&output),
+        _ => parsed,
+    }
+}
+
+fn ms(value: Option<f64>) -> String {
+    value.map_or("-".to_string(), |v| format!("{:.1} ms", v))
+}
+
+fn render_text(host: &str, count: u32, output: Option<&str>, stats: &PingStats) -> String {
+    let mut out = format!(
+        "--- ping {} from the router, {} packet(s) ---\n",
+        host, count
+    );
+    if let Some(output) = output {
+        out.push_str(&format!("{}\n---\n", output.trim_end()));
+    }
+    out.push_str(&format!(
+        "min {}  avg {}  max {}  loss {}\n",
+        ms(stats.min_ms),
+        ms(stats.avg_ms),
+        ms(stats.max_ms),
+        stats
+            .loss_pct
+            .map_or("-".to_string(), |l| format!("{}%", l))
+    ));
+    out
+}
+
+pub async fn run(
+    client: &RouterClient,
+    host: Option<String>,
+    count: u32,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let host = match host {
+        Some(host) => host,
+        None => validate_host(&ask("Host to ping")?)?,
+    };
+    let resp = client.ping(&host, count).await?;
+    let stats = stats_from_response(&resp);
+    let output = field(&resp, OUTPUT_SPEC);
+
+    match format {
+        OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&resp)?),
+        OutputFormat::Json => {
+            let result = json!({
+                "host": host,
+                "count": count,
+                "min_ms": stats.min_ms,
+                "avg_ms": stats.avg_ms,
+                "max_ms": stats.max_ms,
+                "loss_pct": stats.loss_pct,
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text => print!("{}", render_text(&host, count, output.as_deref(), &stats)),
+    }
+
+    if output.is_none() && stats == PingStats::default() {
+        let reason = failure_reason(&resp).unwrap_or("router returned no ping results");
+        return Err(format!("Ping failed: {}", reason).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_iputils_and_busybox_summaries() {
+        let iputils = "4 packets transmitted, 3 received, 25% packet loss, time 3004ms\n\
+                       rtt min/avg/max/mdev = 9.812/12.104/15.020/1.903 ms";
+        let stats = parse_output(iputils);
+        assert_eq!(stats.min_ms, Some(9.812));
+        assert_eq!(stats.max_ms, Some(15.02));
+        assert_eq!(stats.loss_pct, Some(25.0));
+
+        let busybox = "4 packets transmitted, 4 packets received, 0% packet loss\n\
+                       round-trip min/avg/max = 20.1/22.5/30.0 ms";
+        let stats = parse_output(busybox);
+        assert_eq!(stats.avg_ms, Some(22.5));
+        assert_eq!(stats.loss_pct, Some(0.0));
+    }
+
+    #[test]
+    fn unreachable_hosts_only_report_loss() {
+        let stats = parse_output("2 packets transmitted, 0 received, 100% packet loss");
+        assert_eq!(stats.loss_pct, Some(100.0));
+        assert_eq!(stats.avg_ms, None);
+    }
+
+    #[test]
+    fn text_output_frames_the_router_output() {
+        let stats = parse_output("1 packets transmitted, 1 received, 0% packet loss");
+        assert_eq!(
+            render_text("8.8.8.8", 1, Some("64 bytes from 8.8.8.8\n"), &stats),
+            "--- ping 8.8.8.8 from the router, 1 packet(s) ---\n\
+             64 bytes from 8.8.8.8\n---\n\
+             min -  avg -  max -  loss 0%\n"
+        );
+    }
+
+    #[test]
+    fn hosts_cannot_smuggle_commands() {
+        assert_eq!(validate_host(" 8.8.8.8 ").unwrap(), "8.8.8.8");
+        assert!(validate_host("2001:4860:4860::8888").is_ok());
+        assert!(validate_host("example.com").is_ok());
+        assert!(validate_host("8.8.8.8; reboot").is_err());
+        assert!(validate_host("").is_err());
+    }
+}
@@ -0,0 +1,263 @@
+use std::error::Error;
+use std::net::Ipv4Addr;
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::{is_success, RouterClient};
+use crate::dashboard::render_columns;
+use crate::error::ZitelError;
+use crate::fields::{field, list_records};
+use crate::prompt::confirm_change;
+use crate::terminal;
+
+// ---------- rules ----------
+// Port forwards are the finer-grained alternative to the DMZ: one external
+// port to one LAN host and port instead of everything to one host.
+const LIST_KEYS: &[&str] = &["rules", "port_forward", "list"];
+const MAX_RULES: usize = 128;
+
+const ID_SPEC: &str = "id|index";
+const EXTERNAL_PORT_SPEC: &str = "ext_port|external_port|wan_port";
+const IP_SPEC: &str = "ip|internal_ip|lan_ip";
+const INTERNAL_PORT_SPEC: &str = "int_port|internal_port|lan_port";
+const PROTOCOL_SPEC: &str = "proto|protocol";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+    #[value(alias = "tcpudp")]
+    Both,
+}
+
+impl Protocol {
+    // as the router spells it, the same as for the DMZ
+    pub fn command_name(self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+            Protocol::Both => "tcpudp",
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Protocol::Tcp => "TCP",
+            Protocol::Udp => "UDP",
+            Protocol::Both => "TCP and UDP",
+        }
+    }
+}
+
+pub struct Rule {
+    /// The router's id for the rule, or its position in the list when the
+    /// router gives none; what `delete` takes.
+    pub id: String,
+    pub external_port: Option<String>,
+    pub ip: Option<String>,
+    pub internal_port: Option<String>,
+    pub protocol: Option<String>,
+}
+
+impl Rule {
+    fn from_entry(position: usize, entry: &Value) -> Self {
+        let value = |spec| field(entry, spec).map(String::from);
+        Self {
+            id: value(ID_SPEC).unwrap_or_else(|| position.to_string()),
+            external_port: value(EXTERNAL_PORT_SPEC),
+            ip: value(IP_SPEC),
+            internal_port: value(INTERNAL_PORT_SPEC),
+            protocol: value(PROTOCOL_SPEC),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "external_port": self.external_port,
+            "ip": self.ip,
+            "internal_port": self.internal_port,
+            "protocol": self.protocol,
+        })
+    }
+}
+
+pub fn parse_rules(data: &Value) -> Vec<Rule> {
+    list_records(data, LIST_KEYS, MAX_RULES)
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| Rule::from_entry(i + 1, entry))
+        .collect()
+}
+
+pub const HEADERS: [&str; 5] = ["ID", "External port", "LAN host", "LAN port", "Protocol"];
+
+fn rule_rows(rules: &[Rule]) -> Vec<Vec<String>> {
+    rules
+        .iter()
+        .map(|r| {
+            let dash = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+            vec![
+                r.id.clone(),
+                dash(&r.external_port),
+                dash(&r.ip),
+                dash(&r.internal_port),
+                dash(&r.protocol),
+            ]
+        })
+        .collect()
+}
+
+/// A rule id as `list` shows it; it goes into a command, so no spaces.
+pub fn validate_id(id: &str) -> Result<String, String> {
+    let id = id.trim();
+    if id.is_empty() || id.chars().any(char::is_whitespace) {
+        return Err(format!("{:?} is not a rule id, see `forward list`", id));
+    }
+    Ok(id.to_string())
+}
+
+// ---------- commands ----------
+pub struct NewRule {
+    pub external_port: u16,
+    pub ip: Ipv4Addr,
+    pub internal_port: u16,
+    pub protocol: Protocol,
+}
+
+impl NewRule {
+    /// Port 0 is not a port; everything else is checked by the types.
+    pub fn add_command(&self) -> Result<String, ZitelError> {
+        if self.external_port == 0 || self.internal_port == 0 {
+            return Err(ZitelError::Input(
+                "Ports must be between 1 and 65535".to_string(),
+            ));
+        }
+        Ok(format!(
+            "add_port_forward {} {} {} {}",
+            self.protocol.command_name(),
+            self.external_port,
+            self.ip,
+            self.internal_port
+        ))
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} port {} to {}:{}",
+            self.protocol.command_name(),
+            self.external_port,
+            self.ip,
+            self.internal_port
+        )
+    }
+}
+
+pub enum ForwardAction {
+    List,
+    Add(NewRule),
+    Delete { id: String },
+}
+
+pub async fn run(
+    client: &RouterClient,
+    action: ForwardAction,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let (resp, summary) = match action {
+        ForwardAction::List => {
+            let resp = client.port_forwards().await?;
+            let rules = parse_rules(&resp);
+            match format {
+                OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&resp)?),
+                OutputFormat::Json => {
+                    let list: Vec<Value> = rules.iter().map(Rule::to_json).collect();
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&json!({ "rules": list }))?
+                    );
+                }
+                OutputFormat::Text if rules.is_empty() => println!("No port forwarding rules"),
+                OutputFormat::Text => print!(
+                    "{}",
+                    render_columns(
+                        "Port Forwarding",
+                        &HEADERS,
+                        &rule_rows(&rules),
+                        terminal::width(),
+                    )
+                ),
+            }
+            return Ok(());
+        }
+        ForwardAction::Add(rule) => {
+            rule.add_command()?;
+            confirm_change(client, &format!("Forward {}?", rule.describe()))?;
+            let resp = client.add_port_forward(&rule).await?;
+            if !is_success(&resp) {
+                return Err(ZitelError::refused("Adding the port forward failed", &resp).into());
+            }
+            (resp, format!("Forwarding {}", rule.describe()))
+        }
+        ForwardAction::Delete { id } => {
+            confirm_change(client, &format!("Delete port forwarding rule {}?", id))?;
+            let resp = client.delete_port_forward(&id).await?;
+            if !is_success(&resp) {
+                return Err(ZitelError::refused("Deleting the port forward failed", &resp).into());
+            }
+            (resp, format!("Port forwarding rule {} deleted", id))
+        }
+    };
+
+    let summary = client.outcome(&summary);
+    match format {
+        OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&resp)?),
+        OutputFormat::Json => {
+            let result = json!({
+                "success": true,
+                "dry_run": client.dry_run(),
+                "message": summary,
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text => println!("{}", summary),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_rules_with_and_without_ids() {
+        let data = json!({ "rules": [
+            { "id": "7", "ext_port": 8080, "ip": "192.168.0.20", "int_port": 80, "proto": "tcp" },
+            { "external_port": "51820", "internal_ip": "192.168.0.30", "protocol": "udp" },
+        ] });
+        let rows = rule_rows(&parse_rules(&data));
+        assert_eq!(rows[0], vec!["7", "8080", "192.168.0.20", "80", "tcp"]);
+        assert_eq!(rows[1], vec!["2", "51820", "192.168.0.30", "-", "udp"]);
+    }
+
+    #[test]
+    fn add_commands_spell_the_protocol_like_the_router() {
+        let rule = NewRule {
+            external_port: 2222,
+            ip: Ipv4Addr::new(192, 168, 0, 5),
+            internal_port: 22,
+            protocol: Protocol::Both,
+        };
+        assert_eq!(
+            rule.add_command().unwrap(),
+            "add_port_forward tcpudp 2222 192.168.0.5 22"
+        );
+        let zero = NewRule {
+            external_port: 0,
+            ..rule
+        };
+        assert!(zero.add_command().is_err());
+    }
+}
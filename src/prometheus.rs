@@ -0,0 +1,264 @@
+use std::error::Error;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::client::RouterClient;
+use crate::jitter;
+use crate::metrics::{self, Metric};
+use crate::timestamp;
+
+// A scraper that connects and sends nothing is dropped after this long.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const METRIC_PREFIX: &str = "zitel";
+
+fn metric_name(metric: &Metric) -> String {
+    let suffix = if metric.counter { "_total" } else { "" };
+    match metric.unit {
+        "dBm" => format!("{}_{}_dbm{}", METRIC_PREFIX, metric.name, suffix),
+        "dB" => format!("{}_{}_db{}", METRIC_PREFIX, metric.name, suffix),
+        unit => format!("{}_{}_{}{}", METRIC_PREFIX, metric.name, unit, suffix),
+    }
+}
+
+/// The exposition text for the last poll. `data` is `None` while the router
+/// cannot be read, which only `zitel_up` reports.
+pub fn render(data: Option<&Value>, polled_at: i64) -> String {
+    let mut out = String::new();
+    let up = if data.is_some() { 1 } else { 0 };
+    let _ = writeln!(
+        out,
+        "# HELP {}_up Whether the last poll of the router succeeded",
+        METRIC_PREFIX
+    );
+    let _ = writeln!(out, "# TYPE {}_up gauge", METRIC_PREFIX);
+    let _ = writeln!(out, "{}_up {}", METRIC_PREFIX, up);
+    let _ = writeln!(
+        out,
+        "# HELP {}_last_poll_timestamp_seconds When the router was last polled",
+        METRIC_PREFIX
+    );
+    let _ = writeln!(
+        out,
+        "# TYPE {}_last_poll_timestamp_seconds gauge",
+        METRIC_PREFIX
+    );
+    let _ = writeln!(
+        out,
+        "{}_last_poll_timestamp_seconds {}",
+        METRIC_PREFIX, polled_at
+    );
+
+    let Some(data) = data else {
+        return out;
+    };
+    for (metric, value) in metrics::sample(data) {
+        // a missing value is left out, not exported as 0
+        let Some(value) = value else { continue };
+        let name = metric_name(metric);
+        let kind = if metric.counter { "counter" } else { "gauge" };
+        let _ = writeln!(out, "# HELP {} {}", name, metric.help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        let _ = writeln!(out, "{} {}", name, value);
+    }
+    out
+}
+
+// ---------- health ----------
+// GET /health is for liveness probes: 200 while the last poll succeeded
+// and is recent enough, 503 otherwise, with when and what failed.
+/// What the polls found so far, which both endpoints answer from.
+#[derive(Default)]
+struct PollState {
+    metrics: String,
+    last_poll: Option<DateTime<Utc>>,
+    last_success: Option<DateTime<Utc>>,
+    /// The most recent failure, kept after later polls succeed.
+    last_error: Option<(DateTime<Utc>, String)>,
+}
+
+impl PollState {
+    fn record(&mut self, poll: Result<&Value, String>, at: DateTime<Utc>) {
+        self.metrics = render(poll.as_ref().ok().copied(), at.timestamp());
+        self.last_poll = Some(at);
+        match poll {
+            Ok(_) => self.last_success = Some(at),
+            Err(e) => self.last_error = Some((at, e)),
+        }
+    }
+
+    /// Whether the exporter is healthy at `now`, and the body saying why.
+    fn health(&self, now: DateTime<Utc>, max_age: Duration) -> (bool, Value) {
+        let age = self
+            .last_success
+            .map(|at| (now - at).num_seconds().max(0) as u64);
+        let healthy = self.last_poll.is_some()
+            && self.last_poll == self.last_success
+            && age.is_some_and(|age| age <= max_age.as_secs());
+        let body = json!({
+            "healthy": healthy,
+            "last_poll": self.last_poll.map(timestamp::iso),
+            "last_success": self.last_success.map(timestamp::iso),
+            "age_secs": age,
+            "max_age_secs": max_age.as_secs(),
+            "last_error": self.last_error.as_ref().map(|(at, e)| json!({
+                "time": timestamp::iso(*at),
+                "error": e,
+            })),
+        });
+        (healthy, body)
+    }
+}
+
+// Only the request line matters: GET /metrics gets the metrics, GET
+// /health the health, anything else a 404.
+async fn answer(
+    mut stream: TcpStream,
+    state: &Mutex<PollState>,
+    max_age: Duration,
+) -> std::io::Result<()> {
+    const METRICS_TYPE: &str = "text/plain; version=0.0.4";
+    let mut request = [0u8; 1024];
+    let read = tokio::time::timeout(REQUEST_TIMEOUT, stream.read(&mut request)).await??;
+    let request = String::from_utf8_lossy(&request[..read]);
+    let mut words = request.split_whitespace();
+    let (status, content_type, body) = {
+        let state = state.lock().unwrap_or_else(|e| e.into_inner());
+        match (words.next(), words.next()) {
+            (Some("GET"), Some("/metrics")) => ("200 OK", METRICS_TYPE, state.metrics.clone()),
+            (Some("GET"), Some("/health")) => {
+                let (healthy, body) = state.health(Utc::now(), max_age);
+                let status = match healthy {
+                    true => "200 OK",
+                    false => "503 Service Unavailable",
+                };
+                (status, "application/json", format!("{}\n", body))
+            }
+            _ => (
+                "404 Not Found",
+                METRICS_TYPE,
+                "Not found, try /metrics or /health\n".to_string(),
+            ),
+        }
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Serves until Ctrl-C. `/health` fails once the last successful poll is
+/// older than `max_age`.
+pub async fn run(
+    client: &RouterClient,
+    listen: SocketAddr,
+    interval: Duration,
+    max_age: Duration,
+) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(listen)
+        .await
+        .map_err(|e| format!("Cannot listen on {}: {}", listen, e))?;
+    eprintln!("Serving metrics on http://{}/metrics", listen);
+
+    // Requests are answered on their own tasks from the last poll's state:
+    // a poll of a router that is down takes minutes with the retries, and a
+    // scrape or health check must not wait for it.
+    let state = Arc::new(Mutex::new(PollState::default()));
+    let shared = Arc::clone(&state);
+    tokio::spawn(async move {
+        while let Ok((stream, peer)) = listener.accept().await {
+            let shared = Arc::clone(&shared);
+            tokio::spawn(async move {
+                if let Err(e) = answer(stream, &shared, max_age).await {
+                    eprintln!("Request from {} failed: {}", peer, e);
+                }
+            });
+        }
+    });
+
+    let polling = async {
+        let mut poll = tokio::time::interval(interval);
+        loop {
+            poll.tick().await;
+            if jitter::enabled() {
+                poll.reset_after(jitter::apply(interval));
+            }
+            let result = client.api_request("get_index_data").await;
+            if let Err(e) = &result {
+                eprintln!("Poll failed: {}", e);
+            }
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            state.record(result.as_ref().map_err(|e| e.to_string()), Utc::now());
+        }
+    };
+    tokio::select! {
+        _ = polling => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposition_has_types_and_skips_missing_values() {
+        let data = json!({ "RSRP": "-95", "recieve": "2048", "SYSUP": "60" });
+        let text = render(Some(&data), 1700000000);
+        assert!(text.contains("zitel_up 1\n"));
+        assert!(text.contains("# TYPE zitel_rsrp_dbm gauge\nzitel_rsrp_dbm -95\n"));
+        assert!(text.contains(
+            "# TYPE zitel_received_bytes_total counter\nzitel_received_bytes_total 2048\n"
+        ));
+        assert!(text.contains("zitel_uptime_seconds 60\n"));
+        assert!(!text.contains("zitel_sinr"));
+    }
+
+    #[test]
+    fn failed_polls_only_report_down() {
+        let text = render(None, 1700000000);
+        assert!(text.contains("zitel_up 0\n"));
+        assert!(!text.contains("rsrp"));
+    }
+
+    #[test]
+    fn health_needs_a_recent_successful_poll() {
+        let max_age = Duration::from_secs(45);
+        let start = Utc::now();
+        let at = |secs| start + chrono::Duration::seconds(secs);
+        let mut state = PollState::default();
+        assert!(!state.health(start, max_age).0);
+
+        state.record(Ok(&json!({ "RSRP": "-95" })), at(0));
+        let (healthy, body) = state.health(at(30), max_age);
+        assert!(healthy);
+        assert_eq!(
+            (body["age_secs"].as_u64(), &body["last_error"]),
+            (Some(30), &Value::Null)
+        );
+        assert!(!state.health(at(46), max_age).0);
+
+        state.record(Err("timed out".to_string()), at(15));
+        let (healthy, body) = state.health(at(20), max_age);
+        assert!(!healthy);
+        assert_eq!(body["last_error"]["error"], "timed out");
+        assert!(state.metrics.contains("zitel_up 0\n"));
+
+        // back up, the error stays in the body
+        state.record(Ok(&json!({})), at(30));
+        let (healthy, body) = state.health(at(31), max_age);
+        assert!(healthy && body["last_error"].is_object());
+    }
+}
@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::client::RouterClient;
+
+// --yes, set once at startup: changes go ahead without asking.
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+pub fn set_assume_yes(yes: bool) {
+    ASSUME_YES.store(yes, Ordering::Relaxed);
+}
+
+pub fn assume_yes() -> bool {
+    ASSUME_YES.load(Ordering::Relaxed)
+}
+
+/// Reads one line answering `label` from the terminal. Without a terminal
+/// there is nobody to ask, so the caller is told to pass the value instead.
+pub fn ask(label: &str) -> Result<String, Box<dyn Error>> {
+    if !io::stdin().is_terminal() {
+        return Err(format!(
+            "No terminal to ask for the {}, pass it as an option",
+            label.to_lowercase()
+        )
+        .into());
+    }
+    eprint!("{}: ", label);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Like `ask`, without echoing what is typed.
+pub fn ask_secret(label: &str) -> Result<String, Box<dyn Error>> {
+    if !io::stdin().is_terminal() {
+        return Err(format!(
+            "No terminal to ask for the {}, pass it as an option",
+            label.to_lowercase()
+        )
+        .into());
+    }
+    Ok(rpassword::prompt_password(format!("{}: ", label))?)
+}
+
+/// Asks a yes/no question on stderr; anything but y/yes, including an empty
+/// line, means no.
+pub fn confirm(question: &str) -> Result<bool, Box<dyn Error>> {
+    if !io::stdin().is_terminal() {
+        return Err("Not asking for confirmation without a terminal, pass --yes".into());
+    }
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Asks before a command changes the router, unless --yes was given or it
+/// is a dry run. A no ends the command before anything is sent.
+pub fn confirm_change(client: &RouterClient, question: &str) -> Result<(), Box<dyn Error>> {
+    if assume_yes() || client.dry_run() || confirm(question)? {
+        Ok(())
+    } else {
+        Err("Cancelled, nothing was changed".into())
+    }
+}
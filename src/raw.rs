@@ -0,0 +1,25 @@
+use std::error::Error;
+
+use crate::client::{is_read_command, RouterClient};
+use crate::prompt::confirm_change;
+
+// ---------- raw commands ----------
+// `zitel raw`: any API command, sent as typed, for firmware features zitel
+// does not know yet and for capturing what new commands answer. Nothing is
+// checked, so anything but a `get_` read is confirmed like a change.
+pub const WARNING: &str = "Raw commands are sent unchecked, the router may act on a mistyped one";
+
+/// Prints the router's answer as it came, whatever the output format.
+pub async fn run(client: &RouterClient, command: &str) -> Result<(), Box<dyn Error>> {
+    let command = command.trim();
+    if !is_read_command(command) {
+        eprintln!("Warning: {}", WARNING);
+        confirm_change(
+            client,
+            &format!("Send {:?}? It may change the router's settings", command),
+        )?;
+    }
+    let resp = client.raw_command(command).await?;
+    println!("{}", serde_json::to_string_pretty(&resp)?);
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use std::error::Error;
+use std::io::{self, IsTerminal};
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::cli::OutputFormat;
+use crate::client::{is_success, RouterClient};
+use crate::error::ZitelError;
+use crate::prompt::{ask, assume_yes, confirm, confirm_change};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const GO_DOWN_TIMEOUT: Duration = Duration::from_secs(60);
+const COME_BACK_TIMEOUT: Duration = Duration::from_secs(300);
+
+// The router often goes down before it has answered the reboot command.
+fn connection_dropped(e: &ZitelError) -> bool {
+    match e {
+        ZitelError::Decode(_) | ZitelError::Timeout(_) | ZitelError::ConnectionRefused(_) => true,
+        ZitelError::Http(e) => e.is_connect() || e.is_timeout() || e.is_request() || e.is_body(),
+        _ => false,
+    }
+}
+
+/// Sends the reboot command. A connection dropped mid-request counts as
+/// success, only an explicit refusal is an error.
+pub async fn send_reboot(client: &RouterClient) -> Result<(), ZitelError> {
+    match client.reboot().await {
+        Ok(resp) if is_success(&resp) => Ok(()),
+        Ok(resp) => Err(ZitelError::refused("Reboot refused", &resp)),
+        Err(e) if connection_dropped(&e) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Waits for the router to go away and then to answer again, returning the
+/// time since `since` (when the reboot was sent).
+pub async fn wait_until_back(
+    client: &RouterClient,
+    since: Instant,
+) -> Result<Duration, Box<dyn Error>> {
+    // otherwise the second loop would see the router before it went down
+    while client.reachable().await {
+        if since.elapsed() > GO_DOWN_TIMEOUT {
+            return Err(
+                "Router is still answering a minute later, it may not have rebooted".into(),
+            );
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    while !client.reachable().await {
+        if since.elapsed() > COME_BACK_TIMEOUT {
+            return Err("Router did not come back within 5 minutes".into());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    Ok(since.elapsed())
+}
+
+pub async fn run(client: &RouterClient, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    confirm_change(
+        client,
+        &format!("Reboot the router at {}?", client.base_url()),
+    )?;
+
+    let started = Instant::now();
+    send_reboot(client).await?;
+    if client.dry_run() {
+        match format {
+            OutputFormat::Json | OutputFormat::Raw => {
+                println!("{}", json!({ "success": true, "dry_run": true }))
+            }
+            OutputFormat::Text => println!("{}", client.outcome("Reboot sent")),
+        }
+        return Ok(());
+    }
+    eprintln!("Reboot sent, waiting for the router to come back...");
+    let elapsed = wait_until_back(client, started).await?;
+
+    match format {
+        OutputFormat::Json | OutputFormat::Raw => {
+            let result = json!({ "success": true, "elapsed_secs": elapsed.as_secs_f64() });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text => println!("Router back online after {:.1} s", elapsed.as_secs_f64()),
+    }
+    Ok(())
+}
+
+// ---------- factory reset ----------
+const RESET_WORD: &str = "RESET";
+
+// Two questions, the second one answered by typing RESET. --yes alone is not
+// enough to skip them, a script has to add --force as well.
+fn confirm_factory_reset(client: &RouterClient, force: bool) -> Result<(), Box<dyn Error>> {
+    if client.dry_run() {
+        return Ok(());
+    }
+    if assume_yes() {
+        return if force {
+            Ok(())
+        } else {
+            Err("A factory reset is never confirmed by --yes alone, add --force".into())
+        };
+    }
+    if !io::stdin().is_terminal() {
+        return Err("Not asking for confirmation without a terminal, pass --yes --force".into());
+    }
+    let question = format!(
+        "Factory reset the router at {}? All settings, including the password, are lost",
+        client.base_url()
+    );
+    if !confirm(&question)? {
+        return Err("Cancelled, nothing was changed".into());
+    }
+    let typed = ask(&format!("Type {} to confirm", RESET_WORD))?;
+    if typed.trim() != RESET_WORD {
+        return Err("Cancelled, nothing was changed".into());
+    }
+    Ok(())
+}
+
+/// Sends the factory reset. As with a reboot the router may drop the
+/// connection before answering, which counts as success.
+pub async fn send_factory_reset(client: &RouterClient) -> Result<(), ZitelError> {
+    match client.factory_reset().await {
+        Ok(resp) if is_success(&resp) => Ok(()),
+        Ok(resp) => Err(ZitelError::refused("Factory reset refused", &resp)),
+        Err(e) if connection_dropped(&e) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn run_factory_reset(
+    client: &RouterClient,
+    force: bool,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    confirm_factory_reset(client, force)?;
+    send_factory_reset(client).await?;
+
+    let summary = client
+        .outcome("Factory reset sent, the router restarts with its default settings and password");
+    match format {
+        OutputFormat::Json | OutputFormat::Raw => {
+            let result = json!({ "success": true, "dry_run": client.dry_run() });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text => println!("{}", summary),
+    }
+    Ok(())
+}
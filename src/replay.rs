@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+// ---------- recorded responses ----------
+// Answers API commands from saved JSON instead of a router, for demos and
+// for reproducing display bugs from a user's capture.
+const DEFAULT_COMMAND: &str = "get_index_data";
+
+/// The command a saved response answers, from its file name:
+/// `get_neighbour_cell.json`, optionally after a timestamp and `_`. Any other
+/// name is taken for a `get_index_data` capture.
+pub fn command_of(path: &Path) -> String {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let name = stem
+        .trim_start_matches(|c: char| c.is_ascii_digit() || matches!(c, '-' | '.' | 'T'))
+        .trim_start_matches('_');
+    if name.starts_with("get_") {
+        name.to_string()
+    } else {
+        DEFAULT_COMMAND.to_string()
+    }
+}
+
+fn read_response(path: &Path) -> Result<Value, Box<dyn Error>> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let value = serde_json::from_str(&text)
+        .map_err(|e| format!("{} is not a JSON response: {}", path.display(), e))?;
+    Ok(value)
+}
+
+/// The responses in `path`, by command name: one file, or every `.json`
+/// file in a directory, where later names (newer timestamps) win.
+pub fn load(path: &Path) -> Result<HashMap<String, Value>, Box<dyn Error>> {
+    let mut responses = HashMap::new();
+    if !path.is_dir() {
+        responses.insert(command_of(path), read_response(path)?);
+        return Ok(responses);
+    }
+    let mut files: Vec<_> = fs::read_dir(path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        return Err(format!("No .json responses in {}", path.display()).into());
+    }
+    for file in files {
+        responses.insert(command_of(&file), read_response(&file)?);
+    }
+    Ok(responses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names_say_which_command_they_answer() {
+        assert_eq!(command_of(Path::new("capture.json")), "get_index_data");
+        assert_eq!(
+            command_of(Path::new("dir/get_neighbour_cell.json")),
+            "get_neighbour_cell"
+        );
+        assert_eq!(
+            command_of(Path::new("20261014-130000.123_get_neighbour_cell.json")),
+            "get_neighbour_cell"
+        );
+    }
+}
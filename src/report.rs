@@ -0,0 +1,115 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::dashboard::Section;
+use crate::signal::{self, Quality};
+
+// ---------- HTML report ----------
+// One self-contained page (styles inline, no scripts or external assets) so
+// it can be attached to a support ticket as is.
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.4em; margin-bottom: 0.2em; }
+p.meta { color: #666; margin-top: 0; }
+table { border-collapse: collapse; margin: 1em 0; min-width: 28em; }
+th { background: #2b3a55; color: #fff; text-align: left; padding: 0.4em 0.8em; }
+td { border: 1px solid #ccc; padding: 0.3em 0.8em; }
+td.label { color: #555; width: 45%; }
+td.good { background: #d4f4d4; }
+td.fair { background: #fdf1c7; }
+td.poor { background: #f9d0d0; }
+";
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn quality_class(quality: Option<Quality>) -> &'static str {
+    match quality {
+        Some(Quality::Good) => " class=\"good\"",
+        Some(Quality::Fair) => " class=\"fair\"",
+        Some(Quality::Poor) => " class=\"poor\"",
+        None => "",
+    }
+}
+
+/// The dashboard sections as an HTML page, signal values coloured with the
+/// same thresholds as the terminal tables.
+pub fn render_html(sections: &[Section], router: &str, generated_at: &str) -> String {
+    let mut out =
+        String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!(
+        "<title>Router report {}</title>\n<style>\n{}</style>\n</head>\n<body>\n",
+        escape(generated_at),
+        STYLE
+    ));
+    out.push_str(&format!(
+        "<h1>Router report</h1>\n<p class=\"meta\">{} at {}</p>\n",
+        escape(router),
+        escape(generated_at)
+    ));
+    for section in sections {
+        out.push_str(&format!(
+            "<table>\n<tr><th colspan=\"2\">{}</th></tr>\n",
+            escape(section.title)
+        ));
+        for row in &section.rows {
+            let text = row.text.as_deref().unwrap_or("-");
+            out.push_str(&format!(
+                "<tr><td class=\"label\">{}</td><td{}>{}</td></tr>\n",
+                escape(row.label),
+                quality_class(signal::quality(row.name, text)),
+                escape(text)
+            ));
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+pub fn export_html(
+    path: &Path,
+    sections: &[Section],
+    router: &str,
+    generated_at: &str,
+) -> Result<(), Box<dyn Error>> {
+    fs::write(path, render_html(sections, router, generated_at))
+        .map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dashboard::sections;
+    use serde_json::json;
+
+    #[test]
+    fn reports_colour_signal_cells_and_escape_values() {
+        let data = json!({ "RSRP": "-95", "SINR": "25", "model": "<ZLT & co>" });
+        let html = render_html(
+            &sections(&data),
+            "http://192.168.0.1",
+            "2026-10-14 12:00:00",
+        );
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<td class=\"fair\">-95</td>"), "{}", html);
+        assert!(html.contains("<td class=\"good\">25</td>"));
+        assert!(html.contains("&lt;ZLT &amp; co&gt;"));
+        assert!(html.contains("2026-10-14 12:00:00"));
+        assert!(!html.contains("<script"));
+    }
+}
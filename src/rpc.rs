@@ -0,0 +1,331 @@
+use std::error::Error;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::client::RouterClient;
+use crate::error::{exit_code, ZitelError};
+use crate::port_forward::Protocol;
+
+// ---------- JSON-RPC control socket ----------
+// `zitel serve`: other local tools send JSON-RPC 2.0 requests, one per line,
+// and get the router's JSON answer back, all over the one session this
+// process logged in with. Connections are read concurrently, the router is
+// asked one request at a time.
+#[cfg(not(unix))]
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:9878";
+
+// JSON-RPC's own codes; failures talking to the router are -32000 minus
+// the exit code the same failure has on the command line
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+pub const METHODS: [&str; 8] = [
+    "dashboard",
+    "neighbors",
+    "dmz",
+    "set_dmz",
+    "bandlock",
+    "bandunlock",
+    "refresh_session",
+    "methods",
+];
+
+pub fn default_socket() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config").join("zitel")))?;
+    Some(dir.join("zitel.sock"))
+}
+
+/// What a request asks for, checked before anything is sent.
+#[derive(Debug, PartialEq)]
+pub enum Call {
+    Dashboard,
+    Neighbors,
+    Dmz,
+    SetDmz(String, Protocol),
+    DisableDmz,
+    BandLock(String),
+    BandUnlock,
+    RefreshSession,
+    Methods,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+pub fn parse_call(method: &str, params: &Value) -> Result<Call, String> {
+    let param = |name: &str| params.get(name).filter(|v| !v.is_null());
+    let text = |name: &str| {
+        param(name).map(|v| match v {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+    };
+    Ok(match method {
+        "dashboard" => Call::Dashboard,
+        "neighbors" => Call::Neighbors,
+        "dmz" => Call::Dmz,
+        "set_dmz" => match (text("ip"), param("enabled").and_then(Value::as_bool)) {
+            (_, Some(false)) => Call::DisableDmz,
+            (Some(ip), _) => {
+                let protocol = match text("protocol") {
+                    Some(name) => Protocol::from_str(&name, true).map_err(|_| {
+                        format!("Unknown protocol {:?}, use tcp, udp or tcpudp", name)
+                    })?,
+                    None => Protocol::Both,
+                };
+                Call::SetDmz(ip, protocol)
+            }
+            (None, _) => return Err("set_dmz needs {\"ip\": ...} or {\"enabled\": false}".into()),
+        },
+        "bandlock" => match text("earfcn") {
+            Some(earfcn) => Call::BandLock(earfcn),
+            None => return Err("bandlock needs {\"earfcn\": ...}".into()),
+        },
+        "bandunlock" => Call::BandUnlock,
+        "refresh_session" => Call::RefreshSession,
+        "methods" => Call::Methods,
+        _ => return Err(format!("Unknown method {:?}", method)),
+    })
+}
+
+async fn execute(client: &RouterClient, call: Call) -> Result<Value, ZitelError> {
+    match call {
+        Call::Dashboard => client.api_request("get_index_data").await,
+        Call::Neighbors => client.api_request("get_neighbour_cell").await,
+        Call::Dmz => client.dmz_status().await,
+        Call::SetDmz(ip, protocol) => client.set_dmz(&ip, protocol).await,
+        Call::DisableDmz => client.disable_dmz().await,
+        Call::BandLock(earfcn) => client.set_band_lock(&earfcn).await,
+        Call::BandUnlock => client.clear_band_lock().await,
+        Call::RefreshSession => {
+            client.authenticate().await?;
+            Ok(json!({ "status": "success", "base_url": client.base_url() }))
+        }
+        Call::Methods => Ok(json!(METHODS)),
+    }
+}
+
+async fn answer(client: &RouterClient, request: &Value) -> Result<Value, RpcError> {
+    let method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::new(INVALID_REQUEST, "Request has no method"))?;
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    let call = parse_call(method, &params).map_err(|message| {
+        let code = match METHODS.contains(&method) {
+            true => INVALID_PARAMS,
+            false => METHOD_NOT_FOUND,
+        };
+        RpcError::new(code, message)
+    })?;
+    log::debug!("rpc {}", method);
+    execute(client, call).await.map_err(|e| {
+        let code = match e {
+            ZitelError::Input(_) => INVALID_PARAMS,
+            _ => SERVER_ERROR - i64::from(exit_code(&e)),
+        };
+        RpcError::new(code, e.to_string())
+    })
+}
+
+/// The response line for one request line, `None` for a notification (a
+/// request without an id), which gets no answer.
+pub async fn handle_line(client: &RouterClient, line: &str) -> Option<Value> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = json!({ "code": PARSE_ERROR, "message": e.to_string() });
+            return Some(json!({ "jsonrpc": "2.0", "id": null, "error": error }));
+        }
+    };
+    let id = request.get("id").cloned();
+    let result = answer(client, &request).await;
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": e.code, "message": e.message },
+        }),
+    })
+}
+
+// ---------- connections ----------
+type Job = (String, oneshot::Sender<Option<Value>>);
+
+// Reads request lines and writes the answers, in order, until the peer
+// hangs up.
+async fn serve_connection<S>(stream: S, jobs: mpsc::UnboundedSender<Job>) -> std::io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (reply_tx, reply_rx) = oneshot::channel();
+        if jobs.send((line, reply_tx)).is_err() {
+            break;
+        }
+        if let Ok(Some(response)) = reply_rx.await {
+            writer
+                .write_all(format!("{}\n", response).as_bytes())
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+fn spawn_connection<S>(stream: S, jobs: &mpsc::UnboundedSender<Job>, peer: String)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let jobs = jobs.clone();
+    tokio::spawn(async move {
+        if let Err(e) = serve_connection(stream, jobs).await {
+            eprintln!("Connection from {} failed: {}", peer, e);
+        }
+    });
+}
+
+pub enum Endpoint {
+    #[cfg(unix)]
+    Socket(PathBuf),
+    Tcp(SocketAddr),
+}
+
+#[cfg(unix)]
+fn bind_socket(path: &Path) -> Result<tokio::net::UnixListener, Box<dyn Error>> {
+    use std::os::unix::fs::PermissionsExt;
+    if path.exists() {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(format!("{} is in use, is zitel serve running?", path.display()).into());
+        }
+        // left behind by a run that did not exit cleanly
+        std::fs::remove_file(path)?;
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)
+        .map_err(|e| format!("Cannot listen on {}: {}", path.display(), e))?;
+    // the session acts with the router password, so only its owner may use it
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+pub async fn run(client: &RouterClient, endpoint: Endpoint) -> Result<(), Box<dyn Error>> {
+    let (jobs_tx, mut jobs_rx) = mpsc::unbounded_channel::<Job>();
+    match &endpoint {
+        #[cfg(unix)]
+        Endpoint::Socket(path) => {
+            let listener = bind_socket(path)?;
+            eprintln!("Serving JSON-RPC on {}", path.display());
+            let jobs = jobs_tx.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    spawn_connection(stream, &jobs, "the socket".to_string());
+                }
+            });
+        }
+        Endpoint::Tcp(listen) => {
+            let listener = TcpListener::bind(listen)
+                .await
+                .map_err(|e| format!("Cannot listen on {}: {}", listen, e))?;
+            eprintln!("Serving JSON-RPC on {}", listen);
+            if !listen.ip().is_loopback() {
+                eprintln!(
+                    "Warning: anyone who can reach {} can change the router, nothing is asked",
+                    listen
+                );
+            }
+            let jobs = jobs_tx.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, peer)) = listener.accept().await {
+                    spawn_connection(stream, &jobs, peer.to_string());
+                }
+            });
+        }
+    }
+    drop(jobs_tx);
+
+    loop {
+        tokio::select! {
+            job = jobs_rx.recv() => {
+                let Some((line, reply)) = job else { break };
+                let _ = reply.send(handle_line(client, &line).await);
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    #[cfg(unix)]
+    if let Endpoint::Socket(path) = &endpoint {
+        let _ = std::fs::remove_file(path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params_are_checked_per_method() {
+        assert_eq!(parse_call("dashboard", &Value::Null), Ok(Call::Dashboard));
+        assert_eq!(
+            parse_call("set_dmz", &json!({ "ip": "192.168.0.50" })),
+            Ok(Call::SetDmz("192.168.0.50".to_string(), Protocol::Both))
+        );
+        assert_eq!(
+            parse_call(
+                "set_dmz",
+                &json!({ "ip": "192.168.0.50", "protocol": "UDP" })
+            ),
+            Ok(Call::SetDmz("192.168.0.50".to_string(), Protocol::Udp))
+        );
+        assert!(parse_call(
+            "set_dmz",
+            &json!({ "ip": "192.168.0.50", "protocol": "icmp" })
+        )
+        .is_err());
+        assert_eq!(
+            parse_call("set_dmz", &json!({ "enabled": false })),
+            Ok(Call::DisableDmz)
+        );
+        assert_eq!(
+            parse_call("bandlock", &json!({ "earfcn": 1850 })),
+            Ok(Call::BandLock("1850".to_string()))
+        );
+        assert!(parse_call("bandlock", &json!({})).is_err());
+        assert!(parse_call("reboot", &Value::Null).is_err());
+        assert!(METHODS
+            .iter()
+            .filter(|m| !m.starts_with("set_") && **m != "bandlock")
+            .all(|m| parse_call(m, &Value::Null).is_ok()));
+    }
+}
@@ -0,0 +1,376 @@
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::cli::OutputFormat;
+use crate::credentials::Credentials;
+
+// ---------- stored passwords ----------
+// What `zitel login` saves, so the password need not sit in the config file
+// or the shell history. It goes to the system keyring (the macOS Keychain,
+// the Windows Credential Manager or the Secret Service); only where there is
+// none is it written to the secrets file, readable by the owner only. The
+// file lists the keyring's entries too, as the keyring cannot be searched
+// for the routers and users it has. The password is never printed, not even
+// in JSON output.
+const SERVICE: &str = "zitel";
+
+#[derive(Default, Serialize, Deserialize)]
+struct Secrets {
+    passwords: Vec<StoredPassword>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredPassword {
+    base_url: String,
+    username: String,
+    /// `None` when it is in the keyring.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+}
+
+impl StoredPassword {
+    fn account(&self) -> String {
+        account(&self.base_url, &self.username)
+    }
+}
+
+fn account(base_url: &str, username: &str) -> String {
+    format!("{}@{}", username, base_url)
+}
+
+/// A password store keyed by account; the system's, or one a test keeps.
+pub trait Keyring {
+    fn get(&self, account: &str) -> keyring::Result<String>;
+    fn set(&self, account: &str, password: &str) -> keyring::Result<()>;
+    fn delete(&self, account: &str) -> keyring::Result<()>;
+}
+
+pub struct OsKeyring;
+
+impl Keyring for OsKeyring {
+    fn get(&self, account: &str) -> keyring::Result<String> {
+        keyring::Entry::new(SERVICE, account)?.get_password()
+    }
+
+    fn set(&self, account: &str, password: &str) -> keyring::Result<()> {
+        keyring::Entry::new(SERVICE, account)?.set_password(password)
+    }
+
+    fn delete(&self, account: &str) -> keyring::Result<()> {
+        keyring::Entry::new(SERVICE, account)?.delete_credential()
+    }
+}
+
+/// Where `zitel login` put the password.
+#[derive(Debug, PartialEq)]
+pub enum Storage {
+    Keyring,
+    File,
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config").join("zitel").join("secrets.json"))
+}
+
+fn read(path: &Path) -> io::Result<Secrets> {
+    match fs::read_to_string(path) {
+        Ok(text) => {
+            serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Secrets::default()),
+        Err(e) => Err(e),
+    }
+}
+
+fn write(path: &Path, secrets: &Secrets) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(path)?
+        .write_all(serde_json::to_string_pretty(secrets)?.as_bytes())
+}
+
+fn keyring_get(keyring: &dyn Keyring, account: &str) -> Option<String> {
+    match keyring.get(account) {
+        Ok(password) => Some(password),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            log::warn!("Cannot read {} from the keyring: {}", account, e);
+            None
+        }
+    }
+}
+
+/// The stored password of `username` on the router at `base_url`; any
+/// router will do when `base_url` is `None` (it is not known yet while
+/// discovering the gateway). A missing or unreadable file or keyring finds
+/// nothing.
+pub fn lookup(
+    keyring: &dyn Keyring,
+    path: &Path,
+    base_url: Option<&str>,
+    username: &str,
+) -> Option<String> {
+    let secrets = read(path).unwrap_or_else(|e| {
+        log::warn!(
+            "Cannot read stored passwords from {}: {}",
+            path.display(),
+            e
+        );
+        Secrets::default()
+    });
+    let found = secrets
+        .passwords
+        .into_iter()
+        .find(|p| p.username == username && base_url.is_none_or(|url| p.base_url == url));
+    match found {
+        Some(StoredPassword {
+            password: Some(password),
+            ..
+        }) => Some(password),
+        Some(stored) => keyring_get(keyring, &stored.account()),
+        // the file may be gone while the keyring still has it
+        None => base_url.and_then(|url| keyring_get(keyring, &account(url, username))),
+    }
+}
+
+/// Saves the password, replacing one stored before for the same router and
+/// user: in the keyring, or in the file when there is no keyring to use.
+pub fn store(
+    keyring: &dyn Keyring,
+    path: &Path,
+    base_url: &str,
+    credentials: &Credentials,
+) -> io::Result<Storage> {
+    let mut secrets = read(path)?;
+    secrets
+        .passwords
+        .retain(|p| !(p.base_url == base_url && p.username == credentials.username));
+    let account = account(base_url, &credentials.username);
+    let password = match keyring.set(&account, &credentials.password) {
+        Ok(()) => None,
+        Err(e) => {
+            log::warn!("No keyring to keep the password in ({}), using the file", e);
+            Some(credentials.password.clone())
+        }
+    };
+    let storage = match password {
+        Some(_) => Storage::File,
+        None => Storage::Keyring,
+    };
+    secrets.passwords.push(StoredPassword {
+        base_url: base_url.to_string(),
+        username: credentials.username.clone(),
+        password,
+    });
+    write(path, &secrets)?;
+    Ok(storage)
+}
+
+/// Removes every password stored for the router, from the keyring and the
+/// file, returning how many there were.
+pub fn forget(keyring: &dyn Keyring, path: &Path, base_url: &str) -> io::Result<usize> {
+    let mut secrets = read(path)?;
+    let before = secrets.passwords.len();
+    for stored in secrets.passwords.iter().filter(|p| p.base_url == base_url) {
+        if stored.password.is_none() {
+            match keyring.delete(&stored.account()) {
+                Ok(()) | Err(keyring::Error::NoEntry) => {}
+                Err(e) => log::warn!("Cannot remove {} from the keyring: {}", stored.account(), e),
+            }
+        }
+    }
+    secrets.passwords.retain(|p| p.base_url != base_url);
+    let removed = before - secrets.passwords.len();
+    if removed > 0 {
+        write(path, &secrets)?;
+    }
+    Ok(removed)
+}
+
+/// Stores the credentials the run just logged in with.
+pub fn run_login(
+    path: &Path,
+    base_url: &str,
+    credentials: &Credentials,
+    in_config: bool,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let storage = store(&OsKeyring, path, base_url, credentials)
+        .map_err(|e| format!("Cannot save the password to {}: {}", path.display(), e))?;
+    let place = match storage {
+        Storage::Keyring => "the system keyring".to_string(),
+        Storage::File => path.display().to_string(),
+    };
+    match format {
+        OutputFormat::Json | OutputFormat::Raw => println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({
+                "saved": true,
+                "base_url": base_url,
+                "username": credentials.username,
+                "keyring": storage == Storage::Keyring,
+                "path": (storage == Storage::File).then_some(path),
+            }))?
+        ),
+        OutputFormat::Text => {
+            println!(
+                "Password for {} on {} saved to {}",
+                credentials.username, base_url, place
+            );
+            if in_config {
+                println!("The config file still has a password, it can be removed now");
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn run_logout(path: &Path, base_url: &str, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let removed = forget(&OsKeyring, path, base_url)
+        .map_err(|e| format!("Cannot update {}: {}", path.display(), e))?;
+    match format {
+        OutputFormat::Json | OutputFormat::Raw => println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "removed": removed, "base_url": base_url }))?
+        ),
+        OutputFormat::Text if removed == 0 => println!("No password stored for {}", base_url),
+        OutputFormat::Text => println!("Stored password for {} removed", base_url),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    // `None` is a system without a keyring
+    struct TestKeyring(Option<RefCell<HashMap<String, String>>>);
+
+    impl TestKeyring {
+        fn entries(&self) -> &RefCell<HashMap<String, String>> {
+            self.0.as_ref().unwrap()
+        }
+
+        fn store(&self) -> keyring::Result<&RefCell<HashMap<String, String>>> {
+            self.0
+                .as_ref()
+                .ok_or_else(|| keyring::Error::NoStorageAccess("no keyring".into()))
+        }
+    }
+
+    impl Keyring for TestKeyring {
+        fn get(&self, account: &str) -> keyring::Result<String> {
+            let entries = self.store()?.borrow();
+            entries.get(account).cloned().ok_or(keyring::Error::NoEntry)
+        }
+
+        fn set(&self, account: &str, password: &str) -> keyring::Result<()> {
+            let mut entries = self.store()?.borrow_mut();
+            entries.insert(account.to_string(), password.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, account: &str) -> keyring::Result<()> {
+            let mut entries = self.store()?.borrow_mut();
+            entries
+                .remove(account)
+                .map(drop)
+                .ok_or(keyring::Error::NoEntry)
+        }
+    }
+
+    fn admin(password: &str) -> Credentials {
+        Credentials {
+            username: "admin".to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("zitel-{}-{}.json", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn passwords_go_to_the_keyring_and_not_the_file() {
+        let path = temp_path("keyring");
+        let keyring = TestKeyring(Some(RefCell::default()));
+        let stored = store(&keyring, &path, "http://192.168.0.1", &admin("old")).unwrap();
+        assert_eq!(stored, Storage::Keyring);
+        store(&keyring, &path, "http://192.168.0.1", &admin("new")).unwrap();
+        store(&keyring, &path, "http://10.0.0.1", &admin("other")).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("new"));
+        assert_eq!(
+            keyring.entries().borrow()["admin@http://192.168.0.1"],
+            "new"
+        );
+
+        assert_eq!(
+            lookup(&keyring, &path, Some("http://192.168.0.1"), "admin").as_deref(),
+            Some("new")
+        );
+        assert_eq!(
+            lookup(&keyring, &path, None, "admin").as_deref(),
+            Some("new")
+        );
+        assert_eq!(
+            lookup(&keyring, &path, Some("http://192.168.0.1"), "root"),
+            None
+        );
+
+        assert_eq!(forget(&keyring, &path, "http://192.168.0.1").unwrap(), 1);
+        assert_eq!(
+            lookup(&keyring, &path, Some("http://192.168.0.1"), "admin"),
+            None
+        );
+        assert_eq!(keyring.entries().borrow().len(), 1);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn without_a_keyring_passwords_are_kept_in_a_private_file() {
+        let path = temp_path("secrets");
+        let keyring = TestKeyring(None);
+        let stored = store(&keyring, &path, "http://192.168.0.1", &admin("old")).unwrap();
+        assert_eq!(stored, Storage::File);
+        store(&keyring, &path, "http://192.168.0.1", &admin("new")).unwrap();
+        store(&keyring, &path, "http://10.0.0.1", &admin("other")).unwrap();
+
+        assert_eq!(
+            lookup(&keyring, &path, Some("http://192.168.0.1"), "admin").as_deref(),
+            Some("new")
+        );
+        assert_eq!(
+            lookup(&keyring, &path, Some("http://192.168.0.1"), "root"),
+            None
+        );
+        assert!(lookup(&keyring, &path, None, "admin").is_some());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o077, 0);
+        }
+
+        assert_eq!(forget(&keyring, &path, "http://192.168.0.1").unwrap(), 1);
+        assert_eq!(
+            lookup(&keyring, &path, Some("http://192.168.0.1"), "admin"),
+            None
+        );
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,234 @@
+use serde_json::Value;
+
+use crate::charset;
+use crate::dashboard::find_field;
+use crate::fields::field;
+
+// ---------- signal quality ----------
+// Usual LTE quality bands: better than the first threshold is good, down to
+// the second one fair, below that poor (RSRP -79 good, -80..-100 fair).
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Quality {
+    Good,
+    Fair,
+    Poor,
+}
+
+// (good from, fair from) per metric, keyed by the dashboard field name
+const THRESHOLDS: &[(&str, f64, f64)] = &[
+    ("rsrp", -80.0, -100.0),
+    ("rsrq", -10.0, -15.0),
+    ("sinr", 13.0, 0.0),
+    ("rssi", -65.0, -85.0),
+];
+
+/// Leading number of a router value such as "-95" or "-95 dBm".
+pub fn leading_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let end = text
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || c == '.' || (i == 0 && (c == '-' || c == '+'))))
+        .map_or(text.len(), |(i, _)| i);
+    text[..end].parse().ok()
+}
+
+/// Quality of a signal metric, `None` for other fields or unparsable values.
+pub fn quality(name: &str, text: &str) -> Option<Quality> {
+    quality_of(name, leading_number(text)?)
+}
+
+pub fn quality_of(name: &str, value: f64) -> Option<Quality> {
+    let &(_, good, fair) = THRESHOLDS.iter().find(|(metric, _, _)| *metric == name)?;
+    Some(if value > good {
+        Quality::Good
+    } else if value >= fair {
+        Quality::Fair
+    } else {
+        Quality::Poor
+    })
+}
+
+// ---------- typed readings ----------
+/// The signal metrics of one get_index_data answer, parsed once. Missing,
+/// empty or non-numeric values are `None`; units such as "dBm" are ignored.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SignalMetrics {
+    pub rsrp: Option<i32>,
+    pub rsrq: Option<f32>,
+    pub sinr: Option<f32>,
+    pub rssi: Option<i32>,
+}
+
+// the whole-dBm metrics; the odd firmware sends "-95.0"
+fn whole(value: f64) -> Option<i32> {
+    value.is_finite().then(|| value.round() as i32)
+}
+
+impl SignalMetrics {
+    pub fn from_data(data: &Value) -> Self {
+        let number = |name: &str| {
+            let spec = find_field(name).map_or(name, |f| f.spec);
+            field(data, spec).as_deref().and_then(leading_number)
+        };
+        Self {
+            rsrp: number("rsrp").and_then(whole),
+            rsrq: number("rsrq").map(|v| v as f32),
+            sinr: number("sinr").map(|v| v as f32),
+            rssi: number("rssi").and_then(whole),
+        }
+    }
+
+    /// A metric by its dashboard field name.
+    pub fn get(&self, name: &str) -> Option<f64> {
+        match name {
+            "rsrp" => self.rsrp.map(f64::from),
+            "rsrq" => self.rsrq.map(f64::from),
+            "sinr" => self.sinr.map(f64::from),
+            "rssi" => self.rssi.map(f64::from),
+            _ => None,
+        }
+    }
+
+    pub fn quality(&self, name: &str) -> Option<Quality> {
+        quality_of(name, self.get(name)?)
+    }
+}
+
+// ---------- bar gauge ----------
+pub const BAR_WIDTH: usize = 8;
+
+// Normal reporting range per metric, for the bar gauge.
+const BAR_RANGES: &[(&str, f64, f64)] = &[
+    ("rsrp", -140.0, -44.0),
+    ("rsrq", -20.0, -3.0),
+    ("sinr", -20.0, 30.0),
+];
+
+pub fn bar_range(name: &str) -> Option<(f64, f64)> {
+    BAR_RANGES
+        .iter()
+        .find(|(metric, _, _)| *metric == name)
+        .map(|&(_, min, max)| (min, max))
+}
+
+/// A fixed-width bar showing where `text` falls between `min` and `max`,
+/// clamped to the ends, or "n/a" when `text` holds no number.
+pub fn signal_bar(text: &str, min: f64, max: f64) -> String {
+    bar_of(leading_number(text), min, max)
+}
+
+pub fn bar_of(value: Option<f64>, min: f64, max: f64) -> String {
+    let Some(value) = value else {
+        return "n/a".to_string();
+    };
+    let fraction = ((value - min) / (max - min)).clamp(0.0, 1.0);
+    let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+    let (full, empty) = charset::bar_parts();
+    let bar = std::iter::repeat_n(full, filled);
+    bar.chain(std::iter::repeat_n(empty, BAR_WIDTH - filled))
+        .collect()
+}
+
+// ---------- sparkline ----------
+/// One block per value, scaled between the lowest and highest value shown,
+/// so small changes stay visible while aiming an antenna.
+pub fn sparkline(values: &[f64]) -> String {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let levels = charset::spark_levels();
+    values
+        .iter()
+        .map(|&value| {
+            let fraction = if max > min {
+                (value - min) / (max - min)
+            } else {
+                0.5
+            };
+            let level = (fraction * (levels.len() - 1) as f64).round() as usize;
+            levels[level]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparkline_spans_the_window() {
+        assert_eq!(sparkline(&[-110.0, -100.0, -90.0]), "▁▅█");
+        assert_eq!(sparkline(&[-95.0, -95.0]), "▅▅");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn rsrp_bands_match_the_usual_thresholds() {
+        assert_eq!(quality("rsrp", "-79"), Some(Quality::Good));
+        assert_eq!(quality("rsrp", "-80"), Some(Quality::Fair));
+        assert_eq!(quality("rsrp", "-100"), Some(Quality::Fair));
+        assert_eq!(quality("rsrp", "-101"), Some(Quality::Poor));
+    }
+
+    #[test]
+    fn units_after_the_number_are_ignored() {
+        assert_eq!(quality("sinr", "21.5 dB"), Some(Quality::Good));
+        assert_eq!(quality("rssi", "-90dBm"), Some(Quality::Poor));
+    }
+
+    #[test]
+    fn bars_fill_with_the_value_and_clamp() {
+        assert_eq!(signal_bar("-140", -140.0, -44.0), "░░░░░░░░");
+        assert_eq!(signal_bar("-92", -140.0, -44.0), "████░░░░");
+        assert_eq!(signal_bar("-44", -140.0, -44.0), "████████");
+        assert_eq!(signal_bar("-30", -140.0, -44.0), "████████");
+        assert_eq!(signal_bar("-200", -140.0, -44.0), "░░░░░░░░");
+        assert_eq!(signal_bar("n/a", -140.0, -44.0), "n/a");
+    }
+
+    #[test]
+    fn readings_parse_with_units_and_without_values() {
+        let data = serde_json::json!({
+            "RSRP": " -95 dBm ",
+            "RSRQ": "-11.5dB",
+            "SINR": "",
+            "RSSI": "n/a",
+        });
+        let metrics = SignalMetrics::from_data(&data);
+        assert_eq!(
+            metrics,
+            SignalMetrics {
+                rsrp: Some(-95),
+                rsrq: Some(-11.5),
+                sinr: None,
+                rssi: None,
+            }
+        );
+        assert_eq!(metrics.quality("rsrp"), Some(Quality::Fair));
+        assert_eq!(metrics.quality("sinr"), None);
+
+        let nested = serde_json::json!({ "lte": { "rsrp": -71.6, "sinr": 18 } });
+        let metrics = SignalMetrics::from_data(&nested);
+        assert_eq!(metrics.rsrp, Some(-72));
+        assert_eq!(metrics.get("sinr"), Some(18.0));
+        assert_eq!(
+            SignalMetrics::from_data(&Value::Null),
+            SignalMetrics::default()
+        );
+    }
+
+    #[test]
+    fn numbers_need_digits_first() {
+        assert_eq!(leading_number("+5"), Some(5.0));
+        assert_eq!(leading_number("-"), None);
+        assert_eq!(leading_number("dBm -95"), None);
+        assert_eq!(leading_number("1.2.3"), None);
+    }
+
+    #[test]
+    fn other_fields_and_garbage_have_no_quality() {
+        assert_eq!(quality("band", "3"), None);
+        assert_eq!(quality("rsrp", "n/a"), None);
+        assert_eq!(quality("rsrp", ""), None);
+    }
+}
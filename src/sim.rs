@@ -0,0 +1,173 @@
+use std::error::Error;
+
+use serde_json::{json, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::{is_success, RouterClient};
+use crate::error::ZitelError;
+use crate::prompt::confirm_change;
+
+// Keys different firmware revisions use for the SIM state, in the SIM status
+// response as well as in get_index_data.
+const SIM_STATUS_KEYS: &[&str] = &["sim_status", "SIM", "simstatus"];
+const PIN_ATTEMPTS_KEYS: &[&str] = &["pin_attempts", "pinleft", "PINLEFT"];
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum SimStatus {
+    Ready,
+    PinRequired,
+    PukRequired,
+    Absent,
+    Unknown(String),
+}
+
+impl SimStatus {
+    pub fn label(&self) -> &str {
+        match self {
+            SimStatus::Ready => "ready",
+            SimStatus::PinRequired => "pin_required",
+            SimStatus::PukRequired => "puk_required",
+            SimStatus::Absent => "absent",
+            SimStatus::Unknown(raw) => raw,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            SimStatus::Ready => "SIM ready".to_string(),
+            SimStatus::PinRequired => "SIM locked, PIN required".to_string(),
+            SimStatus::PukRequired => "SIM blocked, PUK required".to_string(),
+            SimStatus::Absent => "No SIM card inserted".to_string(),
+            SimStatus::Unknown(raw) => format!("SIM status {}", raw),
+        }
+    }
+}
+
+/// Reads the SIM state out of a response, `None` when the firmware does not
+/// report one at all.
+pub fn parse_sim_status(data: &Value) -> Option<SimStatus> {
+    let raw = SIM_STATUS_KEYS.iter().find_map(|key| data[*key].as_str())?;
+    let normalized = raw.trim().to_lowercase();
+    let status = if normalized.contains("puk") {
+        SimStatus::PukRequired
+    } else if normalized.contains("pin") {
+        SimStatus::PinRequired
+    } else if normalized.contains("ready") {
+        SimStatus::Ready
+    } else if ["absent", "no sim", "nosim", "not inserted", "missing"]
+        .iter()
+        .any(|s| normalized.contains(s))
+    {
+        SimStatus::Absent
+    } else {
+        SimStatus::Unknown(raw.trim().to_string())
+    };
+    Some(status)
+}
+
+fn pin_attempts(data: &Value) -> Option<u32> {
+    PIN_ATTEMPTS_KEYS.iter().find_map(|key| match &data[*key] {
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Number(n) => n.as_u64().map(|n| n as u32),
+        _ => None,
+    })
+}
+
+pub fn validate_pin(pin: &str) -> Result<(), String> {
+    if (4..=8).contains(&pin.len()) && pin.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err("PIN must be 4 to 8 digits".to_string())
+    }
+}
+
+// ---------- commands ----------
+pub enum SimAction {
+    Status,
+    Unlock { pin: String },
+}
+
+fn status_text(status: Option<&SimStatus>, attempts: Option<u32>) -> String {
+    let mut out = match status {
+        Some(status) => format!("{}\n", status.description()),
+        None => "Router did not report a SIM status\n".to_string(),
+    };
+    if let Some(attempts) = attempts {
+        out.push_str(&format!("PIN attempts left: {}\n", attempts));
+    }
+    out
+}
+
+fn print_status(resp: &Value, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let status = parse_sim_status(resp);
+    let attempts = pin_attempts(resp);
+    match format {
+        OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(resp)?),
+        OutputFormat::Json => {
+            let normalized = json!({
+                "status": status.as_ref().map(|s| s.label()),
+                "pin_attempts": attempts,
+            });
+            println!("{}", serde_json::to_string_pretty(&normalized)?);
+        }
+        OutputFormat::Text => print!("{}", status_text(status.as_ref(), attempts)),
+    }
+    Ok(())
+}
+
+pub async fn run(
+    client: &RouterClient,
+    action: SimAction,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let pin = match action {
+        SimAction::Status => {
+            let resp = client.sim_status().await?;
+            return print_status(&resp, format);
+        }
+        SimAction::Unlock { pin } => pin,
+    };
+    validate_pin(&pin)?;
+
+    let before = client.sim_status().await?;
+    match parse_sim_status(&before) {
+        Some(SimStatus::Ready) => {
+            eprintln!("SIM is already unlocked, not sending the PIN");
+            return print_status(&before, format);
+        }
+        Some(SimStatus::PukRequired) => {
+            return Err("SIM is blocked and needs a PUK, a PIN will not unlock it".into());
+        }
+        Some(SimStatus::Absent) => return Err("No SIM card inserted".into()),
+        _ => {}
+    }
+    match pin_attempts(&before) {
+        Some(attempts) => eprintln!(
+            "Warning: {} PIN attempt(s) left, a wrong PIN brings the SIM closer to needing a PUK",
+            attempts
+        ),
+        None => eprintln!("Warning: repeated wrong PINs will block the SIM and require a PUK"),
+    }
+
+    confirm_change(client, "Send the PIN to the SIM?")?;
+    let resp = client.unlock_sim(&pin).await?;
+    if !is_success(&resp) {
+        return Err(ZitelError::refused("SIM unlock failed", &resp).into());
+    }
+    if client.dry_run() {
+        match format {
+            OutputFormat::Json | OutputFormat::Raw => {
+                println!("{}", json!({ "success": true, "dry_run": true }))
+            }
+            OutputFormat::Text => println!("{}", client.outcome("SIM unlocked")),
+        }
+        return Ok(());
+    }
+
+    let after = client.sim_status().await?;
+    print_status(&after, format)?;
+    match parse_sim_status(&after) {
+        Some(SimStatus::Ready) | None => Ok(()),
+        Some(other) => Err(format!("SIM still not ready: {}", other.description()).into()),
+    }
+}
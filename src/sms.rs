@@ -0,0 +1,382 @@
+use std::error::Error;
+
+use serde_json::{json, Value};
+
+use crate::capabilities;
+use crate::cli::OutputFormat;
+use crate::client::{failure_reason, is_success, RouterClient};
+use crate::dashboard::render_columns;
+use crate::error::ZitelError;
+use crate::fields::{field, list_records};
+use crate::prompt::{ask, confirm_change};
+use crate::terminal;
+
+// ---------- inbox ----------
+const LIST_KEYS: &[&str] = &["messages", "sms", "sms_list", "list"];
+const MAX_MESSAGES: usize = 500;
+// Column the message body is wrapped at in the text table, less on narrow
+// terminals. Sender and time take about SENDER_TIME_WIDTH including borders.
+const BODY_WIDTH: usize = 48;
+const MIN_BODY_WIDTH: usize = 16;
+const SENDER_TIME_WIDTH: usize = 45;
+
+const SENDER_SPEC: &str = "sender|from|phone|number";
+const TIME_SPEC: &str = "date|time|timestamp|received";
+const BODY_SPEC: &str = "content|text|body|message";
+
+pub struct Sms {
+    pub sender: Option<String>,
+    pub time: Option<String>,
+    pub body: String,
+}
+
+impl Sms {
+    fn from_record(record: &Value) -> Self {
+        let value = |spec| field(record, spec).map(String::from);
+        Self {
+            sender: value(SENDER_SPEC),
+            time: value(TIME_SPEC),
+            body: value(BODY_SPEC).unwrap_or_default(),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({ "sender": self.sender, "time": self.time, "body": self.body })
+    }
+}
+
+pub fn parse_inbox(data: &Value) -> Vec<Sms> {
+    list_records(data, LIST_KEYS, MAX_MESSAGES)
+        .iter()
+        .map(Sms::from_record)
+        .collect()
+}
+
+/// The inbox, or an error saying so when the firmware has no SMS support.
+pub async fn fetch_inbox(client: &RouterClient) -> Result<Vec<Sms>, Box<dyn Error>> {
+    let result = client.sms_inbox().await;
+    if capabilities::unsupported(&result) {
+        return Err(ZitelError::RouterStatus(
+            "This router firmware does not expose SMS".to_string(),
+        )
+        .into());
+    }
+    Ok(parse_inbox(&result?))
+}
+
+/// Greedy word wrap; words longer than `width` are split.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        while word.len() > width {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            lines.push(word.drain(..width).collect());
+        }
+        let word: String = word.into_iter().collect();
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+// ---------- pages ----------
+pub struct InboxPage<'a> {
+    pub messages: &'a [Sms],
+    /// 1 based.
+    pub page: usize,
+    pub pages: usize,
+}
+
+/// Page `page` (1 based) of `per_page` messages; past the end is an error
+/// that names the last page.
+pub fn page(messages: &[Sms], page: usize, per_page: usize) -> Result<InboxPage<'_>, String> {
+    let pages = messages.len().div_ceil(per_page).max(1);
+    if page == 0 || page > pages {
+        return Err(format!(
+            "Page {} does not exist, the inbox has {}",
+            page, pages
+        ));
+    }
+    let start = (page - 1) * per_page;
+    let end = (start + per_page).min(messages.len());
+    Ok(InboxPage {
+        messages: &messages[start..end],
+        page,
+        pages,
+    })
+}
+
+fn table_rows(messages: &[Sms], body_width: usize) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    for sms in messages {
+        let dash = || "-".to_string();
+        for (i, line) in wrap(&sms.body, body_width).into_iter().enumerate() {
+            rows.push(match i {
+                0 => vec![
+                    sms.sender.clone().unwrap_or_else(dash),
+                    sms.time.clone().unwrap_or_else(dash),
+                    line,
+                ],
+                _ => vec![String::new(), String::new(), line],
+            });
+        }
+    }
+    rows
+}
+
+// ---------- one-shot command ----------
+pub async fn run_inbox(
+    client: &RouterClient,
+    page_number: usize,
+    per_page: usize,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    if format == OutputFormat::Raw {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&client.sms_inbox().await?)?
+        );
+        return Ok(());
+    }
+
+    let messages = fetch_inbox(client).await?;
+    let shown = page(&messages, page_number, per_page)?;
+    match format {
+        OutputFormat::Json | OutputFormat::Raw => {
+            let list: Vec<Value> = shown.messages.iter().map(Sms::to_json).collect();
+            let result = json!({
+                "total": messages.len(),
+                "page": shown.page,
+                "pages": shown.pages,
+                "messages": list,
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text if messages.is_empty() => println!("No messages"),
+        OutputFormat::Text => {
+            let title = format!(
+                "SMS Inbox - page {} of {} ({} messages)",
+                shown.page,
+                shown.pages,
+                messages.len()
+            );
+            let width = terminal::width();
+            let body_width = width.map_or(BODY_WIDTH, |w| {
+                w.saturating_sub(SENDER_TIME_WIDTH)
+                    .clamp(MIN_BODY_WIDTH, BODY_WIDTH)
+            });
+            let rows = table_rows(shown.messages, body_width);
+            print!(
+                "{}",
+                render_columns(&title, &["From", "Time", "Message"], &rows, width)
+            );
+        }
+    }
+    Ok(())
+}
+
+// ---------- sending ----------
+// GSM 03.38 default alphabet; the extension characters take two septets.
+const GSM_BASIC: &str = "@£$¥èéùìòÇ\nØø\rÅåΔ_ΦΓΛΩΠΨΣΘΞÆæßÉ !\"#¤%&'()*+,-./0123456789:;<=>?\
+                         ¡ABCDEFGHIJKLMNOPQRSTUVWXYZÄÖÑÜ§¿abcdefghijklmnopqrstuvwxyzäöñüà";
+const GSM_EXTENDED: &str = "^{}\\[~]|€\u{c}";
+
+/// Strips spaces and dashes and checks for an optional `+` followed by
+/// 3 to 15 digits (short codes up to full E.164 numbers).
+pub fn validate_number(input: &str) -> Result<String, String> {
+    let number: String = input
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '(' | ')'))
+        .collect();
+    let digits = number.strip_prefix('+').unwrap_or(&number);
+    if (3..=15).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_digit()) {
+        Ok(number)
+    } else {
+        Err(format!(
+            "{:?} is not a phone number, expected an optional + and 3 to 15 digits",
+            input
+        ))
+    }
+}
+
+pub struct Encoding {
+    pub chars: usize,
+    pub parts: usize,
+    /// Needs UCS-2, which allows 70 instead of 160 characters per message.
+    pub unicode: bool,
+}
+
+/// How the text will be sent: GSM 7-bit when every character allows it,
+/// otherwise UCS-2, and in how many concatenated parts.
+pub fn encoding(text: &str) -> Encoding {
+    let chars = text.chars().count();
+    let septets: Option<usize> = text
+        .chars()
+        .map(|c| match c {
+            c if GSM_BASIC.contains(c) => Some(1),
+            c if GSM_EXTENDED.contains(c) => Some(2),
+            _ => None,
+        })
+        .sum();
+    let (length, single, part) = match septets {
+        Some(septets) => (septets, 160, 153),
+        None => (chars, 70, 67),
+    };
+    let parts = if length <= single {
+        1
+    } else {
+        length.div_ceil(part)
+    };
+    Encoding {
+        chars,
+        parts,
+        unicode: septets.is_none(),
+    }
+}
+
+pub async fn run_send(
+    client: &RouterClient,
+    to: Option<String>,
+    message: Option<String>,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let to = match to {
+        Some(to) => to,
+        None => validate_number(&ask("Destination number")?)?,
+    };
+    let message = match message {
+        Some(message) => message,
+        None => ask("Message")?,
+    };
+    if message.trim().is_empty() {
+        return Err("Not sending an empty message".into());
+    }
+
+    let encoding = encoding(&message);
+    if encoding.parts > 1 {
+        eprintln!(
+            "Warning: {} characters{} will go out as {} messages and may be billed as such",
+            encoding.chars,
+            if encoding.unicode {
+                " of Unicode text"
+            } else {
+                ""
+            },
+            encoding.parts
+        );
+    }
+
+    confirm_change(client, &format!("Send this message to {}?", to))?;
+    let resp = client.send_sms(&to, &message).await?;
+    let done = is_success(&resp);
+    let summary = client.outcome(&format!(
+        "SMS sent to {} ({} characters, {} part{})",
+        to,
+        encoding.chars,
+        encoding.parts,
+        if encoding.parts == 1 { "" } else { "s" }
+    ));
+
+    match format {
+        OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&resp)?),
+        OutputFormat::Json => {
+            let result = json!({
+                "success": done,
+                "dry_run": client.dry_run(),
+                "to": to,
+                "length": encoding.chars,
+                "parts": encoding.parts,
+                "unicode": encoding.unicode,
+                "message": if done { Some(summary.as_str()) } else { failure_reason(&resp) },
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text if done => println!("{}", summary),
+        OutputFormat::Text => {}
+    }
+
+    if done {
+        Ok(())
+    } else {
+        Err(ZitelError::refused("Sending the SMS failed", &resp).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_numbered_messages() {
+        let data = json!({
+            "lenghtt": "2",
+            "phone1": "+4917012345", "date1": "24/05/01 10:00", "content1": "Balance: 5 EUR",
+            "phone2": "Carrier", "content2": "Welcome",
+        });
+        let inbox = parse_inbox(&data);
+        assert_eq!(inbox.len(), 2);
+        assert_eq!(inbox[0].sender.as_deref(), Some("+4917012345"));
+        assert_eq!(inbox[0].body, "Balance: 5 EUR");
+        assert_eq!(inbox[1].time, None);
+    }
+
+    #[test]
+    fn wraps_on_words_and_splits_long_ones() {
+        assert_eq!(wrap("one two three", 7), vec!["one two", "three"]);
+        assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("", 4), vec![""]);
+    }
+
+    #[test]
+    fn pages_cover_the_inbox() {
+        let inbox: Vec<Sms> = (0..5)
+            .map(|i| Sms {
+                sender: None,
+                time: None,
+                body: i.to_string(),
+            })
+            .collect();
+        let last = page(&inbox, 3, 2).unwrap();
+        assert_eq!((last.pages, last.messages.len()), (3, 1));
+        assert_eq!(last.messages[0].body, "4");
+        assert!(page(&inbox, 4, 2).is_err());
+        assert!(page(&inbox, 0, 2).is_err());
+        assert_eq!(page(&[], 1, 2).unwrap().pages, 1);
+    }
+
+    #[test]
+    fn numbers_allow_plus_and_separators() {
+        assert_eq!(
+            validate_number("+49 170-123 4567").unwrap(),
+            "+491701234567"
+        );
+        assert_eq!(validate_number("1234").unwrap(), "1234");
+        assert!(validate_number("12").is_err());
+        assert!(validate_number("+1234567890123456").is_err());
+        assert!(validate_number("call me").is_err());
+    }
+
+    #[test]
+    fn long_and_unicode_texts_are_split() {
+        let short = encoding("Balance?");
+        assert_eq!((short.chars, short.parts, short.unicode), (8, 1, false));
+        assert_eq!(encoding(&"a".repeat(160)).parts, 1);
+        assert_eq!(encoding(&"a".repeat(161)).parts, 2);
+        // each € takes two septets
+        assert_eq!(encoding(&"€".repeat(81)).parts, 2);
+        let emoji = encoding(&"😀".repeat(71));
+        assert_eq!((emoji.parts, emoji.unicode), (2, true));
+    }
+}
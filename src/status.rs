@@ -0,0 +1,206 @@
+use std::error::Error;
+
+use serde_json::{Map, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::RouterClient;
+use crate::dashboard::{fetch_index_data, find_field, seconds};
+use crate::fields::field;
+use crate::logging::redacted;
+use crate::network_mode::Technology;
+use crate::operator;
+use crate::usage::Usage;
+
+// ---------- status line ----------
+// One short line for a tmux status bar or a polybar widget. The template
+// names values in braces: any dashboard field by its JSON name, plus the
+// compact forms below.
+pub const DEFAULT_TEMPLATE: &str = "{net} B{band} RSRP={rsrp} SINR={sinr} up={uptime} rx={rx}";
+
+const DERIVED: &[&str] = &[
+    "net",
+    "operator",
+    "uptime",
+    "wan_uptime",
+    "rx",
+    "tx",
+    "total",
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Piece {
+    Text(String),
+    Value(String),
+}
+
+/// Splits the template into text and `{name}` values; `{{` and `}}` are
+/// literal braces. Unknown names are refused up front.
+pub fn parse_template(template: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(format!("Unclosed {{{} in the status template", name)),
+                    }
+                }
+                if !DERIVED.contains(&name.as_str()) && find_field(&name).is_none() {
+                    return Err(format!("Unknown status value {{{}}}", name));
+                }
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Value(name));
+            }
+            '}' => return Err("Unmatched } in the status template, write }} for a brace".into()),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+/// "4G", "5G" or "5G NSA" from the connection type (`LTE`, `NR5G-SA`, ...).
+fn generation(connection_type: &str) -> String {
+    match Technology::parse(connection_type) {
+        Some(technology) => technology.generation().to_string(),
+        None => connection_type.to_string(),
+    }
+}
+
+/// The two largest units without spaces: "3d4h", "4h12m", "5m3s".
+fn compact_duration(secs: u64) -> String {
+    let units = [(86400, "d"), (3600, "h"), (60, "m"), (1, "s")];
+    let Some(first) = units.iter().position(|(size, _)| secs >= *size) else {
+        return "0s".to_string();
+    };
+    units[first..]
+        .iter()
+        .take(2)
+        .scan(secs, |left, (size, unit)| {
+            let n = *left / size;
+            *left %= size;
+            Some(format!("{}{}", n, unit))
+        })
+        .collect()
+}
+
+/// One decimal, binary units as the dashboard uses: "12.3GB".
+fn compact_bytes(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < units.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{}B", bytes),
+        _ => format!("{:.1}{}", value, units[unit]),
+    }
+}
+
+fn value(data: &Value, name: &str) -> Option<String> {
+    let usage = || Usage::read(data);
+    let uptime = |name| find_field(name).and_then(|f| seconds(data, f.spec));
+    match name {
+        "net" => value(data, "type").map(|t| generation(&t)),
+        "operator" => operator::describe(data),
+        "uptime" => uptime("uptime_s").map(compact_duration),
+        "wan_uptime" => uptime("wan_uptime_s").map(compact_duration),
+        "rx" => usage().received.map(compact_bytes),
+        "tx" => usage().sent.map(compact_bytes),
+        "total" => usage().total().map(compact_bytes),
+        _ => find_field(name).and_then(|f| field(data, f.spec).map(String::from)),
+    }
+}
+
+/// The line for `data`; values the router left out show as `-`.
+pub fn render(pieces: &[Piece], data: &Value) -> String {
+    pieces
+        .iter()
+        .map(|piece| match piece {
+            Piece::Text(text) => text.clone(),
+            Piece::Value(name) => value(data, name).unwrap_or_else(|| "-".to_string()),
+        })
+        .collect()
+}
+
+pub async fn run(
+    client: &RouterClient,
+    template: &str,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let pieces = parse_template(template)?;
+    let response = fetch_index_data(client).await?;
+    // a template can name any field, --redact must cover them all
+    let data = redacted(&response, false);
+    match format {
+        OutputFormat::Raw => println!("{}", data),
+        OutputFormat::Json => {
+            let values: Map<String, Value> = pieces
+                .iter()
+                .filter_map(|piece| match piece {
+                    Piece::Value(name) => Some((name.clone(), value(&data, name).into())),
+                    Piece::Text(_) => None,
+                })
+                .collect();
+            println!("{}", Value::Object(values));
+        }
+        OutputFormat::Text => println!("{}", render(&pieces, &data)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn the_default_line_is_compact() {
+        let data = json!({
+            "TYPE": "LTE", "BAND": "43", "RSRP": "-92", "SINR": "12",
+            "SYSUP": "273600", "recieve": "13207024435",
+        });
+        let pieces = parse_template(DEFAULT_TEMPLATE).unwrap();
+        assert_eq!(
+            render(&pieces, &data),
+            "4G B43 RSRP=-92 SINR=12 up=3d4h rx=12.3GB"
+        );
+        assert_eq!(render(&pieces, &json!({})), "- B- RSRP=- SINR=- up=- rx=-");
+    }
+
+    #[test]
+    fn templates_are_checked_before_any_request() {
+        assert_eq!(
+            parse_template("{{{rsrq}}}").unwrap(),
+            [
+                Piece::Text("{".to_string()),
+                Piece::Value("rsrq".to_string()),
+                Piece::Text("}".to_string())
+            ]
+        );
+        assert!(parse_template("{signal}").is_err());
+        assert!(parse_template("up}").is_err());
+        assert!(parse_template("{rsrp").is_err());
+        assert_eq!(compact_duration(59), "59s");
+        assert_eq!(compact_duration(3720), "1h2m");
+    }
+}
@@ -0,0 +1,230 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::cursor::{MoveTo, Show};
+use crossterm::event::{
+    self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    Event, KeyEventKind,
+};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen,
+};
+
+// ---------- terminal capabilities ----------
+const PASTE_START: &str = "\x1b[200~";
+const PASTE_END: &str = "\x1b[201~";
+
+/// Old conhost on Windows does not understand bracketed paste and echoes the
+/// escape sequences back as input. Windows Terminal sets WT_SESSION.
+pub fn bracketed_paste_supported() -> bool {
+    if cfg!(windows) {
+        std::env::var_os("WT_SESSION").is_some()
+    } else {
+        true
+    }
+}
+
+/// Columns of the terminal on stdout, `None` when output is piped or the
+/// size cannot be read.
+pub fn width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    crossterm::terminal::size()
+        .ok()
+        .map(|(columns, _)| columns as usize)
+        .filter(|&columns| columns > 0)
+}
+
+/// Rows of the terminal on stdout, on the same terms as `width`.
+pub fn height() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    crossterm::terminal::size()
+        .ok()
+        .map(|(_, rows)| rows as usize)
+        .filter(|&rows| rows > 0)
+}
+
+// ---------- interactive view modes ----------
+/// Raw mode, the alternate screen, mouse capture and (when supported)
+/// bracketed paste for as long as the guard lives. Dropping it restores the
+/// terminal, so an error returned half way, a panic or Ctrl-C never leaves
+/// the shell pasting escape codes.
+pub struct TuiGuard<W: Write> {
+    out: W,
+    bracketed_paste: bool,
+}
+
+impl<W: Write> TuiGuard<W> {
+    pub fn enter(out: W, bracketed_paste: bool) -> io::Result<Self> {
+        // built first, so a mode that fails to switch on undoes the others
+        let mut guard = Self::new(out, bracketed_paste);
+        let hook = std::panic::take_hook();
+        // the panic message would otherwise go to the alternate screen
+        std::panic::set_hook(Box::new(move |info| {
+            let _ = restore(&mut io::stdout(), bracketed_paste);
+            hook(info);
+        }));
+        enable_raw_mode()?;
+        execute!(guard.out, EnterAlternateScreen, EnableMouseCapture)?;
+        if bracketed_paste {
+            execute!(guard.out, EnableBracketedPaste)?;
+        }
+        Ok(guard)
+    }
+
+    fn new(out: W, bracketed_paste: bool) -> Self {
+        Self {
+            out,
+            bracketed_paste,
+        }
+    }
+}
+
+impl<W: Write> Drop for TuiGuard<W> {
+    fn drop(&mut self) {
+        let _ = restore(&mut self.out, self.bracketed_paste);
+    }
+}
+
+fn restore(out: &mut impl Write, bracketed_paste: bool) -> io::Result<()> {
+    // every step is tried even if one fails
+    let raw = disable_raw_mode();
+    if bracketed_paste {
+        let _ = execute!(out, DisableBracketedPaste);
+    }
+    execute!(out, LeaveAlternateScreen, DisableMouseCapture, Show)?;
+    raw
+}
+
+// ---------- live printed views ----------
+/// A printed view redrawn in place: only lines that changed since the last
+/// frame are rewritten, so refreshes do not flicker. A resized terminal
+/// gets a full clear, the old lines may have wrapped.
+#[derive(Default)]
+pub struct Screen {
+    previous: Vec<String>,
+    size: Option<(u16, u16)>,
+    drawn: bool,
+}
+
+impl Screen {
+    /// Shows `text` from the top left of the screen.
+    pub fn draw(&mut self, out: &mut impl Write, text: &str) -> io::Result<()> {
+        self.draw_sized(out, text, crossterm::terminal::size().ok())
+    }
+
+    fn draw_sized(
+        &mut self,
+        out: &mut impl Write,
+        text: &str,
+        size: Option<(u16, u16)>,
+    ) -> io::Result<()> {
+        let lines: Vec<String> = text.lines().map(String::from).collect();
+        if !self.drawn || size != self.size {
+            execute!(out, MoveTo(0, 0), Clear(ClearType::All))?;
+            self.previous.clear();
+        }
+        for (row, line) in lines.iter().enumerate() {
+            if self.previous.get(row) != Some(line) {
+                execute!(out, MoveTo(0, row as u16))?;
+                write!(out, "{}", line)?;
+                execute!(out, Clear(ClearType::UntilNewLine))?;
+            }
+        }
+        if self.previous.len() > lines.len() {
+            execute!(
+                out,
+                MoveTo(0, lines.len() as u16),
+                Clear(ClearType::FromCursorDown)
+            )?;
+        }
+        self.previous = lines;
+        self.size = size;
+        self.drawn = true;
+        out.flush()
+    }
+}
+
+/// Waits up to `timeout` for a key press, in raw mode.
+pub fn key_pressed_within(timeout: Duration) -> io::Result<bool> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if !event::poll(left)? {
+            return Ok(false);
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+/// Removes bracketed-paste markers, including the ones left behind when the
+/// terminal only half understood the mode and printed `[200~` without ESC.
+pub fn strip_paste_markers(input: &str) -> String {
+    input
+        .replace(PASTE_START, "")
+        .replace(PASTE_END, "")
+        .replace("[200~", "")
+        .replace("[201~", "")
+}
+
+/// A pasted page number ("2", " 3\n", ...) as a zero based page index.
+pub fn menu_choice(input: &str, page_count: usize) -> Option<usize> {
+    let choice = strip_paste_markers(input).trim().parse::<usize>().ok()?;
+    choice.checked_sub(1).filter(|i| *i < page_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screens_rewrite_only_changed_lines() {
+        let mut screen = Screen::default();
+        let mut first = Vec::new();
+        screen
+            .draw_sized(&mut first, "Band 3\nRSRP -95\nscan 1", Some((80, 24)))
+            .unwrap();
+        let first = String::from_utf8(first).unwrap();
+        assert!(first.contains("\x1b[2J") && first.contains("Band 3"));
+
+        let mut second = Vec::new();
+        screen
+            .draw_sized(&mut second, "Band 3\nRSRP -91", Some((80, 24)))
+            .unwrap();
+        let second = String::from_utf8(second).unwrap();
+        assert!(!second.contains("\x1b[2J") && !second.contains("Band 3"));
+        assert!(second.contains("RSRP -91"));
+        // the dropped third line is cleared
+        assert!(second.contains("\x1b[J"), "{:?}", second);
+
+        let mut resized = Vec::new();
+        screen
+            .draw_sized(&mut resized, "Band 3\nRSRP -91", Some((100, 30)))
+            .unwrap();
+        assert!(String::from_utf8(resized).unwrap().contains("\x1b[2J"));
+    }
+
+    fn draw_and_fail(out: &mut Vec<u8>) -> io::Result<()> {
+        let _guard = TuiGuard::new(out, true);
+        let drawn: io::Result<()> = Err(io::Error::other("draw failed"));
+        drawn?;
+        Ok(())
+    }
+
+    #[test]
+    fn early_exit_still_disables_bracketed_paste() {
+        let mut out = Vec::new();
+        assert!(draw_and_fail(&mut out).is_err());
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("\x1b[?2004l"), "{:?}", out);
+        assert!(out.contains("\x1b[?1049l"), "{:?}", out);
+    }
+}
@@ -0,0 +1,143 @@
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::RouterClient;
+use crate::dashboard::{format_rate, RECEIVED_KEY, SENT_KEY};
+use crate::fields::aliased_parse;
+
+// ---------- throughput from byte counters ----------
+pub const MAX_WINDOW_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rate {
+    BitsPerSecond(f64),
+    /// The counter went backwards: the router rebooted or reset it.
+    Reset,
+}
+
+impl Rate {
+    pub fn mbps(self) -> Option<f64> {
+        match self {
+            Rate::BitsPerSecond(bps) => Some(bps / 1_000_000.0),
+            Rate::Reset => None,
+        }
+    }
+
+    pub fn describe(self) -> String {
+        match self {
+            Rate::BitsPerSecond(bps) => format_rate(bps),
+            Rate::Reset => "counter reset".to_string(),
+        }
+    }
+}
+
+/// The rate between two readings of a byte counter `elapsed` apart.
+pub fn rate(before: u64, after: u64, elapsed: Duration) -> Rate {
+    if after < before {
+        return Rate::Reset;
+    }
+    let secs = elapsed.as_secs_f64();
+    if secs <= 0.0 {
+        return Rate::BitsPerSecond(0.0);
+    }
+    Rate::BitsPerSecond((after - before) as f64 * 8.0 / secs)
+}
+
+#[derive(Clone, Copy)]
+struct Counters {
+    received: u64,
+    sent: u64,
+}
+
+fn counters(data: &Value) -> Option<Counters> {
+    Some(Counters {
+        received: aliased_parse(data, RECEIVED_KEY)?,
+        sent: aliased_parse(data, SENT_KEY)?,
+    })
+}
+
+pub struct Throughput {
+    pub down: Rate,
+    pub up: Rate,
+    pub elapsed: Duration,
+}
+
+fn between(before: Counters, after: Counters, elapsed: Duration) -> Throughput {
+    Throughput {
+        down: rate(before.received, after.received, elapsed),
+        up: rate(before.sent, after.sent, elapsed),
+        elapsed,
+    }
+}
+
+async fn read_counters(client: &RouterClient) -> Result<(Counters, Instant), Box<dyn Error>> {
+    let data = client.api_request("get_index_data").await?;
+    let at = Instant::now();
+    let counters = counters(&data).ok_or("Router did not report its byte counters")?;
+    Ok((counters, at))
+}
+
+/// Reads the counters twice, `window` apart. The rate is over the time
+/// between the two answers, not the nominal window.
+pub async fn measure(
+    client: &RouterClient,
+    window: Duration,
+) -> Result<Throughput, Box<dyn Error>> {
+    let (before, started) = read_counters(client).await?;
+    tokio::time::sleep(window).await;
+    let (after, finished) = read_counters(client).await?;
+    Ok(between(before, after, finished - started))
+}
+
+pub async fn run(
+    client: &RouterClient,
+    window: Duration,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    if format == OutputFormat::Text {
+        eprintln!("Measuring for {}s...", window.as_secs());
+    }
+    let throughput = measure(client, window).await?;
+
+    match format {
+        OutputFormat::Json | OutputFormat::Raw => {
+            let result = json!({
+                "window_s": throughput.elapsed.as_secs_f64(),
+                "download_mbps": throughput.down.mbps(),
+                "upload_mbps": throughput.up.mbps(),
+                "download_reset": throughput.down == Rate::Reset,
+                "upload_reset": throughput.up == Rate::Reset,
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text => {
+            println!("Download: {}", throughput.down.describe());
+            println!("Upload:   {}", throughput.up.describe());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_are_bits_per_second() {
+        let rate = rate(1_000_000, 3_500_000, Duration::from_secs(2));
+        assert_eq!(rate, Rate::BitsPerSecond(10_000_000.0));
+        assert_eq!(rate.mbps(), Some(10.0));
+        assert_eq!(rate.describe(), "10.00 Mbps");
+    }
+
+    #[test]
+    fn shrinking_counters_are_resets() {
+        let rate = rate(5_000, 100, Duration::from_secs(5));
+        assert_eq!(rate, Rate::Reset);
+        assert_eq!(rate.mbps(), None);
+        assert_eq!(rate.describe(), "counter reset");
+    }
+}
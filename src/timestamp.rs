@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::{DateTime, Local, SecondsFormat, TimeZone, Utc};
+
+// Every timestamp the tool prints or logs goes through here so that --utc
+// switches all of them at once. Local time is the default.
+static USE_UTC: AtomicBool = AtomicBool::new(false);
+
+pub fn set_utc(utc: bool) {
+    USE_UTC.store(utc, Ordering::Relaxed);
+}
+
+fn format_with<Tz: TimeZone>(time: DateTime<Tz>, display: bool) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    if display {
+        time.format("%Y-%m-%d %H:%M:%S %:z").to_string()
+    } else {
+        time.to_rfc3339_opts(SecondsFormat::Secs, false)
+    }
+}
+
+// sorts in time order and is safe in a file name on every platform
+const FILE_NAME_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+fn now(display: bool) -> String {
+    if USE_UTC.load(Ordering::Relaxed) {
+        format_with(Utc::now(), display)
+    } else {
+        format_with(Local::now(), display)
+    }
+}
+
+/// ISO-8601 timestamp with offset, for logs and machine-readable output.
+pub fn now_iso() -> String {
+    now(false)
+}
+
+/// `time` as `now_iso` would have put it.
+pub fn iso(time: DateTime<Utc>) -> String {
+    if USE_UTC.load(Ordering::Relaxed) {
+        format_with(time, false)
+    } else {
+        format_with(time.with_timezone(&Local), false)
+    }
+}
+
+/// Human friendly timestamp for the screen, still carrying the offset.
+pub fn now_display() -> String {
+    now(true)
+}
+
+/// Millisecond timestamp for file names, e.g. `20261014-130000.123`.
+pub fn now_file_name() -> String {
+    if USE_UTC.load(Ordering::Relaxed) {
+        Utc::now().format(FILE_NAME_FORMAT).to_string()
+    } else {
+        Local::now().format(FILE_NAME_FORMAT).to_string()
+    }
+}
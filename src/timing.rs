@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// ---------- timing profile ----------
+#[derive(Default)]
+struct Entry {
+    calls: u32,
+    total: Duration,
+    max: Duration,
+}
+
+/// Time spent per API command, authentication and rendering, shared between
+/// the router client (which may live on the background task) and the UI loop.
+#[derive(Clone, Default)]
+pub struct Timings {
+    entries: Arc<Mutex<BTreeMap<String, Entry>>>,
+}
+
+impl Timings {
+    pub fn record(&self, name: &str, elapsed: Duration) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = entries.entry(name.to_string()).or_default();
+        entry.calls += 1;
+        entry.total += elapsed;
+        entry.max = entry.max.max(elapsed);
+    }
+
+    pub fn summary(&self) -> String {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = "Timing summary\n".to_string();
+        out.push_str(&format!(
+            "  {:24} {:>7} {:>11} {:>10} {:>10}\n",
+            "operation", "calls", "total", "avg", "max"
+        ));
+        for (name, entry) in entries.iter() {
+            let avg = entry.total / entry.calls.max(1);
+            out.push_str(&format!(
+                "  {:24} {:>7} {:>11} {:>10} {:>10}\n",
+                name,
+                entry.calls,
+                format_ms(entry.total),
+                format_ms(avg),
+                format_ms(entry.max)
+            ));
+        }
+        out
+    }
+
+    pub fn print_summary(&self) {
+        eprint!("{}", self.summary());
+    }
+}
+
+fn format_ms(d: Duration) -> String {
+    format!("{:.1} ms", d.as_secs_f64() * 1000.0)
+}
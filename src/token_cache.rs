@@ -0,0 +1,51 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+// ---------- session token cache ----------
+// One token per file, tagged with the router and user it belongs to so a
+// different --base-url or --username never picks up someone else's session.
+#[derive(Serialize, Deserialize)]
+struct CachedToken {
+    base_url: String,
+    username: String,
+    token: String,
+    /// Unix seconds.
+    saved_at: i64,
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("zitel").join("token.json"))
+}
+
+/// The cached token for this router and user, if it is younger than `ttl`.
+pub fn load(path: &Path, base_url: &str, username: &str, ttl: Duration) -> Option<String> {
+    let cached: CachedToken = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    let age = chrono::Utc::now().timestamp() - cached.saved_at;
+    let fresh = (0..ttl.as_secs() as i64).contains(&age);
+    (fresh && cached.base_url == base_url && cached.username == username).then_some(cached.token)
+}
+
+pub fn save(path: &Path, base_url: &str, username: &str, token: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let cached = CachedToken {
+        base_url: base_url.to_string(),
+        username: username.to_string(),
+        token: token.to_string(),
+        saved_at: chrono::Utc::now().timestamp(),
+    };
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // the token is as good as the password until it expires
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(path)?
+        .write_all(serde_json::to_string(&cached)?.as_bytes())
+}
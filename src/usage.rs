@@ -0,0 +1,129 @@
+use std::error::Error;
+
+use serde_json::{json, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::{is_success, RouterClient};
+use crate::dashboard::{format_bytes, RECEIVED_KEY, SENT_KEY};
+use crate::data_cap;
+use crate::error::ZitelError;
+use crate::fields::aliased_parse;
+use crate::prompt::confirm_change;
+
+// ---------- data usage ----------
+/// The router's byte counters since they were last reset; `total` is what
+/// a data cap counts.
+pub struct Usage {
+    pub received: Option<u64>,
+    pub sent: Option<u64>,
+}
+
+impl Usage {
+    pub fn read(data: &Value) -> Self {
+        Self {
+            received: aliased_parse(data, RECEIVED_KEY),
+            sent: aliased_parse(data, SENT_KEY),
+        }
+    }
+
+    /// Both counters added up, `None` unless the router reported both.
+    pub fn total(&self) -> Option<u64> {
+        Some(self.received?.saturating_add(self.sent?))
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "received_bytes": self.received,
+            "sent_bytes": self.sent,
+            "total_bytes": self.total(),
+        })
+    }
+
+    fn text(&self) -> String {
+        let bytes = |b: Option<u64>| b.map_or("-".to_string(), format_bytes);
+        format!(
+            "Received: {}\nSent:     {}\nTotal:    {}\n",
+            bytes(self.received),
+            bytes(self.sent),
+            bytes(self.total())
+        )
+    }
+}
+
+pub async fn run_show(client: &RouterClient, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let data = client.api_request("get_index_data").await?;
+    let usage = Usage::read(&data);
+    match format {
+        OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&data)?),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&usage.to_json())?),
+        OutputFormat::Text => print!("{}", usage.text()),
+    }
+    Ok(())
+}
+
+/// Zeroes the counters, then reads them back so the reset can be seen.
+pub async fn run_reset(client: &RouterClient, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    confirm_change(
+        client,
+        "Reset the data usage counters? The usage so far is lost",
+    )?;
+    let resp = client.reset_usage().await?;
+    if !is_success(&resp) {
+        let what = "Resetting the data usage failed (this firmware may not support it)";
+        return Err(ZitelError::refused(what, &resp).into());
+    }
+    let summary = client.outcome("Data usage counters reset");
+    // a deliberate reset starts a new data cap cycle
+    if let (false, Some(path)) = (client.dry_run(), data_cap::default_path()) {
+        if let Err(e) = data_cap::forget(&path, client.base_url()) {
+            log::warn!("Cannot update {}: {}", path.display(), e);
+        }
+    }
+    // nothing was reset in a dry run, so there is nothing to read back
+    let data = match client.dry_run() {
+        true => None,
+        false => Some(client.api_request("get_index_data").await?),
+    };
+
+    match format {
+        OutputFormat::Raw => {
+            let resp = data.as_ref().unwrap_or(&resp);
+            println!("{}", serde_json::to_string_pretty(resp)?);
+        }
+        OutputFormat::Json => {
+            let mut result = json!({
+                "success": true,
+                "dry_run": client.dry_run(),
+                "message": summary,
+            });
+            if let Some(data) = &data {
+                result["usage"] = Usage::read(data).to_json();
+            }
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Text => {
+            println!("{}", summary);
+            if let Some(data) = &data {
+                print!("{}", Usage::read(data).text());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn totals_need_both_counters() {
+        let usage = Usage::read(&json!({ "recieve": "1536", "sentt": 512 }));
+        assert_eq!(usage.total(), Some(2048));
+        assert!(
+            usage.text().ends_with("Total:    2.00 KB\n"),
+            "{}",
+            usage.text()
+        );
+        assert_eq!(Usage::read(&json!({ "recieve": "1536" })).total(), None);
+    }
+}
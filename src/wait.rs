@@ -0,0 +1,188 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::cli::OutputFormat;
+use crate::client::RouterClient;
+use crate::jitter;
+use crate::metrics::{self, Metric};
+use crate::timestamp;
+
+// ---------- wait for a signal level ----------
+// For pointing an antenna: poll until the metric crosses the target, so the
+// installer can watch (or listen for) one line instead of the dashboard.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Above(f64),
+    Below(f64),
+}
+
+impl Target {
+    fn met(self, value: f64) -> bool {
+        match self {
+            Target::Above(limit) => value > limit,
+            Target::Below(limit) => value < limit,
+        }
+    }
+
+    // how far the value still is from the target, never negative
+    fn distance(self, value: f64) -> f64 {
+        match self {
+            Target::Above(limit) => (limit - value).max(0.0),
+            Target::Below(limit) => (value - limit).max(0.0),
+        }
+    }
+
+    fn better(self, a: f64, b: f64) -> f64 {
+        match self {
+            Target::Above(_) => a.max(b),
+            Target::Below(_) => a.min(b),
+        }
+    }
+
+    fn describe(self, unit: &str) -> String {
+        match self {
+            Target::Above(limit) => format!("above {} {}", limit, unit),
+            Target::Below(limit) => format!("below {} {}", limit, unit),
+        }
+    }
+}
+
+pub struct WaitOptions {
+    pub metric: String,
+    pub target: Target,
+    pub interval: Duration,
+    pub max_wait: Duration,
+    /// Ring the terminal bell once the target is reached.
+    pub bell: bool,
+}
+
+fn progress_line(
+    time: &str,
+    metric: &Metric,
+    value: Option<f64>,
+    target: Target,
+    left: Duration,
+) -> String {
+    let status = match value {
+        Some(v) if target.met(v) => "reached".to_string(),
+        // a difference of dBm values is in dB
+        Some(v) => format!(
+            "{} {} to go",
+            target.distance(v),
+            metric.unit.trim_end_matches("m")
+        ),
+        None => "no reading".to_string(),
+    };
+    let value = value.map_or_else(|| "-".to_string(), |v| format!("{} {}", v, metric.unit));
+    format!(
+        "{}  {} {}  (target {}: {}, {}s left)",
+        time,
+        metric.label,
+        value,
+        target.describe(metric.unit),
+        status,
+        left.as_secs_f64().ceil()
+    )
+}
+
+pub async fn run(
+    client: &RouterClient,
+    options: WaitOptions,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let metric = metrics::find(&options.metric)
+        .ok_or_else(|| format!("Unknown metric {:?}", options.metric))?;
+    let target = options.target;
+    let started = Instant::now();
+    let mut best: Option<f64> = None;
+
+    loop {
+        let left = options.max_wait.saturating_sub(started.elapsed());
+        match client.api_request("get_index_data").await {
+            Ok(data) => {
+                let value = metric.value(&data);
+                let time = timestamp::now_iso();
+                match format {
+                    OutputFormat::Text => {
+                        println!("{}", progress_line(&time, metric, value, target, left))
+                    }
+                    OutputFormat::Json | OutputFormat::Raw => println!(
+                        "{}",
+                        json!({
+                            "time": time,
+                            "metric": metric.name,
+                            "value": value,
+                            "met": value.is_some_and(|v| target.met(v)),
+                        })
+                    ),
+                }
+                if let Some(value) = value {
+                    best = Some(best.map_or(value, |b| target.better(b, value)));
+                    if target.met(value) {
+                        if options.bell {
+                            eprint!("\x07");
+                            io::stderr().flush()?;
+                        }
+                        if format == OutputFormat::Text {
+                            println!(
+                                "{} reached {} {} after {}s",
+                                metric.label,
+                                value,
+                                metric.unit,
+                                started.elapsed().as_secs()
+                            );
+                        }
+                        return Ok(());
+                    }
+                }
+            }
+            Err(e) => eprintln!("Poll failed: {}", e),
+        }
+
+        if left.is_zero() {
+            let best = best.map_or_else(
+                || "no reading".to_string(),
+                |b| format!("best {} {}", b, metric.unit),
+            );
+            return Err(format!(
+                "{} did not get {} within {}s ({})",
+                metric.label,
+                target.describe(metric.unit),
+                options.max_wait.as_secs(),
+                best
+            )
+            .into());
+        }
+        tokio::time::sleep(jitter::apply(options.interval).min(left)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_lines_say_how_far_the_target_is() {
+        let rsrp = metrics::find("rsrp").unwrap();
+        let above = Target::Above(-85.0);
+        assert_eq!(
+            progress_line(
+                "12:00:00",
+                rsrp,
+                Some(-92.0),
+                above,
+                Duration::from_secs(288)
+            ),
+            "12:00:00  RSRP -92 dBm  (target above -85 dBm: 7 dB to go, 288s left)"
+        );
+        assert!(progress_line("t", rsrp, Some(-84.0), above, Duration::ZERO).contains("reached"));
+        assert!(progress_line("t", rsrp, None, above, Duration::ZERO).contains("no reading"));
+        // exactly at the limit is not past it
+        assert!(!above.met(-85.0));
+        assert!(Target::Below(10.0).met(9.5));
+        assert_eq!(Target::Below(10.0).better(12.0, 11.0), 11.0);
+    }
+}
@@ -0,0 +1,334 @@
+use std::error::Error;
+
+use serde_json::{json, Value};
+
+use crate::cli::OutputFormat;
+use crate::client::{is_success, RouterClient};
+use crate::dashboard::render_columns;
+use crate::error::ZitelError;
+use crate::fields::field;
+use crate::prompt::{ask_secret, confirm_change};
+use crate::terminal;
+
+// ---------- radios ----------
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Radio {
+    TwoFour,
+    Five,
+}
+
+// field specs per radio: enabled, SSID, security, channel
+struct RadioSpecs {
+    enabled: &'static str,
+    ssid: &'static str,
+    security: &'static str,
+    channel: &'static str,
+}
+
+impl Radio {
+    pub const ALL: [Radio; 2] = [Radio::TwoFour, Radio::Five];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Radio::TwoFour => "2.4 GHz",
+            Radio::Five => "5 GHz",
+        }
+    }
+
+    // the band argument of set_wifi
+    fn command_name(self) -> &'static str {
+        match self {
+            Radio::TwoFour => "2g",
+            Radio::Five => "5g",
+        }
+    }
+
+    fn specs(self) -> RadioSpecs {
+        match self {
+            Radio::TwoFour => RadioSpecs {
+                enabled: "wifi_2g_enable|wifi.enable_2g|wifi_enable",
+                ssid: "ssid_2g|wifi.ssid_2g|SSID|ssid",
+                security: "auth_2g|wifi.auth_2g|security|encryption",
+                channel: "channel_2g|wifi.channel_2g|channel",
+            },
+            Radio::Five => RadioSpecs {
+                enabled: "wifi_5g_enable|wifi.enable_5g",
+                ssid: "ssid_5g|wifi.ssid_5g|SSID5|ssid5",
+                security: "auth_5g|wifi.auth_5g|security5",
+                channel: "channel_5g|wifi.channel_5g|channel5",
+            },
+        }
+    }
+}
+
+// ---------- status ----------
+pub struct WifiNetwork {
+    pub radio: Radio,
+    pub enabled: Option<bool>,
+    pub ssid: Option<String>,
+    pub security: Option<String>,
+    pub channel: Option<String>,
+}
+
+impl WifiNetwork {
+    fn read(data: &Value, radio: Radio) -> Self {
+        let specs = radio.specs();
+        let value = |spec| field(data, spec).map(String::from);
+        Self {
+            radio,
+            enabled: field(data, specs.enabled).map(|v| {
+                matches!(
+                    v.trim().to_lowercase().as_str(),
+                    "1" | "true" | "on" | "enabled"
+                )
+            }),
+            ssid: value(specs.ssid),
+            security: value(specs.security),
+            channel: value(specs.channel),
+        }
+    }
+
+    fn reported(&self) -> bool {
+        self.enabled.is_some() || self.ssid.is_some()
+    }
+
+    fn status_text(&self) -> &'static str {
+        match self.enabled {
+            Some(true) => "on",
+            Some(false) => "off",
+            None => "-",
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "band": self.radio.label(),
+            "enabled": self.enabled,
+            "ssid": self.ssid,
+            "security": self.security,
+            "channel": self.channel,
+        })
+    }
+}
+
+/// The radios the router reports anything about, 2.4 GHz first.
+pub fn parse_wifi_status(data: &Value) -> Vec<WifiNetwork> {
+    Radio::ALL
+        .iter()
+        .map(|&radio| WifiNetwork::read(data, radio))
+        .filter(WifiNetwork::reported)
+        .collect()
+}
+
+pub const HEADERS: [&str; 5] = ["Band", "Status", "SSID", "Security", "Channel"];
+
+fn network_rows(networks: &[WifiNetwork]) -> Vec<Vec<String>> {
+    networks
+        .iter()
+        .map(|n| {
+            let dash = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+            vec![
+                n.radio.label().to_string(),
+                n.status_text().to_string(),
+                dash(&n.ssid),
+                dash(&n.security),
+                dash(&n.channel),
+            ]
+        })
+        .collect()
+}
+
+// ---------- validation ----------
+/// 1 to 32 bytes. The router's commands are split on spaces, so an SSID
+/// cannot contain any.
+pub fn validate_ssid(ssid: &str) -> Result<String, String> {
+    if ssid.is_empty() || ssid.len() > 32 {
+        return Err(format!("SSID must be 1 to 32 bytes, got {}", ssid.len()));
+    }
+    if ssid.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(format!(
+            "SSID {:?} contains spaces or control characters, which this router's API cannot take",
+            ssid
+        ));
+    }
+    Ok(ssid.to_string())
+}
+
+/// A WPA2 passphrase: 8 to 63 printable ASCII characters.
+pub fn validate_passphrase(passphrase: &str) -> Result<String, String> {
+    if !(8..=63).contains(&passphrase.len()) {
+        return Err(format!(
+            "WPA2 passphrase must be 8 to 63 characters, got {}",
+            passphrase.chars().count()
+        ));
+    }
+    if !passphrase.chars().all(|c| c == ' ' || c.is_ascii_graphic()) {
+        return Err("WPA2 passphrase may only contain printable ASCII characters".to_string());
+    }
+    Ok(passphrase.to_string())
+}
+
+/// The set_wifi command for one radio; the passphrase goes last since the
+/// router takes the rest of the command as its value.
+pub fn set_command(radio: Radio, ssid: &str, passphrase: &str) -> Result<String, ZitelError> {
+    let ssid = validate_ssid(ssid).map_err(ZitelError::Input)?;
+    let passphrase = validate_passphrase(passphrase).map_err(ZitelError::Input)?;
+    Ok(format!(
+        "set_wifi {} {} {}",
+        radio.command_name(),
+        ssid,
+        passphrase
+    ))
+}
+
+// ---------- one-shot commands ----------
+pub async fn run_status(client: &RouterClient, format: OutputFormat) -> Result<(), Box<dyn Error>> {
+    let resp = client.wifi_status().await?;
+    let networks = parse_wifi_status(&resp);
+    match format {
+        OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(&resp)?),
+        OutputFormat::Json => {
+            let list: Vec<Value> = networks.iter().map(WifiNetwork::to_json).collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&json!({ "networks": list }))?
+            );
+        }
+        OutputFormat::Text if networks.is_empty() => {
+            println!("Router did not report any WiFi network")
+        }
+        OutputFormat::Text => print!(
+            "{}",
+            render_columns(
+                "WiFi",
+                &HEADERS,
+                &network_rows(&networks),
+                terminal::width()
+            )
+        ),
+    }
+    Ok(())
+}
+
+/// Sets the SSID and passphrase of `radios`. Without `ssid` each radio
+/// keeps its current one; without `passphrase` it is asked for.
+pub async fn run_set(
+    client: &RouterClient,
+    radios: &[Radio],
+    ssid: Option<String>,
+    passphrase: Option<String>,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let passphrase = match passphrase {
+        Some(passphrase) => passphrase,
+        None => ask_secret("New WiFi passphrase")?,
+    };
+    validate_passphrase(&passphrase).map_err(ZitelError::Input)?;
+    if let Some(ssid) = &ssid {
+        validate_ssid(ssid).map_err(ZitelError::Input)?;
+    }
+
+    let current = match &ssid {
+        Some(_) => Vec::new(),
+        None => parse_wifi_status(&client.wifi_status().await?),
+    };
+    let mut changes = Vec::new();
+    for &radio in radios {
+        let ssid = match &ssid {
+            Some(ssid) => ssid.clone(),
+            None => current
+                .iter()
+                .find(|n| n.radio == radio)
+                .and_then(|n| n.ssid.clone())
+                .ok_or_else(|| {
+                    format!(
+                        "Router did not report the {} SSID, pass --ssid",
+                        radio.label()
+                    )
+                })?,
+        };
+        changes.push((radio, ssid));
+    }
+
+    let bands: Vec<&str> = changes.iter().map(|(radio, _)| radio.label()).collect();
+    confirm_change(
+        client,
+        &format!(
+            "Change the {} WiFi? Clients on it will be disconnected",
+            bands.join(" and ")
+        ),
+    )?;
+
+    let mut results = Vec::new();
+    for (radio, ssid) in &changes {
+        let resp = client.set_wifi(*radio, ssid, &passphrase).await?;
+        if !is_success(&resp) {
+            let what = format!("Changing the {} WiFi failed", radio.label());
+            return Err(ZitelError::refused(&what, &resp).into());
+        }
+        let summary = client.outcome(&format!("{} WiFi set to SSID {}", radio.label(), ssid));
+        results.push((*radio, ssid, resp, summary));
+    }
+
+    for (radio, ssid, resp, summary) in &results {
+        match format {
+            OutputFormat::Raw => println!("{}", serde_json::to_string_pretty(resp)?),
+            OutputFormat::Json => {
+                let result = json!({
+                    "success": true,
+                    "dry_run": client.dry_run(),
+                    "band": radio.label(),
+                    "ssid": ssid,
+                });
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            }
+            OutputFormat::Text => println!("{}", summary),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_both_radios() {
+        let data = json!({
+            "wifi_2g_enable": "1", "ssid_2g": "Home", "auth_2g": "WPA2-PSK", "channel_2g": "6",
+            "wifi_5g_enable": "0", "ssid_5g": "Home-5G",
+        });
+        let networks = parse_wifi_status(&data);
+        assert_eq!(networks.len(), 2);
+        assert_eq!(
+            network_rows(&networks),
+            vec![
+                vec!["2.4 GHz", "on", "Home", "WPA2-PSK", "6"],
+                vec!["5 GHz", "off", "Home-5G", "-", "-"],
+            ]
+        );
+        assert!(parse_wifi_status(&json!({ "status": "ok" })).is_empty());
+    }
+
+    #[test]
+    fn passphrases_follow_wpa2_limits() {
+        assert!(validate_passphrase("1234567").is_err());
+        assert!(validate_passphrase("12345678").is_ok());
+        assert!(validate_passphrase(&"a".repeat(63)).is_ok());
+        assert!(validate_passphrase(&"a".repeat(64)).is_err());
+        assert!(validate_passphrase("pass word ok").is_ok());
+        assert!(validate_passphrase("pässwörter").is_err());
+    }
+
+    #[test]
+    fn ssids_cannot_split_the_command() {
+        assert!(validate_ssid("Home-5G").is_ok());
+        assert!(validate_ssid("My Home").is_err());
+        assert!(validate_ssid("").is_err());
+        assert!(validate_ssid(&"x".repeat(33)).is_err());
+        assert_eq!(
+            set_command(Radio::Five, "Home-5G", "secret pass").unwrap(),
+            "set_wifi 5g Home-5G secret pass"
+        );
+    }
+}